@@ -0,0 +1,52 @@
+//! Build script: embeds the git commit and build time shown by `--version`
+
+use std::process::Command;
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+fn main() {
+    println!("cargo:rustc-env=GMAIL_MCP_GIT_SHA={}", git_sha().unwrap_or_else(|| "unknown".to_string()));
+    println!("cargo:rustc-env=GMAIL_MCP_BUILD_DATE={}", build_date());
+
+    // Rebuild when HEAD moves, and honor reproducible-build timestamps
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    if let Ok(head) = std::fs::read_to_string(".git/HEAD") {
+        if let Some(reference) = head.trim().strip_prefix("ref: ") {
+            println!("cargo:rerun-if-changed=.git/{}", reference);
+        }
+    }
+}
+
+/// Short hash of the checked-out commit, marked `-dirty` when the tree has local changes
+fn git_sha() -> Option<String> {
+    let output = Command::new("git").args(["rev-parse", "--short=12", "HEAD"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let sha = String::from_utf8(output.stdout).ok()?.trim().to_string();
+
+    let dirty = Command::new("git")
+        .args(["status", "--porcelain", "--untracked-files=no"])
+        .output()
+        .map(|o| o.status.success() && !o.stdout.is_empty())
+        .unwrap_or(false);
+
+    Some(if dirty { format!("{}-dirty", sha) } else { sha })
+}
+
+/// Build time as RFC 3339 UTC, taken from `SOURCE_DATE_EPOCH` when set
+fn build_date() -> String {
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .and_then(|secs| OffsetDateTime::from_unix_timestamp(secs).ok())
+        .unwrap_or_else(OffsetDateTime::now_utc);
+
+    timestamp
+        .replace_nanosecond(0)
+        .unwrap_or(timestamp)
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| "unknown".to_string())
+}
@@ -2,9 +2,14 @@
 //!
 //! Handles paths, environment variables, and configuration loading.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::error::{ConfigError, GmailMcpError, Result};
+use crate::gmail::client::{
+    DEFAULT_ATTACHMENT_RETRIES, DEFAULT_SEARCH_RESULTS, MAX_ATTACHMENT_RETRIES, MAX_SEARCH_RESULTS,
+};
+use crate::gmail::utils::Signature;
+use crate::mcp::access::ToolAccess;
 
 /// Configuration for the Gmail MCP Server
 #[derive(Debug, Clone)]
@@ -18,16 +23,95 @@ pub struct Config {
     /// Path to stored credentials (access/refresh tokens)
     pub credentials_path: PathBuf,
 
+    /// Stored credentials JSON given directly (`GMAIL_CREDENTIALS_JSON`)
+    ///
+    /// When set, tokens are read from here instead of `credentials_path` and
+    /// refreshed tokens are kept in memory only.
+    pub credentials_json: Option<String>,
+
+    /// Path to named search queries saved with `save_query`
+    pub saved_queries_path: PathBuf,
+
+    /// Path to the marker `get_new_messages` advances on each call
+    pub last_seen_path: PathBuf,
+
+    /// Mailbox to operate on (`GMAIL_USER_ID`); `me` is the authenticated user
+    pub user_id: String,
+
+    /// Path to recipient group aliases
+    pub aliases_path: PathBuf,
+
     /// OAuth callback URL
     pub oauth_callback_url: String,
 
     /// OAuth callback port
     pub oauth_callback_port: u16,
 
-    /// Gmail API scopes
+    /// Gmail API scopes requested at `auth` time; `GMAIL_FULL_ACCESS` adds
+    /// `https://mail.google.com/` for permanent deletion
     pub scopes: Vec<String>,
+
+    /// Largest JSON-RPC message accepted on stdin, in bytes
+    pub max_message_bytes: usize,
+
+    /// Longest tool result text returned, in characters; 0 disables the cap
+    pub max_result_chars: usize,
+
+    /// Mask addresses and truncate content in logged errors (`GMAIL_LOG_REDACT`)
+    pub redact_logs: bool,
+
+    /// Search results returned when `maxResults` is omitted (`GMAIL_DEFAULT_MAX_RESULTS`)
+    pub default_max_results: u32,
+
+    /// Client-side limit on Gmail quota units per second; 0 disables it
+    pub rate_limit: u64,
+
+    /// Times a failed attachment fetch is retried (`GMAIL_ATTACHMENT_RETRIES`)
+    pub attachment_retries: u32,
+
+    /// Signature appended to outgoing mail (`GMAIL_SIGNATURE` / `GMAIL_SIGNATURE_HTML`)
+    pub signature: Option<Signature>,
+
+    /// Display name put in front of the sender address (`GMAIL_FROM_NAME`)
+    pub from_name: Option<String>,
+
+    /// Messages a bulk operation may touch without `confirm: true` (`GMAIL_CONFIRM_THRESHOLD`)
+    pub confirm_threshold: usize,
+
+    /// Tools exposed to the client (`GMAIL_ENABLED_TOOLS`, `GMAIL_DISABLED_TOOLS`, `GMAIL_READONLY`)
+    pub tool_access: ToolAccess,
+}
+
+/// Default for `Config::max_message_bytes`: large enough for a raw message with attachments
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 32 * 1024 * 1024;
+
+/// Default for `Config::max_result_chars`: far above any ordinary tool result
+pub const DEFAULT_MAX_RESULT_CHARS: usize = 500_000;
+
+/// Scopes to request: `gmail.modify` and `gmail.settings.basic`, plus full
+/// access when the operator opts in to permanent deletion
+pub fn oauth_scopes(full_access: bool) -> Vec<String> {
+    let mut scopes = vec![
+        gmail::scopes::MODIFY.to_string(),
+        gmail::scopes::SETTINGS_BASIC.to_string(),
+    ];
+    if full_access {
+        scopes.push(gmail::scopes::FULL_ACCESS.to_string());
+    }
+    scopes
+}
+
+/// Parse an opt-in boolean setting; only an explicit "on" enables it
+pub fn flag_enabled(value: Option<&str>) -> bool {
+    matches!(
+        value.map(|v| v.trim().to_ascii_lowercase()).as_deref(),
+        Some("1" | "true" | "on" | "yes")
+    )
 }
 
+/// Default for `Config::confirm_threshold`
+pub const DEFAULT_CONFIRM_THRESHOLD: usize = 100;
+
 impl Config {
     /// Create a new configuration with default paths
     pub fn new() -> Result<Self> {
@@ -41,6 +125,23 @@ impl Config {
             .map(PathBuf::from)
             .unwrap_or_else(|_| config_dir.join("credentials.json"));
 
+        let credentials_json = std::env::var("GMAIL_CREDENTIALS_JSON")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+
+        let saved_queries_path = config_dir.join("saved_queries.json");
+        let last_seen_path = config_dir.join("last_seen.json");
+
+        let user_id = std::env::var("GMAIL_USER_ID")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| gmail::USER_ID.to_string());
+
+        let aliases_path = std::env::var("GMAIL_ALIASES_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| config_dir.join("aliases.toml"));
+
         let oauth_callback_port = std::env::var("GMAIL_OAUTH_PORT")
             .ok()
             .and_then(|p| p.parse().ok())
@@ -48,16 +149,72 @@ impl Config {
 
         let oauth_callback_url = format!("http://localhost:{}/oauth2callback", oauth_callback_port);
 
+        let max_message_bytes = std::env::var("GMAIL_MAX_MESSAGE_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_MESSAGE_BYTES);
+
+        let max_result_chars = std::env::var("GMAIL_MAX_RESULT_CHARS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_RESULT_CHARS);
+
+        let confirm_threshold = std::env::var("GMAIL_CONFIRM_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CONFIRM_THRESHOLD);
+
+        let default_max_results = std::env::var("GMAIL_DEFAULT_MAX_RESULTS")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|&n| n > 0)
+            .map_or(DEFAULT_SEARCH_RESULTS, |n| n.min(MAX_SEARCH_RESULTS));
+
+        let rate_limit = std::env::var("GMAIL_RATE_LIMIT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(crate::gmail::quota::USER_UNITS_PER_SECOND);
+
+        let attachment_retries = std::env::var("GMAIL_ATTACHMENT_RETRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map_or(DEFAULT_ATTACHMENT_RETRIES, |n: u32| n.min(MAX_ATTACHMENT_RETRIES));
+
+        let redact_logs =
+            crate::redact::redaction_enabled(std::env::var("GMAIL_LOG_REDACT").ok().as_deref());
+
+        let signature = Signature::new(
+            std::env::var("GMAIL_SIGNATURE").ok(),
+            std::env::var("GMAIL_SIGNATURE_HTML").ok(),
+        );
+
+        let from_name = std::env::var("GMAIL_FROM_NAME")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+
         Ok(Self {
             config_dir,
             oauth_path,
             credentials_path,
+            credentials_json,
+            saved_queries_path,
+            last_seen_path,
+            user_id,
+            aliases_path,
             oauth_callback_url,
             oauth_callback_port,
-            scopes: vec![
-                "https://www.googleapis.com/auth/gmail.modify".to_string(),
-                "https://www.googleapis.com/auth/gmail.settings.basic".to_string(),
-            ],
+            scopes: oauth_scopes(flag_enabled(std::env::var("GMAIL_FULL_ACCESS").ok().as_deref())),
+            max_message_bytes,
+            max_result_chars,
+            default_max_results,
+            redact_logs,
+            rate_limit,
+            attachment_retries,
+            signature,
+            from_name,
+            confirm_threshold,
+            tool_access: ToolAccess::from_env(),
         })
     }
 
@@ -80,6 +237,11 @@ impl Config {
             })?;
         }
 
+        // Tokens and client secrets live here, so keep it private to the owner
+        if let Err(e) = restrict_permissions(&config_dir, 0o700) {
+            tracing::warn!("Could not restrict permissions on {}: {}", config_dir.display(), e);
+        }
+
         Ok(config_dir)
     }
 
@@ -101,11 +263,26 @@ impl Config {
 
         if local_oauth.exists() && !self.oauth_keys_exist() {
             std::fs::copy(&local_oauth, &self.oauth_path).map_err(GmailMcpError::Io)?;
+            restrict_permissions(&self.oauth_path, 0o600).map_err(GmailMcpError::Io)?;
             return Ok(true);
         }
 
         Ok(false)
     }
+
+    /// Tighten existing OAuth keys and credentials files to owner-only access
+    ///
+    /// Files written by older versions (or copied in by hand) may still be
+    /// group/world readable.
+    pub fn restrict_secret_files(&self) {
+        for path in [&self.oauth_path, &self.credentials_path] {
+            if path.exists() {
+                if let Err(e) = restrict_permissions(path, 0o600) {
+                    tracing::warn!("Could not restrict permissions on {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
 }
 
 impl Default for Config {
@@ -114,14 +291,78 @@ impl Default for Config {
     }
 }
 
+/// Set Unix permission bits on `path`
+///
+/// Windows has no equivalent mode bits; files there inherit the ACL of the
+/// user profile directory, so this is a no-op.
+pub fn restrict_permissions(path: &Path, mode: u32) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+
+    Ok(())
+}
+
+/// Atomically replace `path` with `contents`, readable only by the owner
+///
+/// The data goes to a temp file in the same directory and is renamed over the
+/// target, so a crash mid-write never leaves a truncated file behind. On Unix
+/// the file is created with mode 0600.
+pub async fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp_path = dir.join(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4().simple()));
+
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let write = async {
+        let mut file = options.open(&tmp_path).await?;
+        file.write_all(contents).await?;
+        file.sync_all().await?;
+        drop(file);
+        tokio::fs::rename(&tmp_path, path).await
+    };
+
+    if let Err(e) = write.await {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(e);
+    }
+
+    Ok(())
+}
+
 /// Gmail API constants
 pub mod gmail {
     /// Base URL for Gmail API
     pub const API_BASE_URL: &str = "https://gmail.googleapis.com/gmail/v1";
 
-    /// User ID for the authenticated user
+    /// Default user ID: the authenticated user's own mailbox
     pub const USER_ID: &str = "me";
 
+    /// OAuth scopes used by the server
+    pub mod scopes {
+        /// Read, send and modify messages (no permanent deletion)
+        pub const MODIFY: &str = "https://www.googleapis.com/auth/gmail.modify";
+
+        /// Manage filters and basic settings
+        pub const SETTINGS_BASIC: &str = "https://www.googleapis.com/auth/gmail.settings.basic";
+
+        /// Full mailbox access, required for permanent deletion
+        pub const FULL_ACCESS: &str = "https://mail.google.com/";
+    }
+
     /// System label IDs (kept for reference/documentation)
     #[allow(dead_code)]
     pub mod labels {
@@ -152,5 +393,58 @@ mod tests {
         assert_eq!(config.scopes.len(), 2);
         assert!(config.scopes[0].contains("gmail.modify"));
     }
+
+    #[test]
+    fn test_full_access_scope_is_opt_in() {
+        assert!(!oauth_scopes(false).iter().any(|s| s == gmail::scopes::FULL_ACCESS));
+        assert_eq!(oauth_scopes(true).last().map(String::as_str), Some(gmail::scopes::FULL_ACCESS));
+
+        assert!(flag_enabled(Some(" 1")));
+        assert!(flag_enabled(Some("TRUE")));
+        assert!(!flag_enabled(Some("0")));
+        assert!(!flag_enabled(None));
+    }
+
+    #[tokio::test]
+    async fn test_write_private_file_replaces_contents() {
+        let dir = std::env::temp_dir().join(format!("gmail-mcp-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("credentials.json");
+
+        std::fs::write(&path, "old contents that are longer").unwrap();
+        write_private_file(&path, b"new").await.unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o077, 0, "credentials must not be group/world accessible");
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restrict_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("gmail-mcp-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("gcp-oauth.keys.json");
+        std::fs::write(&path, "{}").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        restrict_permissions(&path, 0o600).unwrap();
+        restrict_permissions(&dir, 0o700).unwrap();
+
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(std::fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
 
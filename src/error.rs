@@ -49,12 +49,18 @@ pub enum AuthError {
     #[error("Invalid OAuth keys format: expected 'installed' or 'web' credentials")]
     InvalidKeysFormat,
 
-    #[error("Credentials file not found: {path}")]
+    #[error("Credentials not found or no longer valid: {path}. Run 'gmail-mcp-server auth' to sign in again")]
     CredentialsNotFound { path: String },
 
     #[error("Failed to refresh access token: {message}")]
     TokenRefreshFailed { message: String },
 
+    #[error("Refresh token was revoked or has expired (stored credentials removed). Run 'gmail-mcp-server auth' to sign in again")]
+    RefreshTokenRevoked,
+
+    #[error("No registered redirect URI matches {configured} (registered: {registered}). Add it to the OAuth client's authorized redirect URIs, or register a localhost URI")]
+    RedirectUriMismatch { configured: String, registered: String },
+
     #[error("OAuth callback error: {message}")]
     CallbackError { message: String },
 
@@ -96,13 +102,31 @@ pub enum GmailApiError {
     #[error("API request failed: {message}")]
     RequestFailed { message: String },
 
+    #[error("Access token rejected (401): {message}")]
+    Unauthorized { message: String },
+
+    #[error("Message too large: {size} bytes exceeds Gmail's limit of {limit} bytes")]
+    MessageTooLarge { size: usize, limit: usize },
+
     #[error("Rate limited: retry after {retry_after_secs} seconds")]
     RateLimited { retry_after_secs: u64 },
 
-    #[error("Insufficient permissions: {scope}")]
+    #[error("Gmail temporarily unavailable: {message}")]
+    Unavailable { message: String },
+
+    #[error("Insufficient permissions: the stored token lacks the {scope} scope. {}", grant_hint(.scope))]
     InsufficientPermissions { scope: String },
 }
 
+/// How to get a missing scope granted; full access is only requested on opt-in
+fn grant_hint(scope: &str) -> &'static str {
+    if scope == crate::config::gmail::scopes::FULL_ACCESS {
+        "Set GMAIL_FULL_ACCESS=1 and re-run 'gmail-mcp-server auth' to grant it"
+    } else {
+        "Re-run 'gmail-mcp-server auth' to grant it"
+    }
+}
+
 /// Configuration errors
 #[derive(Error, Debug)]
 #[allow(dead_code)] // Some variants reserved for future use
@@ -138,6 +162,9 @@ pub enum ValidationError {
 
     #[error("Invalid MIME type: {mime_type}")]
     InvalidMimeType { mime_type: String },
+
+    #[error("Unknown recipient '{name}': not an email address or a group in aliases.toml")]
+    UnknownRecipient { name: String },
 }
 
 /// MCP protocol errors
@@ -147,6 +174,9 @@ pub enum McpError {
     #[error("Unknown tool: {name}")]
     UnknownTool { name: String },
 
+    #[error("Unknown prompt: {name}")]
+    UnknownPrompt { name: String },
+
     #[error("Invalid tool arguments: {message}")]
     InvalidArguments { message: String },
 
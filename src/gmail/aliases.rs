@@ -0,0 +1,148 @@
+//! Recipient aliases
+//!
+//! Maps group names to recipient lists so a send can address "marketing"
+//! instead of spelling out every address. Groups live in `aliases.toml` in the
+//! config directory, one key per group:
+//!
+//! ```toml
+//! marketing = ["alice@example.com", "bob@example.com"]
+//! boss = "carol@example.com"
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::{ConfigError, GmailMcpError, Result, ValidationError};
+use crate::gmail::utils::validate_email;
+
+/// A group's members, written as a single address or a list
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Members {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Group names mapped to their recipients
+#[derive(Debug, Clone, Default)]
+pub struct RecipientAliases {
+    /// Keyed by lowercased group name
+    groups: HashMap<String, Vec<String>>,
+}
+
+impl RecipientAliases {
+    /// Load aliases from `path`; a missing file means no aliases
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents).map_err(|e| {
+                GmailMcpError::Config(ConfigError::InvalidConfig {
+                    message: format!("{}: {}", path.display(), e),
+                })
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(GmailMcpError::Io(e)),
+        }
+    }
+
+    /// Parse the contents of an aliases file
+    pub fn parse(contents: &str) -> std::result::Result<Self, String> {
+        let raw: HashMap<String, Members> = toml::from_str(contents).map_err(|e| e.to_string())?;
+
+        let mut groups = HashMap::new();
+        for (name, members) in raw {
+            let members = match members {
+                Members::One(address) => vec![address],
+                Members::Many(addresses) => addresses,
+            };
+            if let Some(bad) = members.iter().find(|m| !validate_email(m)) {
+                return Err(format!("group '{}' has an invalid address: {}", name, bad));
+            }
+            groups.insert(name.to_lowercase(), members);
+        }
+
+        Ok(Self { groups })
+    }
+
+    /// Number of groups defined
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Whether no groups are defined
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Expand group names in `recipients` into their members
+    ///
+    /// Entries containing `@` are kept as given (and validated later with the
+    /// rest of the message); anything else must name a group. Group names are
+    /// case-insensitive, and an address reached twice is only kept once.
+    pub fn resolve(&self, recipients: Vec<String>) -> Result<Vec<String>> {
+        let mut resolved: Vec<String> = Vec::with_capacity(recipients.len());
+        let mut push = |address: String| {
+            if !resolved.iter().any(|r| r.eq_ignore_ascii_case(&address)) {
+                resolved.push(address);
+            }
+        };
+
+        for recipient in recipients {
+            if recipient.contains('@') {
+                push(recipient);
+                continue;
+            }
+
+            let members = self.groups.get(&recipient.trim().to_lowercase()).ok_or_else(|| {
+                GmailMcpError::Validation(ValidationError::UnknownRecipient {
+                    name: recipient.clone(),
+                })
+            })?;
+            for member in members {
+                push(member.clone());
+            }
+        }
+
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALIASES: &str = r#"
+        Marketing = ["alice@example.com", "bob@example.com"]
+        boss = "carol@example.com"
+    "#;
+
+    #[test]
+    fn test_resolve_expands_groups() {
+        let aliases = RecipientAliases::parse(ALIASES).unwrap();
+        assert_eq!(aliases.len(), 2);
+
+        let resolved = aliases
+            .resolve(vec![
+                "marketing".to_string(),
+                "Bob@example.com".to_string(),
+                "Boss".to_string(),
+            ])
+            .unwrap();
+        assert_eq!(resolved, vec!["alice@example.com", "bob@example.com", "carol@example.com"]);
+    }
+
+    #[test]
+    fn test_resolve_rejects_unknown_names() {
+        let aliases = RecipientAliases::parse(ALIASES).unwrap();
+        let err = aliases.resolve(vec!["sales team".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("Unknown recipient 'sales team'"));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_members() {
+        let err = RecipientAliases::parse(r#"team = ["alice@example.com", "bob"]"#).unwrap_err();
+        assert!(err.contains("group 'team'"));
+        assert!(RecipientAliases::parse("team = 3").is_err());
+    }
+}
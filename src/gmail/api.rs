@@ -0,0 +1,437 @@
+//! Gmail operations used by the MCP layer
+//!
+//! `GmailApi` is implemented by `GmailClient`, which talks to the Gmail REST
+//! API, and by `MockGmailClient`, which keeps an in-memory mailbox for tests
+//! and `--test-mode`. The tool handler and server only see the trait.
+
+use std::path::Path;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::error::Result;
+use crate::gmail::client::{
+    BatchControl, BatchOperationResult, GmailClient, HistoryChanges, ImportOptions, MessageSummary,
+    ReadMessageResult, ReplyContext, SearchMessageResult, SearchSnippetResult, SendEmailResult,
+};
+use crate::gmail::filters::FilterListResult;
+use crate::gmail::labels::LabelListResult;
+use crate::gmail::quota::QuotaUsage;
+use crate::gmail::types::*;
+use crate::gmail::utils::{self, EmailParams, UnsubscribeInfo};
+
+/// Mailbox operations behind the MCP tools and resources
+///
+/// Covers every public async `GmailClient` method, with the same names and
+/// signatures, so callers can hold an `Arc<dyn GmailApi>` and never depend on
+/// the HTTP client.
+#[async_trait]
+pub trait GmailApi: Send + Sync {
+    /// Estimated Gmail quota units used so far
+    fn quota_usage(&self) -> QuotaUsage;
+
+    /// Fail early if the stored token was not granted `required`
+    async fn check_scope(&self, required: &str) -> Result<()>;
+
+    /// Get the authenticated user's mailbox profile
+    async fn get_profile(&self) -> Result<Profile>;
+
+    /// The authenticated user's email address
+    async fn sender_email(&self) -> Result<&str>;
+
+    /// Send an email
+    async fn send_email(&self, params: EmailParams) -> Result<SendEmailResult>;
+
+    /// Create a draft
+    async fn create_draft(&self, params: EmailParams) -> Result<Draft>;
+
+    /// Import a raw RFC822 message as if it had been received
+    async fn import_message(&self, raw: &[u8], options: &ImportOptions) -> Result<Message>;
+
+    /// Insert a raw RFC822 message without scanning or filtering it
+    async fn insert_message(&self, raw: &[u8], label_ids: &[String]) -> Result<Message>;
+
+    /// Get a message by ID, with its full payload
+    async fn get_message(&self, message_id: &str) -> Result<Message>;
+
+    /// Get only the named headers of a message, in the order they appear
+    async fn get_message_headers(&self, message_id: &str, names: &[String]) -> Result<Vec<Header>>;
+
+    /// Label IDs currently on a message
+    async fn get_label_ids(&self, message_id: &str) -> Result<Vec<String>>;
+
+    /// Headers, labels and snippet of a message, without its body
+    async fn get_message_summary(&self, message_id: &str) -> Result<MessageSummary>;
+
+    /// Get a message with parsed content
+    async fn read_message(&self, message_id: &str) -> Result<ReadMessageResult>;
+
+    /// Get the unsubscribe targets advertised by a message
+    async fn get_unsubscribe_info(&self, message_id: &str) -> Result<UnsubscribeInfo>;
+
+    /// Perform an RFC 8058 one-click unsubscribe
+    async fn unsubscribe_one_click(&self, url: &str) -> Result<()>;
+
+    /// Download an `http(s)://` URL to attach to an outgoing message
+    async fn download_url_attachment(&self, url: &str) -> Result<utils::AttachmentData>;
+
+    /// Collect the threading headers needed to reply to a message
+    async fn reply_context(&self, message_id: &str) -> Result<ReplyContext>;
+
+    /// Threading headers for replying to the latest message of a thread
+    async fn thread_reply_context(&self, thread_id: &str) -> Result<ReplyContext>;
+
+    /// Messages added to `label_id` since `start_history_id`; `None` if that history is gone
+    async fn list_history(&self, start_history_id: &str, label_id: &str) -> Result<Option<HistoryChanges>>;
+
+    /// Read every message in a thread, oldest first
+    async fn read_thread(&self, thread_id: &str) -> Result<Vec<ReadMessageResult>>;
+
+    /// List a message's attachments without extracting its body
+    async fn list_attachments(&self, message_id: &str) -> Result<Vec<EmailAttachment>>;
+
+    /// Search for messages
+    async fn search_messages(&self, query: &str, max_results: Option<u32>) -> Result<Vec<SearchMessageResult>>;
+
+    /// Search for messages, returning only IDs, dates and snippets
+    async fn search_message_snippets(
+        &self,
+        query: &str,
+        max_results: Option<u32>,
+    ) -> Result<Vec<SearchSnippetResult>>;
+
+    /// Modify message labels
+    async fn modify_message(
+        &self,
+        message_id: &str,
+        add_label_ids: Option<Vec<String>>,
+        remove_label_ids: Option<Vec<String>>,
+    ) -> Result<Message>;
+
+    /// Move a message to trash
+    async fn delete_message(&self, message_id: &str) -> Result<()>;
+
+    /// List the IDs of at most `limit` messages matching a query
+    async fn list_message_ids(&self, query: &str, limit: usize) -> Result<Vec<String>>;
+
+    /// List the IDs of every message matching a query
+    async fn list_all_message_ids(&self, query: &str) -> Result<Vec<String>>;
+
+    /// Permanently delete messages, reporting the outcome of each chunk
+    async fn batch_delete_messages_permanently(
+        &self,
+        message_ids: &[String],
+        batch_size: usize,
+        control: &BatchControl,
+    ) -> Result<BatchOperationResult>;
+
+    /// Download an attachment
+    async fn get_attachment(&self, message_id: &str, attachment_id: &str) -> Result<AttachmentData>;
+
+    /// Download an attachment straight into a file at `path`, returning its size
+    async fn save_attachment(&self, message_id: &str, attachment_id: &str, path: &Path) -> Result<u64>;
+
+    /// Batch modify messages
+    async fn batch_modify_messages(
+        &self,
+        message_ids: &[String],
+        add_label_ids: Option<Vec<String>>,
+        remove_label_ids: Option<Vec<String>>,
+        batch_size: usize,
+        control: &BatchControl,
+    ) -> Result<BatchOperationResult>;
+
+    /// Batch move messages to trash
+    async fn batch_delete_messages(
+        &self,
+        message_ids: &[String],
+        batch_size: usize,
+        control: &BatchControl,
+    ) -> Result<BatchOperationResult>;
+
+    /// Send each message separately, `interval` apart
+    async fn send_email_batch(
+        &self,
+        messages: Vec<EmailParams>,
+        interval: Duration,
+        control: &BatchControl,
+    ) -> BatchOperationResult;
+
+    /// List all labels
+    async fn list_labels(&self) -> Result<LabelListResult>;
+
+    /// List all labels with message counts for user labels
+    async fn list_labels_with_counts(&self) -> Result<LabelListResult>;
+
+    /// Get a label by ID or name, with message and unread counts
+    async fn get_label(&self, id_or_name: &str) -> Result<Label>;
+
+    /// Create a label
+    async fn create_label(
+        &self,
+        name: &str,
+        message_list_visibility: Option<&str>,
+        label_list_visibility: Option<&str>,
+    ) -> Result<Label>;
+
+    /// Update a label
+    async fn update_label(&self, label_id: &str, updates: UpdateLabelRequest) -> Result<Label>;
+
+    /// Delete a label
+    async fn delete_label(&self, label_id: &str) -> Result<()>;
+
+    /// Resolve label names or IDs to label IDs
+    async fn resolve_label_ids(&self, names_or_ids: Option<Vec<String>>) -> Result<Option<Vec<String>>>;
+
+    /// Resolve label names or IDs to label IDs, creating any missing labels
+    async fn resolve_or_create_label_ids(&self, names_or_ids: Option<Vec<String>>) -> Result<Option<Vec<String>>>;
+
+    /// Get or create a label
+    async fn get_or_create_label(
+        &self,
+        name: &str,
+        message_list_visibility: Option<&str>,
+        label_list_visibility: Option<&str>,
+    ) -> Result<Label>;
+
+    /// List all filters
+    async fn list_filters(&self) -> Result<FilterListResult>;
+
+    /// Get a specific filter
+    async fn get_filter(&self, filter_id: &str) -> Result<Filter>;
+
+    /// Create a filter
+    async fn create_filter(&self, criteria: FilterCriteria, action: FilterAction) -> Result<Filter>;
+
+    /// Delete a filter
+    async fn delete_filter(&self, filter_id: &str) -> Result<()>;
+}
+
+#[async_trait]
+impl GmailApi for GmailClient {
+    fn quota_usage(&self) -> QuotaUsage {
+        GmailClient::quota_usage(self)
+    }
+
+    async fn check_scope(&self, required: &str) -> Result<()> {
+        GmailClient::check_scope(self, required).await
+    }
+
+    async fn get_profile(&self) -> Result<Profile> {
+        GmailClient::get_profile(self).await
+    }
+
+    async fn sender_email(&self) -> Result<&str> {
+        GmailClient::sender_email(self).await
+    }
+
+    async fn send_email(&self, params: EmailParams) -> Result<SendEmailResult> {
+        GmailClient::send_email(self, params).await
+    }
+
+    async fn create_draft(&self, params: EmailParams) -> Result<Draft> {
+        GmailClient::create_draft(self, params).await
+    }
+
+    async fn import_message(&self, raw: &[u8], options: &ImportOptions) -> Result<Message> {
+        GmailClient::import_message(self, raw, options).await
+    }
+
+    async fn insert_message(&self, raw: &[u8], label_ids: &[String]) -> Result<Message> {
+        GmailClient::insert_message(self, raw, label_ids).await
+    }
+
+    async fn get_message(&self, message_id: &str) -> Result<Message> {
+        GmailClient::get_message(self, message_id).await
+    }
+
+    async fn get_message_headers(&self, message_id: &str, names: &[String]) -> Result<Vec<Header>> {
+        GmailClient::get_message_headers(self, message_id, names).await
+    }
+
+    async fn get_label_ids(&self, message_id: &str) -> Result<Vec<String>> {
+        GmailClient::get_label_ids(self, message_id).await
+    }
+
+    async fn get_message_summary(&self, message_id: &str) -> Result<MessageSummary> {
+        GmailClient::get_message_summary(self, message_id).await
+    }
+
+    async fn read_message(&self, message_id: &str) -> Result<ReadMessageResult> {
+        GmailClient::read_message(self, message_id).await
+    }
+
+    async fn get_unsubscribe_info(&self, message_id: &str) -> Result<UnsubscribeInfo> {
+        GmailClient::get_unsubscribe_info(self, message_id).await
+    }
+
+    async fn unsubscribe_one_click(&self, url: &str) -> Result<()> {
+        GmailClient::unsubscribe_one_click(self, url).await
+    }
+
+    async fn download_url_attachment(&self, url: &str) -> Result<utils::AttachmentData> {
+        GmailClient::download_url_attachment(self, url).await
+    }
+
+    async fn reply_context(&self, message_id: &str) -> Result<ReplyContext> {
+        GmailClient::reply_context(self, message_id).await
+    }
+
+    async fn thread_reply_context(&self, thread_id: &str) -> Result<ReplyContext> {
+        GmailClient::thread_reply_context(self, thread_id).await
+    }
+
+    async fn list_history(&self, start_history_id: &str, label_id: &str) -> Result<Option<HistoryChanges>> {
+        GmailClient::list_history(self, start_history_id, label_id).await
+    }
+
+    async fn read_thread(&self, thread_id: &str) -> Result<Vec<ReadMessageResult>> {
+        GmailClient::read_thread(self, thread_id).await
+    }
+
+    async fn list_attachments(&self, message_id: &str) -> Result<Vec<EmailAttachment>> {
+        GmailClient::list_attachments(self, message_id).await
+    }
+
+    async fn search_messages(&self, query: &str, max_results: Option<u32>) -> Result<Vec<SearchMessageResult>> {
+        GmailClient::search_messages(self, query, max_results).await
+    }
+
+    async fn search_message_snippets(
+        &self,
+        query: &str,
+        max_results: Option<u32>,
+    ) -> Result<Vec<SearchSnippetResult>> {
+        GmailClient::search_message_snippets(self, query, max_results).await
+    }
+
+    async fn modify_message(
+        &self,
+        message_id: &str,
+        add_label_ids: Option<Vec<String>>,
+        remove_label_ids: Option<Vec<String>>,
+    ) -> Result<Message> {
+        GmailClient::modify_message(self, message_id, add_label_ids, remove_label_ids).await
+    }
+
+    async fn delete_message(&self, message_id: &str) -> Result<()> {
+        GmailClient::delete_message(self, message_id).await
+    }
+
+    async fn list_message_ids(&self, query: &str, limit: usize) -> Result<Vec<String>> {
+        GmailClient::list_message_ids(self, query, limit).await
+    }
+
+    async fn list_all_message_ids(&self, query: &str) -> Result<Vec<String>> {
+        GmailClient::list_all_message_ids(self, query).await
+    }
+
+    async fn batch_delete_messages_permanently(
+        &self,
+        message_ids: &[String],
+        batch_size: usize,
+        control: &BatchControl,
+    ) -> Result<BatchOperationResult> {
+        GmailClient::batch_delete_messages_permanently(self, message_ids, batch_size, control).await
+    }
+
+    async fn get_attachment(&self, message_id: &str, attachment_id: &str) -> Result<AttachmentData> {
+        GmailClient::get_attachment(self, message_id, attachment_id).await
+    }
+
+    async fn save_attachment(&self, message_id: &str, attachment_id: &str, path: &Path) -> Result<u64> {
+        GmailClient::save_attachment(self, message_id, attachment_id, path).await
+    }
+
+    async fn batch_modify_messages(
+        &self,
+        message_ids: &[String],
+        add_label_ids: Option<Vec<String>>,
+        remove_label_ids: Option<Vec<String>>,
+        batch_size: usize,
+        control: &BatchControl,
+    ) -> Result<BatchOperationResult> {
+        GmailClient::batch_modify_messages(self, message_ids, add_label_ids, remove_label_ids, batch_size, control)
+            .await
+    }
+
+    async fn batch_delete_messages(
+        &self,
+        message_ids: &[String],
+        batch_size: usize,
+        control: &BatchControl,
+    ) -> Result<BatchOperationResult> {
+        GmailClient::batch_delete_messages(self, message_ids, batch_size, control).await
+    }
+
+    async fn send_email_batch(
+        &self,
+        messages: Vec<EmailParams>,
+        interval: Duration,
+        control: &BatchControl,
+    ) -> BatchOperationResult {
+        GmailClient::send_email_batch(self, messages, interval, control).await
+    }
+
+    async fn list_labels(&self) -> Result<LabelListResult> {
+        GmailClient::list_labels(self).await
+    }
+
+    async fn list_labels_with_counts(&self) -> Result<LabelListResult> {
+        GmailClient::list_labels_with_counts(self).await
+    }
+
+    async fn get_label(&self, id_or_name: &str) -> Result<Label> {
+        GmailClient::get_label(self, id_or_name).await
+    }
+
+    async fn create_label(
+        &self,
+        name: &str,
+        message_list_visibility: Option<&str>,
+        label_list_visibility: Option<&str>,
+    ) -> Result<Label> {
+        GmailClient::create_label(self, name, message_list_visibility, label_list_visibility).await
+    }
+
+    async fn update_label(&self, label_id: &str, updates: UpdateLabelRequest) -> Result<Label> {
+        GmailClient::update_label(self, label_id, updates).await
+    }
+
+    async fn delete_label(&self, label_id: &str) -> Result<()> {
+        GmailClient::delete_label(self, label_id).await
+    }
+
+    async fn resolve_label_ids(&self, names_or_ids: Option<Vec<String>>) -> Result<Option<Vec<String>>> {
+        GmailClient::resolve_label_ids(self, names_or_ids).await
+    }
+
+    async fn resolve_or_create_label_ids(&self, names_or_ids: Option<Vec<String>>) -> Result<Option<Vec<String>>> {
+        GmailClient::resolve_or_create_label_ids(self, names_or_ids).await
+    }
+
+    async fn get_or_create_label(
+        &self,
+        name: &str,
+        message_list_visibility: Option<&str>,
+        label_list_visibility: Option<&str>,
+    ) -> Result<Label> {
+        GmailClient::get_or_create_label(self, name, message_list_visibility, label_list_visibility).await
+    }
+
+    async fn list_filters(&self) -> Result<FilterListResult> {
+        GmailClient::list_filters(self).await
+    }
+
+    async fn get_filter(&self, filter_id: &str) -> Result<Filter> {
+        GmailClient::get_filter(self, filter_id).await
+    }
+
+    async fn create_filter(&self, criteria: FilterCriteria, action: FilterAction) -> Result<Filter> {
+        GmailClient::create_filter(self, criteria, action).await
+    }
+
+    async fn delete_filter(&self, filter_id: &str) -> Result<()> {
+        GmailClient::delete_filter(self, filter_id).await
+    }
+}
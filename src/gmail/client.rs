@@ -2,18 +2,73 @@
 //!
 //! High-level client for Gmail API operations.
 
-use crate::config::gmail::{API_BASE_URL, USER_ID};
-use crate::error::{GmailApiError, GmailMcpError, Result};
-use crate::gmail::auth::Authenticator;
+use crate::config::gmail::{scopes, API_BASE_URL, USER_ID};
+use crate::error::{GmailApiError, GmailMcpError, Result, ValidationError};
+use crate::gmail::auth::{scope_covers, Authenticator};
 use crate::gmail::filters::{FilterListResult, FilterManager};
 use crate::gmail::labels::{LabelListResult, LabelManager};
+use crate::gmail::quota::{ApiMethod, QuotaTracker, QuotaUsage, USER_UNITS_PER_SECOND};
+use crate::gmail::stream::AttachmentDataExtractor;
 use crate::gmail::types::*;
 use crate::gmail::utils::{
-    create_email_message, encode_raw_message, extract_attachments, extract_email_content,
-    find_header, html_to_text, EmailParams,
+    self, attachment_filename_from_url, build_references, create_email_message, encode_raw_bytes, generate_message_id,
+    encode_raw_message, extract_addresses, extract_attachments, extract_delivery_status, extract_email_content, find_header, format_size,
+    get_message_headers, html_to_text, internal_date_to_rfc3339, is_public_ip, mime_type_for_extension, parse_list_unsubscribe,
+    reply_subject, validate_label_changes, EmailParams, UnsubscribeInfo,
 };
 
+use std::collections::HashSet;
+use std::future::Future;
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::OnceCell;
+use tokio_util::sync::CancellationToken;
+
+/// Largest page size accepted by `messages.list`
+const MAX_LIST_PAGE_SIZE: u32 = 500;
+
+/// Results returned by a search when `maxResults` is omitted
+pub const DEFAULT_SEARCH_RESULTS: u32 = 10;
+
+/// Most results a single search returns; each one costs a metadata fetch
+pub const MAX_SEARCH_RESULTS: u32 = MAX_LIST_PAGE_SIZE;
+
+/// Largest number of IDs accepted by a single `messages/batchDelete` call
+pub const MAX_BATCH_DELETE_IDS: usize = 1000;
+
+/// Largest message Gmail accepts for sending, attachments and encoding included
+pub const MAX_EMAIL_BYTES: usize = 25 * 1024 * 1024;
+
+/// Largest attachment downloaded from a URL (Gmail caps whole messages at 25 MB)
+pub const MAX_URL_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+
+/// How long an attachment download may take
+const URL_ATTACHMENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Redirects followed when downloading an attachment
+const MAX_URL_ATTACHMENT_REDIRECTS: usize = 5;
+
+/// Default number of times a failed attachment fetch is retried
+pub const DEFAULT_ATTACHMENT_RETRIES: u32 = 3;
+
+/// Most retries allowed for a failed attachment fetch
+pub const MAX_ATTACHMENT_RETRIES: u32 = 10;
+
+/// Delay before the first attachment retry, doubled for each further one
+const ATTACHMENT_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest backoff between attachment retries
+const MAX_ATTACHMENT_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Default pause between messages in `send_email_batch`
+pub const DEFAULT_SEND_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Longest pause `send_email_batch` callers may ask for between messages
+pub const MAX_SEND_INTERVAL: Duration = Duration::from_secs(60);
 
 /// Gmail API client
 pub struct GmailClient {
@@ -22,6 +77,18 @@ pub struct GmailClient {
 
     /// OAuth authenticator
     authenticator: Arc<Authenticator>,
+
+    /// Mailbox the client operates on: `me` or a delegated user's address
+    user_id: String,
+
+    /// Authenticated user's address, fetched once for Message-ID domains
+    sender_email: OnceCell<String>,
+
+    /// Estimated quota units spent by this client, and the rate limiter all calls go through
+    quota: QuotaTracker,
+
+    /// Times a failed attachment fetch is retried with backoff
+    attachment_retries: u32,
 }
 
 impl GmailClient {
@@ -30,193 +97,684 @@ impl GmailClient {
         Self {
             http_client: reqwest::Client::new(),
             authenticator,
+            user_id: USER_ID.to_string(),
+            sender_email: OnceCell::new(),
+            quota: QuotaTracker::new().with_rate_limit(USER_UNITS_PER_SECOND),
+            attachment_retries: DEFAULT_ATTACHMENT_RETRIES,
         }
     }
 
+    /// Pace API calls to `units_per_second` quota units; 0 disables limiting
+    pub fn with_rate_limit(mut self, units_per_second: u64) -> Self {
+        self.quota = QuotaTracker::new().with_rate_limit(units_per_second);
+        self
+    }
+
+    /// Operate on `user_id`'s mailbox (`me` is the authenticated user)
+    pub fn with_user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = user_id.into();
+        self
+    }
+
+    /// Retry failed attachment fetches up to `retries` times (at most
+    /// `MAX_ATTACHMENT_RETRIES`); 0 disables retrying
+    pub fn with_attachment_retries(mut self, retries: u32) -> Self {
+        self.attachment_retries = retries.min(MAX_ATTACHMENT_RETRIES);
+        self
+    }
+
+    /// Estimated Gmail quota units used since the client was created
+    pub fn quota_usage(&self) -> QuotaUsage {
+        self.quota.usage()
+    }
+
+    /// Label manager that counts and paces its requests against this client's quota
+    fn labels<'a>(&'a self, token: &'a str) -> LabelManager<'a> {
+        LabelManager::new(&self.http_client, token)
+            .with_quota(&self.quota)
+            .with_user_id(&self.user_id)
+    }
+
+    /// Filter manager that counts and paces its requests against this client's quota
+    fn filters<'a>(&'a self, token: &'a str) -> FilterManager<'a> {
+        FilterManager::new(&self.http_client, token)
+            .with_quota(&self.quota)
+            .with_user_id(&self.user_id)
+    }
+
     /// Get a valid access token
     async fn access_token(&self) -> Result<String> {
         self.authenticator.get_access_token().await
     }
 
+    /// Run a Gmail request, refreshing the access token and retrying once on a 401
+    ///
+    /// Tokens revoked or rotated while the server sat idle still look fresh to
+    /// `access_token`, so the first rejected call forces a refresh instead of
+    /// surfacing the 401.
+    async fn with_auth_retry<T, F, Fut>(&self, op: F) -> Result<T>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let token = self.access_token().await?;
+        match op(token).await {
+            Err(GmailMcpError::Gmail(GmailApiError::Unauthorized { .. })) => {
+                let token = self.authenticator.refresh_after_unauthorized().await?;
+                op(token).await
+            }
+            outcome => outcome,
+        }
+    }
+
+    /// Fail early if the stored token was not granted `required`
+    ///
+    /// Tokens minted before the scope was requested are otherwise only caught
+    /// by a 403 deep in the API call. An unknown grant is let through.
+    pub async fn check_scope(&self, required: &str) -> Result<()> {
+        match self.authenticator.granted_scopes().await {
+            Some(granted) if !scope_covers(&granted, required) => {
+                Err(GmailMcpError::Gmail(GmailApiError::InsufficientPermissions {
+                    scope: required.to_string(),
+                }))
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Base URL for messages
-    fn messages_url() -> String {
-        format!("{}/users/{}/messages", API_BASE_URL, USER_ID)
+    fn messages_url(&self) -> String {
+        format!("{}/messages", self.user_url())
+    }
+
+    /// Base URL for threads
+    fn threads_url(&self) -> String {
+        format!("{}/threads", self.user_url())
     }
 
     /// Base URL for drafts
-    fn drafts_url() -> String {
-        format!("{}/users/{}/drafts", API_BASE_URL, USER_ID)
+    fn drafts_url(&self) -> String {
+        format!("{}/drafts", self.user_url())
+    }
+
+    /// Base URL for the mailbox's user resource
+    fn user_url(&self) -> String {
+        format!("{}/users/{}", API_BASE_URL, urlencoding::encode(&self.user_id))
     }
 
     // ==================== Message Operations ====================
 
+    /// Get the authenticated user's mailbox profile
+    pub async fn get_profile(&self) -> Result<Profile> {
+        let url = &format!("{}/profile", self.user_url());
+
+        self.with_auth_retry(|token| async move {
+            self.quota.acquire(ApiMethod::GetProfile).await;
+            let response = self
+                .http_client
+                .get(url)
+                .bearer_auth(&token)
+                .send()
+                .await?;
+
+            if response.status().is_success() {
+                Ok(response.json().await?)
+            } else {
+                Err(request_error(response, "get profile").await)
+            }
+        })
+        .await
+    }
+
+    /// The authenticated user's email address (cached after the first lookup)
+    pub async fn sender_email(&self) -> Result<&str> {
+        self.sender_email
+            .get_or_try_init(|| async { self.get_profile().await.map(|p| p.email_address) })
+            .await
+            .map(String::as_str)
+    }
+
     /// Send an email
-    pub async fn send_email(&self, params: EmailParams) -> Result<Message> {
-        let token = self.access_token().await?;
+    ///
+    /// A `Message-ID` is generated when the caller didn't set one, so the sent
+    /// message can be matched up later. Its domain comes from the user's
+    /// address, falling back to `gmail.com` when the profile can't be fetched.
+    pub async fn send_email(&self, mut params: EmailParams) -> Result<SendEmailResult> {
+        if params.message_id.is_none() {
+            let sender = match self.sender_email().await {
+                Ok(sender) => sender,
+                Err(e) => {
+                    tracing::warn!("Could not look up the sender address for the Message-ID: {}", e);
+                    ""
+                }
+            };
+            params.message_id = Some(generate_message_id(sender));
+        }
 
         // For now, we only support simple emails without attachments
         // Attachment support would require multipart MIME handling
         let raw_message = create_email_message(&params)?;
+        let size = check_email_size(&raw_message)?;
         let encoded = encode_raw_message(&raw_message);
 
-        let request = SendMessageRequest {
+        let request = &SendMessageRequest {
             raw: encoded,
             thread_id: params.thread_id,
         };
 
-        let url = format!("{}/send", Self::messages_url());
-
-        let response = self
-            .http_client
-            .post(&url)
-            .bearer_auth(&token)
-            .json(&request)
-            .send()
+        let url = &format!("{}/send", self.messages_url());
+
+        let message: Message = self
+            .with_auth_retry(|token| async move {
+                self.quota.acquire(ApiMethod::MessagesSend).await;
+                let response = self
+                    .http_client
+                    .post(url)
+                    .bearer_auth(&token)
+                    .json(request)
+                    .send()
+                    .await?;
+
+                if response.status().is_success() {
+                    Ok(response.json().await?)
+                } else {
+                    Err(send_error(response, "send email", size).await)
+                }
+            })
             .await?;
 
-        if response.status().is_success() {
-            Ok(response.json().await?)
-        } else {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            Err(GmailMcpError::Gmail(GmailApiError::RequestFailed {
-                message: format!("Failed to send email ({}): {}", status, text),
-            }))
-        }
+        Ok(SendEmailResult {
+            message,
+            message_id_header: params.message_id.unwrap_or_default(),
+        })
     }
 
     /// Create a draft
     pub async fn create_draft(&self, params: EmailParams) -> Result<Draft> {
-        let token = self.access_token().await?;
-
         let raw_message = create_email_message(&params)?;
+        let size = check_email_size(&raw_message)?;
         let encoded = encode_raw_message(&raw_message);
 
-        let request = CreateDraftRequest {
+        let request = &CreateDraftRequest {
             message: SendMessageRequest {
                 raw: encoded,
                 thread_id: params.thread_id,
             },
         };
 
-        let response = self
-            .http_client
-            .post(Self::drafts_url())
-            .bearer_auth(&token)
-            .json(&request)
-            .send()
-            .await?;
+        self.with_auth_retry(|token| async move {
+            self.quota.acquire(ApiMethod::DraftsCreate).await;
+            let response = self
+                .http_client
+                .post(self.drafts_url())
+                .bearer_auth(&token)
+                .json(request)
+                .send()
+                .await?;
 
-        if response.status().is_success() {
-            Ok(response.json().await?)
-        } else {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            Err(GmailMcpError::Gmail(GmailApiError::RequestFailed {
-                message: format!("Failed to create draft ({}): {}", status, text),
-            }))
+            if response.status().is_success() {
+                Ok(response.json().await?)
+            } else {
+                Err(send_error(response, "create draft", size).await)
+            }
+        })
+        .await
+    }
+
+    /// Import a raw RFC822 message into the mailbox
+    ///
+    /// The message goes through Gmail's normal delivery scanning (spam
+    /// classification and filters) as if it had been received, but is not sent.
+    pub async fn import_message(&self, raw: &[u8], options: &ImportOptions) -> Result<Message> {
+        let request = &RawMessageRequest {
+            raw: encode_raw_bytes(raw),
+            label_ids: options.label_ids.clone(),
+        };
+
+        let mut url = format!(
+            "{}/import?neverMarkSpam={}",
+            self.messages_url(),
+            options.never_mark_spam
+        );
+        if let Some(ref source) = options.internal_date_source {
+            url.push_str(&format!("&internalDateSource={}", urlencoding::encode(source)));
         }
+        let url = &url;
+
+        self.with_auth_retry(|token| async move {
+            self.quota.acquire(ApiMethod::MessagesImport).await;
+            let response = self
+                .http_client
+                .post(url)
+                .bearer_auth(&token)
+                .json(request)
+                .send()
+                .await?;
+
+            if response.status().is_success() {
+                Ok(response.json().await?)
+            } else {
+                Err(request_error(response, "import message").await)
+            }
+        })
+        .await
+    }
+
+    /// Insert a raw RFC822 message directly into the mailbox
+    ///
+    /// Unlike `import_message`, no spam classification or filters are applied;
+    /// the message lands exactly as given with the requested labels.
+    pub async fn insert_message(&self, raw: &[u8], label_ids: &[String]) -> Result<Message> {
+        let request = &RawMessageRequest {
+            raw: encode_raw_bytes(raw),
+            label_ids: label_ids.to_vec(),
+        };
+
+        let url = &format!("{}/insert", self.messages_url());
+
+        self.with_auth_retry(|token| async move {
+            self.quota.acquire(ApiMethod::MessagesInsert).await;
+            let response = self
+                .http_client
+                .post(url)
+                .bearer_auth(&token)
+                .json(request)
+                .send()
+                .await?;
+
+            if response.status().is_success() {
+                Ok(response.json().await?)
+            } else {
+                Err(request_error(response, "insert message").await)
+            }
+        })
+        .await
     }
 
     /// Get a message by ID
     pub async fn get_message(&self, message_id: &str) -> Result<Message> {
-        let token = self.access_token().await?;
-        let url = format!("{}/{}?format=full", Self::messages_url(), message_id);
+        self.fetch_message(message_id, "format=full").await
+    }
+
+    /// Get only the named headers of a message, in the order they appear
+    ///
+    /// Uses `format=metadata`, so no body is downloaded. Repeated headers such
+    /// as `Received` are all returned.
+    pub async fn get_message_headers(&self, message_id: &str, names: &[String]) -> Result<Vec<Header>> {
+        let mut query = "format=metadata".to_string();
+        for name in names {
+            query.push_str(&format!("&metadataHeaders={}", urlencoding::encode(name)));
+        }
+
+        let message = self.fetch_message(message_id, &query).await?;
+        Ok(message
+            .payload
+            .map(|payload| {
+                payload
+                    .headers
+                    .into_iter()
+                    .filter(|h| names.iter().any(|n| n.eq_ignore_ascii_case(&h.name)))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Label IDs currently on a message
+    pub async fn get_label_ids(&self, message_id: &str) -> Result<Vec<String>> {
+        Ok(self.fetch_message(message_id, "format=minimal").await?.label_ids)
+    }
+
+    /// Headers, labels and snippet of a message, without its body
+    pub async fn get_message_summary(&self, message_id: &str) -> Result<MessageSummary> {
+        let message = self
+            .fetch_message(
+                message_id,
+                "format=metadata&metadataHeaders=Subject&metadataHeaders=From&metadataHeaders=To&metadataHeaders=Date",
+            )
+            .await?;
+        let payload = message.payload.as_ref();
+        let header = |name: &str| {
+            payload
+                .and_then(|p| find_header(p, name))
+                .unwrap_or("")
+                .to_string()
+        };
+
+        Ok(MessageSummary {
+            subject: header("subject"),
+            from: header("from"),
+            to: header("to"),
+            date: header("date"),
+            id: message.id,
+            thread_id: message.thread_id.unwrap_or_default(),
+            label_ids: message.label_ids,
+            snippet: message.snippet.unwrap_or_default(),
+        })
+    }
+
+    /// `messages.get` with the given query string
+    async fn fetch_message(&self, message_id: &str, query: &str) -> Result<Message> {
+        let url = &format!("{}/{}?{}", self.messages_url(), message_id, query);
+
+        self.with_auth_retry(|token| async move {
+            self.quota.acquire(ApiMethod::MessagesGet).await;
+            let response = self
+                .http_client
+                .get(url)
+                .bearer_auth(&token)
+                .send()
+                .await?;
+
+            if response.status().is_success() {
+                Ok(response.json().await?)
+            } else if response.status().as_u16() == 404 {
+                Err(GmailMcpError::Gmail(GmailApiError::MessageNotFound {
+                    message_id: message_id.to_string(),
+                }))
+            } else {
+                Err(request_error(response, "get message").await)
+            }
+        })
+        .await
+    }
+
+    /// Get a message with parsed content
+    pub async fn read_message(&self, message_id: &str) -> Result<ReadMessageResult> {
+        let mut message = self.get_message(message_id).await?;
+
+        // A full fetch normally always has a payload; retry once in case the
+        // response was cut short before giving up on the content
+        if message.payload.is_none() {
+            tracing::warn!("Message {} came back without a payload; fetching it again", message_id);
+            message = self.get_message(message_id).await?;
+        }
+
+        Ok(read_message_from(message))
+    }
+
+    /// Get the unsubscribe targets advertised by a message
+    pub async fn get_unsubscribe_info(&self, message_id: &str) -> Result<UnsubscribeInfo> {
+        let message = self.get_message(message_id).await?;
+
+        let Some(payload) = message.payload.as_ref() else {
+            return Ok(UnsubscribeInfo::default());
+        };
+
+        Ok(find_header(payload, "list-unsubscribe")
+            .map(|value| {
+                parse_list_unsubscribe(value, find_header(payload, "list-unsubscribe-post"))
+            })
+            .unwrap_or_default())
+    }
+
+    /// Perform an RFC 8058 one-click unsubscribe
+    ///
+    /// The request goes to the sender's server, so no Gmail credentials are attached.
+    pub async fn unsubscribe_one_click(&self, url: &str) -> Result<()> {
+        if !url.to_ascii_lowercase().starts_with("https://") {
+            return Err(GmailMcpError::Gmail(GmailApiError::RequestFailed {
+                message: format!("One-click unsubscribe requires an https URL: {}", url),
+            }));
+        }
 
         let response = self
             .http_client
-            .get(&url)
-            .bearer_auth(&token)
+            .post(url)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body("List-Unsubscribe=One-Click")
             .send()
             .await?;
 
         if response.status().is_success() {
-            Ok(response.json().await?)
-        } else if response.status().as_u16() == 404 {
-            Err(GmailMcpError::Gmail(GmailApiError::MessageNotFound {
-                message_id: message_id.to_string(),
-            }))
+            Ok(())
         } else {
             let status = response.status();
-            let text = response.text().await.unwrap_or_default();
             Err(GmailMcpError::Gmail(GmailApiError::RequestFailed {
-                message: format!("Failed to get message ({}): {}", status, text),
+                message: format!("One-click unsubscribe failed ({})", status),
             }))
         }
     }
 
-    /// Get a message with parsed content
-    pub async fn read_message(&self, message_id: &str) -> Result<ReadMessageResult> {
-        let message = self.get_message(message_id).await?;
+    /// Download an `http(s)://` URL to attach to an outgoing message
+    ///
+    /// The filename comes from `Content-Disposition` or the URL path and the
+    /// MIME type from `Content-Type`. Downloads are capped at
+    /// `MAX_URL_ATTACHMENT_BYTES` and `URL_ATTACHMENT_TIMEOUT`. Like one-click
+    /// unsubscribe, the request goes to a third party without Gmail credentials.
+    ///
+    /// Every host, including redirect targets, must resolve to public addresses
+    /// only (see `is_public_ip`), and the connection goes to the addresses that
+    /// were checked, so a URL can't be used to reach the local network.
+    pub async fn download_url_attachment(&self, url: &str) -> Result<utils::AttachmentData> {
+        let failed = |message: String| {
+            GmailMcpError::Validation(ValidationError::InvalidParameter {
+                name: "attachments".to_string(),
+                message,
+            })
+        };
 
-        let payload = message.payload.as_ref();
-        let snippet = message.snippet.clone();
+        let mut parsed = reqwest::Url::parse(url).map_err(|e| failed(format!("Invalid URL {}: {}", url, e)))?;
+        let mut redirects = 0;
+        let mut response = loop {
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err(failed(format!("Only http(s) URLs can be attached: {}", parsed)));
+            }
+            let client = public_host_client(&parsed).await.map_err(failed)?;
+            let response = client
+                .get(parsed.clone())
+                .timeout(URL_ATTACHMENT_TIMEOUT)
+                .send()
+                .await
+                .map_err(|e| failed(format!("Failed to download {}: {}", url, e)))?;
+
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .filter(|_| response.status().is_redirection());
+            let Some(location) = location else {
+                break response;
+            };
+            redirects += 1;
+            if redirects > MAX_URL_ATTACHMENT_REDIRECTS {
+                return Err(failed(format!("Too many redirects downloading {}", url)));
+            }
+            parsed = parsed
+                .join(location)
+                .map_err(|e| failed(format!("Invalid redirect from {}: {}", parsed, e)))?;
+        };
 
-        let subject = payload
-            .and_then(|p| find_header(p, "subject"))
-            .unwrap_or("")
-            .to_string();
+        if !response.status().is_success() {
+            return Err(failed(format!("Failed to download {} ({})", url, response.status())));
+        }
+        let too_large = || {
+            failed(format!(
+                "{} is larger than the {} attachment limit",
+                url,
+                format_size(MAX_URL_ATTACHMENT_BYTES as i64)
+            ))
+        };
+        if response.content_length().is_some_and(|len| len > MAX_URL_ATTACHMENT_BYTES as u64) {
+            return Err(too_large());
+        }
 
-        let from = payload
-            .and_then(|p| find_header(p, "from"))
-            .unwrap_or("")
-            .to_string();
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let filename = attachment_filename_from_url(
+            &parsed,
+            header(reqwest::header::CONTENT_DISPOSITION).as_deref(),
+        );
+        let mime_type = header(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.split(';').next().map(|m| m.trim().to_string()))
+            .filter(|m| !m.is_empty())
+            .unwrap_or_else(|| {
+                mime_type_for_extension(filename.rsplit_once('.').map(|(_, ext)| ext)).to_string()
+            });
+
+        // Content-Length can be missing or wrong, so enforce the cap while reading
+        let mut data = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| failed(format!("Failed to download {}: {}", url, e)))?
+        {
+            if data.len() + chunk.len() > MAX_URL_ATTACHMENT_BYTES {
+                return Err(too_large());
+            }
+            data.extend_from_slice(&chunk);
+        }
 
-        let to = payload
-            .and_then(|p| find_header(p, "to"))
-            .unwrap_or("")
-            .to_string();
+        Ok(utils::AttachmentData {
+            filename,
+            mime_type,
+            data,
+        })
+    }
 
-        let date = payload
-            .and_then(|p| find_header(p, "date"))
-            .unwrap_or("")
-            .to_string();
+    /// Collect the threading headers needed to reply to a message
+    pub async fn reply_context(&self, message_id: &str) -> Result<ReplyContext> {
+        let message = self.get_message(message_id).await?;
+        reply_context_for(&message)
+    }
 
-        let content = payload
-            .map(extract_email_content)
-            .unwrap_or_default();
+    /// Threading headers for replying to the latest message of a thread
+    ///
+    /// Drafts in the thread are skipped: a reply answers what was received or
+    /// sent, not an unsent draft.
+    pub async fn thread_reply_context(&self, thread_id: &str) -> Result<ReplyContext> {
+        let thread = self
+            .get_thread(
+                thread_id,
+                "format=metadata&metadataHeaders=Message-ID&metadataHeaders=References\
+                 &metadataHeaders=In-Reply-To&metadataHeaders=From&metadataHeaders=Reply-To&metadataHeaders=Subject",
+            )
+            .await?;
 
-        let attachments = payload
-            .map(extract_attachments)
-            .unwrap_or_default();
-
-        // Check if body extraction failed (for logging)
-        let extraction_failed = content.text.is_empty() && content.html.is_empty();
-        
-        // Determine body content with fallback to snippet
-        let is_html_only = content.text.is_empty() && !content.html.is_empty();
-        let (body, html_body) = if !content.text.is_empty() {
-            let html = if content.html.is_empty() { None } else { Some(content.html) };
-            (content.text, html)
-        } else if !content.html.is_empty() {
-            // Convert HTML to readable text for the body
-            let text_from_html = html_to_text(&content.html);
-            (text_from_html, Some(content.html))
-        } else {
-            // Fallback to snippet if body extraction failed
-            (snippet.unwrap_or_default(), None)
-        };
+        let latest = latest_reply_target(&thread.messages).ok_or_else(|| {
+            GmailMcpError::Gmail(GmailApiError::RequestFailed {
+                message: format!("Thread {} has no sent or received messages to reply to", thread_id),
+            })
+        })?;
+        reply_context_for(latest)
+    }
 
-        // Log if we had to fall back to snippet
-        if extraction_failed {
-            tracing::debug!(
-                "Email {} body extraction returned empty, using snippet fallback",
-                message_id
+    /// Messages added to `label_id` since `start_history_id`, oldest first
+    ///
+    /// Returns `None` when Gmail no longer keeps history that far back (a
+    /// week or so), so callers can fall back to a date search.
+    pub async fn list_history(
+        &self,
+        start_history_id: &str,
+        label_id: &str,
+    ) -> Result<Option<HistoryChanges>> {
+        // One item per page; `None` marks history Gmail has already dropped
+        let pages = paginate(None, |page_token, page_size| async move {
+            let mut url = format!(
+                "{}/history?startHistoryId={}&historyTypes=messageAdded&labelId={}&maxResults={}",
+                self.user_url(),
+                urlencoding::encode(start_history_id),
+                urlencoding::encode(label_id),
+                page_size
             );
+            if let Some(ref token) = page_token {
+                url.push_str(&format!("&pageToken={}", urlencoding::encode(token)));
+            }
+            let url = &url;
+
+            let page: Option<HistoryListResponse> = self
+                .with_auth_retry(|token| async move {
+                    self.quota.acquire(ApiMethod::HistoryList).await;
+                    let response = self
+                        .http_client
+                        .get(url)
+                        .bearer_auth(&token)
+                        .send()
+                        .await?;
+
+                    if response.status().is_success() {
+                        Ok(Some(response.json().await?))
+                    } else if response.status().as_u16() == 404 {
+                        Ok(None)
+                    } else {
+                        Err(request_error(response, "list history").await)
+                    }
+                })
+                .await?;
+
+            Ok(Page {
+                next_page_token: page.as_ref().and_then(|p| p.next_page_token.clone()),
+                items: vec![page],
+            })
+        })
+        .await?;
+
+        let mut seen = HashSet::new();
+        let mut message_ids = Vec::new();
+        let mut history_id = start_history_id.to_string();
+        for page in pages {
+            let Some(page) = page else {
+                return Ok(None);
+            };
+            for record in page.history {
+                for added in record.messages_added {
+                    if seen.insert(added.message.id.clone()) {
+                        message_ids.push(added.message.id);
+                    }
+                }
+            }
+            if let Some(id) = page.history_id {
+                history_id = id;
+            }
         }
 
-        Ok(ReadMessageResult {
-            id: message.id,
-            thread_id: message.thread_id.unwrap_or_default(),
-            subject,
-            from,
-            to,
-            date,
-            body,
-            html_body,
-            is_html_only,
-            attachments,
+        Ok(Some(HistoryChanges { message_ids, history_id }))
+    }
+
+    /// Read every message in a thread, oldest first
+    pub async fn read_thread(&self, thread_id: &str) -> Result<Vec<ReadMessageResult>> {
+        let thread = self.get_thread(thread_id, "format=full").await?;
+        Ok(thread.messages.into_iter().map(read_message_from).collect())
+    }
+
+    /// Fetch a thread with threads.get; `query` selects the format
+    async fn get_thread(&self, thread_id: &str, query: &str) -> Result<Thread> {
+        let url = &format!("{}/{}?{}", self.threads_url(), thread_id, query);
+
+        self.with_auth_retry(|token| async move {
+            self.quota.acquire(ApiMethod::ThreadsGet).await;
+            let response = self
+                .http_client
+                .get(url)
+                .bearer_auth(&token)
+                .send()
+                .await?;
+
+            if response.status().is_success() {
+                Ok(response.json().await?)
+            } else if response.status().as_u16() == 404 {
+                Err(GmailMcpError::Gmail(GmailApiError::RequestFailed {
+                    message: format!("Thread not found: {}", thread_id),
+                }))
+            } else {
+                Err(request_error(response, "get thread").await)
+            }
         })
+        .await
+    }
+
+    /// List a message's attachments without extracting its body
+    pub async fn list_attachments(&self, message_id: &str) -> Result<Vec<EmailAttachment>> {
+        let message = self.get_message(message_id).await?;
+        Ok(message
+            .payload
+            .as_ref()
+            .map(extract_attachments)
+            .unwrap_or_default())
     }
 
     /// Search for messages
@@ -225,37 +783,19 @@ impl GmailClient {
         query: &str,
         max_results: Option<u32>,
     ) -> Result<Vec<SearchMessageResult>> {
+        let message_refs = self.list_message_refs(query, Some(search_cap(max_results))).await?;
         let token = self.access_token().await?;
-        let max = max_results.unwrap_or(10);
-
-        let url = format!("{}?q={}&maxResults={}", Self::messages_url(), urlencoding::encode(query), max);
-
-        let response = self
-            .http_client
-            .get(&url)
-            .bearer_auth(&token)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            return Err(GmailMcpError::Gmail(GmailApiError::RequestFailed {
-                message: format!("Failed to search messages ({}): {}", status, text),
-            }));
-        }
-
-        let message_list: MessageList = response.json().await?;
 
         // Fetch metadata for each message
         let mut results = Vec::new();
-        for msg_ref in message_list.messages {
+        for msg_ref in message_refs {
             let url = format!(
                 "{}/{}?format=metadata&metadataHeaders=Subject&metadataHeaders=From&metadataHeaders=Date",
-                Self::messages_url(),
+                self.messages_url(),
                 msg_ref.id
             );
 
+            self.quota.acquire(ApiMethod::MessagesGet).await;
             let response = self
                 .http_client
                 .get(&url)
@@ -282,6 +822,7 @@ impl GmailClient {
                         .and_then(|p| find_header(p, "date"))
                         .unwrap_or("")
                         .to_string(),
+                    received: message.internal_date.as_deref().and_then(internal_date_to_rfc3339),
                 });
             }
         }
@@ -289,6 +830,85 @@ impl GmailClient {
         Ok(results)
     }
 
+    /// Search for messages, returning only IDs, dates and snippets
+    ///
+    /// Each result is fetched with `format=minimal`, which carries the snippet
+    /// but no headers, so this is lighter than `search_messages` when subjects
+    /// and senders aren't needed.
+    pub async fn search_message_snippets(
+        &self,
+        query: &str,
+        max_results: Option<u32>,
+    ) -> Result<Vec<SearchSnippetResult>> {
+        let message_refs = self.list_message_refs(query, Some(search_cap(max_results))).await?;
+        let token = self.access_token().await?;
+
+        let mut results = Vec::new();
+        for msg_ref in message_refs {
+            let url = format!("{}/{}?format=minimal", self.messages_url(), msg_ref.id);
+
+            self.quota.acquire(ApiMethod::MessagesGet).await;
+            let response = self
+                .http_client
+                .get(&url)
+                .bearer_auth(&token)
+                .send()
+                .await?;
+
+            if response.status().is_success() {
+                let message: Message = response.json().await?;
+                results.push(SearchSnippetResult {
+                    id: message.id,
+                    thread_id: msg_ref.thread_id,
+                    snippet: message.snippet.unwrap_or_default(),
+                    received: message.internal_date.as_deref().and_then(internal_date_to_rfc3339),
+                });
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Messages matching `query`, newest first, following result pages until `cap` (or all of them)
+    async fn list_message_refs(&self, query: &str, cap: Option<usize>) -> Result<Vec<MessageRef>> {
+        paginate(cap, |page_token, page_size| async move {
+            let mut url = format!(
+                "{}?q={}&maxResults={}",
+                self.messages_url(),
+                urlencoding::encode(query),
+                page_size
+            );
+            if let Some(ref page) = page_token {
+                url.push_str(&format!("&pageToken={}", urlencoding::encode(page)));
+            }
+            let url = &url;
+
+            let message_list: MessageList = self
+                .with_auth_retry(|token| async move {
+                    self.quota.acquire(ApiMethod::MessagesList).await;
+                    let response = self
+                        .http_client
+                        .get(url)
+                        .bearer_auth(&token)
+                        .send()
+                        .await?;
+
+                    if response.status().is_success() {
+                        Ok(response.json().await?)
+                    } else {
+                        Err(request_error(response, "list messages").await)
+                    }
+                })
+                .await?;
+
+            Ok(Page {
+                items: message_list.messages,
+                next_page_token: message_list.next_page_token,
+            })
+        })
+        .await
+    }
+
     /// Modify message labels
     pub async fn modify_message(
         &self,
@@ -296,108 +916,300 @@ impl GmailClient {
         add_label_ids: Option<Vec<String>>,
         remove_label_ids: Option<Vec<String>>,
     ) -> Result<Message> {
-        let token = self.access_token().await?;
-        let url = format!("{}/{}/modify", Self::messages_url(), message_id);
+        validate_label_changes(add_label_ids.as_deref(), remove_label_ids.as_deref())?;
+
+        let url = &format!("{}/{}/modify", self.messages_url(), message_id);
 
-        let request = ModifyMessageRequest {
+        let request = &ModifyMessageRequest {
             add_label_ids,
             remove_label_ids,
         };
 
-        let response = self
-            .http_client
-            .post(&url)
-            .bearer_auth(&token)
-            .json(&request)
-            .send()
-            .await?;
+        self.with_auth_retry(|token| async move {
+            self.quota.acquire(ApiMethod::MessagesModify).await;
+            let response = self
+                .http_client
+                .post(url)
+                .bearer_auth(&token)
+                .json(request)
+                .send()
+                .await?;
 
-        if response.status().is_success() {
-            Ok(response.json().await?)
-        } else if response.status().as_u16() == 404 {
-            Err(GmailMcpError::Gmail(GmailApiError::MessageNotFound {
-                message_id: message_id.to_string(),
-            }))
-        } else {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            Err(GmailMcpError::Gmail(GmailApiError::RequestFailed {
-                message: format!("Failed to modify message ({}): {}", status, text),
-            }))
+            if response.status().is_success() {
+                Ok(response.json().await?)
+            } else if response.status().as_u16() == 404 {
+                Err(GmailMcpError::Gmail(GmailApiError::MessageNotFound {
+                    message_id: message_id.to_string(),
+                }))
+            } else {
+                Err(request_error(response, "modify message").await)
+            }
+        })
+        .await
+    }
+
+    /// Delete a message by moving it to trash
+    /// 
+    /// Note: This moves the message to trash rather than permanently deleting it.
+    /// The gmail.modify scope doesn't allow permanent deletion, so we use the
+    /// safer trash approach which works with standard OAuth scopes.
+    pub async fn delete_message(&self, message_id: &str) -> Result<()> {
+        // Use Gmail's trash endpoint which works with gmail.modify scope
+        let url = &format!("{}/{}/trash", self.messages_url(), message_id);
+
+        self.with_auth_retry(|token| async move {
+            self.quota.acquire(ApiMethod::MessagesTrash).await;
+            let response = self
+                .http_client
+                .post(url)
+                .bearer_auth(&token)
+                .header("Content-Length", "0")
+                .send()
+                .await?;
+
+            if response.status().is_success() {
+                Ok(())
+            } else if response.status().as_u16() == 404 {
+                Err(GmailMcpError::Gmail(GmailApiError::MessageNotFound {
+                    message_id: message_id.to_string(),
+                }))
+            } else {
+                Err(request_error(response, "trash message").await)
+            }
+        })
+        .await
+    }
+
+    /// List the IDs of at most `limit` messages matching a query, newest first
+    pub async fn list_message_ids(&self, query: &str, limit: usize) -> Result<Vec<String>> {
+        let message_refs = self.list_message_refs(query, Some(limit)).await?;
+        Ok(message_refs.into_iter().map(|m| m.id).collect())
+    }
+
+    /// List the IDs of every message matching a query, following all result pages
+    pub async fn list_all_message_ids(&self, query: &str) -> Result<Vec<String>> {
+        let message_refs = self.list_message_refs(query, None).await?;
+        Ok(message_refs.into_iter().map(|m| m.id).collect())
+    }
+
+    /// Permanently delete messages, bypassing trash, reporting the outcome of each chunk
+    ///
+    /// This uses `messages/batchDelete`, which requires the full
+    /// `https://mail.google.com/` scope; with only `gmail.modify` Gmail answers 403.
+    /// A failed call marks the IDs in its chunk as failed and the remaining
+    /// chunks still run. `batch_size` is capped at Gmail's limit of 1000 IDs per call.
+    pub async fn batch_delete_messages_permanently(
+        &self,
+        message_ids: &[String],
+        batch_size: usize,
+        control: &BatchControl,
+    ) -> Result<BatchOperationResult> {
+        let mut succeeded = Vec::new();
+        let mut failures = Vec::new();
+        let mut cancelled = false;
+
+        for chunk in message_ids.chunks(batch_size.clamp(1, MAX_BATCH_DELETE_IDS)) {
+            if control.cancel.is_cancelled() {
+                cancelled = true;
+                break;
+            }
+
+            match self.batch_delete_chunk(chunk).await {
+                Ok(()) => succeeded.extend_from_slice(chunk),
+                Err(e) => {
+                    let err = e.to_string();
+                    failures.extend(chunk.iter().map(|id| (id.clone(), err.clone())));
+                }
+            }
+
+            if let Some(ref progress) = control.progress {
+                progress(succeeded.len() + failures.len(), message_ids.len());
+            }
         }
+
+        Ok(BatchOperationResult {
+            success_count: succeeded.len(),
+            failure_count: failures.len(),
+            succeeded,
+            failures,
+            cancelled,
+        })
     }
 
-    /// Delete a message by moving it to trash
-    /// 
-    /// Note: This moves the message to trash rather than permanently deleting it.
-    /// The gmail.modify scope doesn't allow permanent deletion, so we use the
-    /// safer trash approach which works with standard OAuth scopes.
-    pub async fn delete_message(&self, message_id: &str) -> Result<()> {
-        // Use Gmail's trash endpoint which works with gmail.modify scope
+    /// One `messages/batchDelete` call for at most `MAX_BATCH_DELETE_IDS` IDs
+    async fn batch_delete_chunk(&self, ids: &[String]) -> Result<()> {
+        let url = format!("{}/batchDelete", self.messages_url());
         let token = self.access_token().await?;
-        let url = format!("{}/{}/trash", Self::messages_url(), message_id);
-
+        self.quota.acquire(ApiMethod::MessagesBatchDelete).await;
         let response = self
             .http_client
             .post(&url)
             .bearer_auth(&token)
-            .header("Content-Length", "0")
+            .json(&BatchDeleteRequest { ids: ids.to_vec() })
             .send()
             .await?;
 
-        if response.status().is_success() {
-            Ok(())
-        } else if response.status().as_u16() == 404 {
-            Err(GmailMcpError::Gmail(GmailApiError::MessageNotFound {
-                message_id: message_id.to_string(),
+        if response.status().as_u16() == 403 {
+            Err(GmailMcpError::Gmail(GmailApiError::InsufficientPermissions {
+                scope: scopes::FULL_ACCESS.to_string(),
             }))
-        } else {
+        } else if response.status().as_u16() == 401 {
+            let text = response.text().await.unwrap_or_default();
+            Err(GmailMcpError::Gmail(GmailApiError::Unauthorized { message: text }))
+        } else if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
             Err(GmailMcpError::Gmail(GmailApiError::RequestFailed {
-                message: format!("Failed to trash message ({}): {}", status, text),
+                message: format!("Failed to delete messages ({}): {}", status, text),
             }))
+        } else {
+            Ok(())
         }
     }
 
     /// Download an attachment
+    ///
+    /// Gmail returns the whole attachment in one response, so a dropped
+    /// connection can't be resumed; transient failures are instead retried
+    /// with exponential backoff, up to the configured number of times.
     pub async fn get_attachment(
         &self,
         message_id: &str,
         attachment_id: &str,
     ) -> Result<AttachmentData> {
-        let token = self.access_token().await?;
+        self.with_attachment_retry(|| async {
+            self.with_auth_retry(|token| async move {
+                let response = self.attachment_response(message_id, attachment_id, &token).await?;
+                Ok(response.json().await?)
+            })
+            .await
+        })
+        .await
+    }
+
+    /// Download an attachment straight into a file at `path`, returning its size
+    ///
+    /// The response is decoded as it arrives, so memory use stays bounded by
+    /// the network chunk size however large the attachment is. Transient
+    /// failures are retried like `get_attachment`, rewriting the file. The
+    /// data goes to a temp file that replaces `path` only once the download
+    /// succeeds, so a failure never touches an existing file there.
+    pub async fn save_attachment(
+        &self,
+        message_id: &str,
+        attachment_id: &str,
+        path: &Path,
+    ) -> Result<u64> {
+        write_via_temp_file(path, |tmp_path| async move {
+            let tmp_path = &tmp_path;
+            self.with_attachment_retry(|| async {
+                self.with_auth_retry(|token| async move {
+                    let mut response = self.attachment_response(message_id, attachment_id, &token).await?;
+                    let file = std::io::BufWriter::new(std::fs::File::create(tmp_path)?);
+                    let mut extractor = AttachmentDataExtractor::new(file);
+                    while let Some(chunk) = response.chunk().await? {
+                        extractor.write(&chunk)?;
+                    }
+                    let (mut file, size) = extractor.finish()?;
+                    file.flush()?;
+                    Ok(size)
+                })
+                .await
+            })
+            .await
+        })
+        .await
+    }
+
+    /// Run an attachment request, retrying transient failures with exponential backoff
+    async fn with_attachment_retry<T, F, Fut>(&self, op: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match op().await {
+                Err(e) if attempt < self.attachment_retries && is_transient(&e) => {
+                    let mut delay = attachment_retry_delay(attempt);
+                    if let GmailMcpError::Gmail(GmailApiError::RateLimited { retry_after_secs }) = e {
+                        delay = delay.max(Duration::from_secs(retry_after_secs));
+                    }
+                    attempt += 1;
+                    tracing::warn!(
+                        "Attachment fetch failed ({}), retrying in {:?} (attempt {} of {})",
+                        e,
+                        delay,
+                        attempt,
+                        self.attachment_retries
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                outcome => return outcome,
+            }
+        }
+    }
+
+    /// Request an attachment, returning the response once its status is a success
+    async fn attachment_response(
+        &self,
+        message_id: &str,
+        attachment_id: &str,
+        token: &str,
+    ) -> Result<reqwest::Response> {
         let url = format!(
             "{}/{}/attachments/{}",
-            Self::messages_url(),
+            self.messages_url(),
             message_id,
             attachment_id
         );
 
+        self.quota.acquire(ApiMethod::AttachmentsGet).await;
         let response = self
             .http_client
-            .get(&url)
-            .bearer_auth(&token)
+            .get(url)
+            .bearer_auth(token)
             .send()
             .await?;
 
         if response.status().is_success() {
-            Ok(response.json().await?)
+            Ok(response)
         } else if response.status().as_u16() == 404 {
             Err(GmailMcpError::Gmail(GmailApiError::AttachmentNotFound {
                 attachment_id: attachment_id.to_string(),
             }))
-        } else {
+        } else if response.status().as_u16() == 429 {
+            let retry_after_secs = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(1);
+            Err(GmailMcpError::Gmail(GmailApiError::RateLimited { retry_after_secs }))
+        } else if response.status().is_server_error() {
             let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            Err(GmailMcpError::Gmail(GmailApiError::RequestFailed {
-                message: format!("Failed to get attachment ({}): {}", status, text),
+            Err(GmailMcpError::Gmail(GmailApiError::Unavailable {
+                message: format!("get attachment ({}): {}", status, response.text().await.unwrap_or_default()),
             }))
+        } else {
+            Err(request_error(response, "get attachment").await)
         }
     }
 
     // ==================== Batch Operations ====================
 
+    /// Make sure the access token will outlive the next chunk of a batch
+    ///
+    /// `access_token` already refreshes tokens within five minutes of expiry;
+    /// calling it up front keeps a long batch from drifting past the expiry
+    /// mid-chunk and turning into a run of 401 failures.
+    async fn ensure_token_fresh(&self) -> Result<()> {
+        if let Some(remaining) = self.authenticator.token_expires_in().await {
+            tracing::debug!("Access token expires in {}s", remaining);
+        }
+        self.access_token().await.map(|_| ())
+    }
+
     /// Batch modify messages
     pub async fn batch_modify_messages(
         &self,
@@ -405,27 +1217,35 @@ impl GmailClient {
         add_label_ids: Option<Vec<String>>,
         remove_label_ids: Option<Vec<String>>,
         batch_size: usize,
+        control: &BatchControl,
     ) -> Result<BatchOperationResult> {
-        let mut successes = Vec::new();
-        let mut failures = Vec::new();
-
-        for chunk in message_ids.chunks(batch_size) {
-            for message_id in chunk {
-                match self
-                    .modify_message(message_id, add_label_ids.clone(), remove_label_ids.clone())
-                    .await
-                {
-                    Ok(_) => successes.push(message_id.clone()),
-                    Err(e) => failures.push((message_id.clone(), e.to_string())),
+        validate_label_changes(add_label_ids.as_deref(), remove_label_ids.as_deref())?;
+
+        let result = run_batch(
+            message_ids,
+            batch_size,
+            control,
+            |message_id| {
+                let add = add_label_ids.clone();
+                let remove = remove_label_ids.clone();
+                async move {
+                    self.modify_message(&message_id, add, remove)
+                        .await
+                        .map(|_| ())
                 }
-            }
-        }
+            },
+            |mode| async move {
+                match mode {
+                    TokenCheck::BeforeChunk => self.ensure_token_fresh().await,
+                    TokenCheck::AfterUnauthorized => {
+                        self.authenticator.refresh_token().await.map(|_| ())
+                    }
+                }
+            },
+        )
+        .await;
 
-        Ok(BatchOperationResult {
-            success_count: successes.len(),
-            failure_count: failures.len(),
-            failures,
-        })
+        Ok(result)
     }
 
     /// Batch delete messages
@@ -433,24 +1253,73 @@ impl GmailClient {
         &self,
         message_ids: &[String],
         batch_size: usize,
+        control: &BatchControl,
     ) -> Result<BatchOperationResult> {
-        let mut successes = Vec::new();
+        let result = run_batch(
+            message_ids,
+            batch_size,
+            control,
+            |message_id| async move { self.delete_message(&message_id).await },
+            |mode| async move {
+                match mode {
+                    TokenCheck::BeforeChunk => self.ensure_token_fresh().await,
+                    TokenCheck::AfterUnauthorized => {
+                        self.authenticator.refresh_token().await.map(|_| ())
+                    }
+                }
+            },
+        )
+        .await;
+
+        Ok(result)
+    }
+
+    /// Send each message separately, one at a time
+    ///
+    /// Sends are sequential with `interval` between them, which keeps a mail
+    /// merge under Gmail's per-user sending rate. Successes record the new
+    /// message IDs; failures are keyed by the item's position and recipients.
+    pub async fn send_email_batch(
+        &self,
+        messages: Vec<EmailParams>,
+        interval: Duration,
+        control: &BatchControl,
+    ) -> BatchOperationResult {
+        let total = messages.len();
+        let mut succeeded = Vec::new();
         let mut failures = Vec::new();
+        let mut cancelled = false;
 
-        for chunk in message_ids.chunks(batch_size) {
-            for message_id in chunk {
-                match self.delete_message(message_id).await {
-                    Ok(_) => successes.push(message_id.clone()),
-                    Err(e) => failures.push((message_id.clone(), e.to_string())),
+        for (index, params) in messages.into_iter().enumerate() {
+            if index > 0 {
+                tokio::select! {
+                    _ = control.cancel.cancelled() => {}
+                    _ = tokio::time::sleep(interval) => {}
                 }
             }
+            if control.cancel.is_cancelled() {
+                cancelled = true;
+                break;
+            }
+
+            let item = format!("#{} to {}", index + 1, params.to.join(", "));
+            match self.send_email(params).await {
+                Ok(sent) => succeeded.push(sent.message.id),
+                Err(e) => failures.push((item, e.to_string())),
+            }
+
+            if let Some(ref progress) = control.progress {
+                progress(succeeded.len() + failures.len(), total);
+            }
         }
 
-        Ok(BatchOperationResult {
-            success_count: successes.len(),
+        BatchOperationResult {
+            success_count: succeeded.len(),
             failure_count: failures.len(),
+            succeeded,
             failures,
-        })
+            cancelled,
+        }
     }
 
     // ==================== Label Operations ====================
@@ -458,10 +1327,26 @@ impl GmailClient {
     /// List all labels
     pub async fn list_labels(&self) -> Result<LabelListResult> {
         let token = self.access_token().await?;
-        let manager = LabelManager::new(&self.http_client, &token);
+        let manager = self.labels(&token);
         manager.list().await
     }
 
+    /// List all labels, fetching message counts for user labels
+    ///
+    /// Costs one extra request per user label; see `LabelManager::list_with_counts`.
+    pub async fn list_labels_with_counts(&self) -> Result<LabelListResult> {
+        let token = self.access_token().await?;
+        let manager = self.labels(&token);
+        manager.list_with_counts().await
+    }
+
+    /// Get a label by ID or name, with message and unread counts
+    pub async fn get_label(&self, id_or_name: &str) -> Result<Label> {
+        let token = self.access_token().await?;
+        let manager = self.labels(&token);
+        manager.get_by_id_or_name(id_or_name).await
+    }
+
     /// Create a label
     pub async fn create_label(
         &self,
@@ -470,7 +1355,7 @@ impl GmailClient {
         label_list_visibility: Option<&str>,
     ) -> Result<Label> {
         let token = self.access_token().await?;
-        let manager = LabelManager::new(&self.http_client, &token);
+        let manager = self.labels(&token);
         manager
             .create(name, message_list_visibility, label_list_visibility)
             .await
@@ -479,17 +1364,43 @@ impl GmailClient {
     /// Update a label
     pub async fn update_label(&self, label_id: &str, updates: UpdateLabelRequest) -> Result<Label> {
         let token = self.access_token().await?;
-        let manager = LabelManager::new(&self.http_client, &token);
+        let manager = self.labels(&token);
         manager.update(label_id, updates).await
     }
 
     /// Delete a label
     pub async fn delete_label(&self, label_id: &str) -> Result<()> {
         let token = self.access_token().await?;
-        let manager = LabelManager::new(&self.http_client, &token);
+        let manager = self.labels(&token);
         manager.delete(label_id).await
     }
 
+    /// Resolve label names or IDs to label IDs
+    pub async fn resolve_label_ids(
+        &self,
+        names_or_ids: Option<Vec<String>>,
+    ) -> Result<Option<Vec<String>>> {
+        let Some(values) = names_or_ids else {
+            return Ok(None);
+        };
+        let token = self.access_token().await?;
+        let manager = self.labels(&token);
+        manager.resolve_ids(&values).await.map(Some)
+    }
+
+    /// Resolve label names or IDs to label IDs, creating any missing labels
+    pub async fn resolve_or_create_label_ids(
+        &self,
+        names_or_ids: Option<Vec<String>>,
+    ) -> Result<Option<Vec<String>>> {
+        let Some(values) = names_or_ids else {
+            return Ok(None);
+        };
+        let token = self.access_token().await?;
+        let manager = self.labels(&token);
+        manager.resolve_or_create_ids(&values).await.map(Some)
+    }
+
     /// Get or create a label
     pub async fn get_or_create_label(
         &self,
@@ -498,7 +1409,7 @@ impl GmailClient {
         label_list_visibility: Option<&str>,
     ) -> Result<Label> {
         let token = self.access_token().await?;
-        let manager = LabelManager::new(&self.http_client, &token);
+        let manager = self.labels(&token);
         manager
             .get_or_create(name, message_list_visibility, label_list_visibility)
             .await
@@ -509,14 +1420,14 @@ impl GmailClient {
     /// List all filters
     pub async fn list_filters(&self) -> Result<FilterListResult> {
         let token = self.access_token().await?;
-        let manager = FilterManager::new(&self.http_client, &token);
+        let manager = self.filters(&token);
         manager.list().await
     }
 
     /// Get a specific filter
     pub async fn get_filter(&self, filter_id: &str) -> Result<Filter> {
         let token = self.access_token().await?;
-        let manager = FilterManager::new(&self.http_client, &token);
+        let manager = self.filters(&token);
         manager.get(filter_id).await
     }
 
@@ -527,14 +1438,14 @@ impl GmailClient {
         action: FilterAction,
     ) -> Result<Filter> {
         let token = self.access_token().await?;
-        let manager = FilterManager::new(&self.http_client, &token);
+        let manager = self.filters(&token);
         manager.create(criteria, action).await
     }
 
     /// Delete a filter
     pub async fn delete_filter(&self, filter_id: &str) -> Result<()> {
         let token = self.access_token().await?;
-        let manager = FilterManager::new(&self.http_client, &token);
+        let manager = self.filters(&token);
         manager.delete(filter_id).await
     }
 }
@@ -553,6 +1464,42 @@ pub struct ReadMessageResult {
     pub html_body: Option<String>,
     pub is_html_only: bool,
     pub attachments: Vec<EmailAttachment>,
+    /// All message headers in their original order, duplicates included
+    pub headers: Vec<Header>,
+    /// Current label IDs (e.g. UNREAD, STARRED)
+    pub label_ids: Vec<String>,
+    /// Estimated size in bytes
+    pub size_estimate: Option<i64>,
+    /// When Gmail received the message, as RFC 3339 UTC
+    pub received: Option<String>,
+    /// Per-recipient results when the message is a bounce report
+    pub delivery_status: Vec<DeliveryStatus>,
+    /// Gmail returned no payload, so headers and body are missing and `body` is the snippet
+    pub content_unavailable: bool,
+}
+
+/// Result of sending an email
+#[derive(Debug, Clone)]
+pub struct SendEmailResult {
+    /// The sent message as returned by Gmail
+    pub message: Message,
+    /// The `Message-ID` header written into the message
+    pub message_id_header: String,
+}
+
+/// Threading headers for replying to a message
+#[derive(Debug, Clone)]
+pub struct ReplyContext {
+    /// The original's `Message-ID`, used as `In-Reply-To`
+    pub in_reply_to: String,
+    /// The original's `References` chain with its `Message-ID` appended
+    pub references: String,
+    /// Thread the original belongs to
+    pub thread_id: Option<String>,
+    /// Where a reply to the sender goes: the original's `Reply-To`, else its `From`
+    pub reply_recipients: Vec<String>,
+    /// The original's subject with a "Re: " prefix
+    pub subject: String,
 }
 
 /// Result of searching messages
@@ -564,6 +1511,53 @@ pub struct SearchMessageResult {
     pub subject: String,
     pub from: String,
     pub date: String,
+    /// When Gmail received the message, as RFC 3339 UTC
+    pub received: Option<String>,
+}
+
+/// Messages added since a history ID
+#[derive(Debug, Clone)]
+pub struct HistoryChanges {
+    /// IDs of the added messages, oldest first
+    pub message_ids: Vec<String>,
+    /// The mailbox's history ID when the listing was made
+    pub history_id: String,
+}
+
+/// A message's headers and snippet, fetched without the body
+#[derive(Debug, Clone)]
+pub struct MessageSummary {
+    pub id: String,
+    pub thread_id: String,
+    pub subject: String,
+    pub from: String,
+    pub to: String,
+    pub date: String,
+    pub label_ids: Vec<String>,
+    pub snippet: String,
+}
+
+/// Result of a snippet-only search
+#[derive(Debug, Clone)]
+pub struct SearchSnippetResult {
+    pub id: String,
+    pub thread_id: String,
+    pub snippet: String,
+    /// When Gmail received the message, as RFC 3339 UTC
+    pub received: Option<String>,
+}
+
+/// Options for importing a raw message
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Label IDs to apply to the imported message
+    pub label_ids: Vec<String>,
+
+    /// Never send the message to spam
+    pub never_mark_spam: bool,
+
+    /// Source of the internal date: "receivedTime" or "dateHeader"
+    pub internal_date_source: Option<String>,
 }
 
 /// Result of a batch operation
@@ -571,11 +1565,703 @@ pub struct SearchMessageResult {
 pub struct BatchOperationResult {
     pub success_count: usize,
     pub failure_count: usize,
+    /// IDs of the items that succeeded (for sends, the new message IDs)
+    pub succeeded: Vec<String>,
     pub failures: Vec<(String, String)>,
+    /// Whether the operation was cancelled before every message was processed
+    pub cancelled: bool,
+}
+
+/// Callback receiving `(processed, total)` message counts
+pub type ProgressCallback = Arc<dyn Fn(usize, usize) + Send + Sync>;
+
+/// Controls for a long-running batch operation
+#[derive(Clone, Default)]
+pub struct BatchControl {
+    /// Stops the batch before the next message when cancelled
+    pub cancel: CancellationToken,
+
+    /// Called after each chunk with the number of messages processed so far
+    pub progress: Option<ProgressCallback>,
+}
+
+/// One page of a Gmail list endpoint
+struct Page<T> {
+    items: Vec<T>,
+    next_page_token: Option<String>,
+}
+
+/// Collect items across the pages of a list endpoint
+///
+/// `fetch_page` is called with the page token (`None` for the first page) and
+/// the page size to request, and returns that page's items and the next token.
+/// Pages are followed until there is no next token or `cap` items have been
+/// collected; page sizes shrink so no more than `cap` items are requested.
+async fn paginate<T, F, Fut>(cap: Option<usize>, mut fetch_page: F) -> Result<Vec<T>>
+where
+    F: FnMut(Option<String>, u32) -> Fut,
+    Fut: Future<Output = Result<Page<T>>>,
+{
+    let mut items = Vec::new();
+    let mut page_token = None;
+
+    loop {
+        let remaining = cap.map_or(usize::MAX, |cap| cap.saturating_sub(items.len()));
+        if remaining == 0 {
+            return Ok(items);
+        }
+
+        let page = fetch_page(page_token.take(), remaining.min(MAX_LIST_PAGE_SIZE as usize) as u32).await?;
+        items.extend(page.items);
+        if let Some(cap) = cap {
+            items.truncate(cap);
+        }
+
+        match page.next_page_token {
+            Some(token) => page_token = Some(token),
+            None => return Ok(items),
+        }
+    }
+}
+
+/// A client that only connects to `url`'s host at its public addresses
+///
+/// The host is resolved here and checked with `is_public_ip`; the client is
+/// pinned to those addresses so a second lookup can't swap in a private one.
+/// Redirects are left to the caller so each hop gets the same check.
+async fn public_host_client(url: &reqwest::Url) -> std::result::Result<reqwest::Client, String> {
+    let host = url.host_str().ok_or_else(|| format!("URL has no host: {}", url))?;
+    let port = url.port_or_known_default().unwrap_or(443);
+    // IPv6 literals keep their brackets in `host_str`
+    let (domain, addrs) = match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        Ok(ip) => (None, vec![SocketAddr::new(ip, port)]),
+        Err(_) => {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+                .await
+                .map_err(|e| format!("Could not resolve {}: {}", host, e))?
+                .collect();
+            (Some(host), addrs)
+        }
+    };
+
+    if addrs.is_empty() {
+        return Err(format!("Could not resolve {}", host));
+    }
+    if let Some(private) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        return Err(format!(
+            "Refusing to download from {}: it resolves to the non-public address {}",
+            host,
+            private.ip()
+        ));
+    }
+
+    let mut builder = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+    if let Some(domain) = domain {
+        builder = builder.resolve_to_addrs(domain, &addrs);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// Number of results a search returns for the requested `maxResults`
+pub(crate) fn search_cap(max_results: Option<u32>) -> usize {
+    max_results.unwrap_or(DEFAULT_SEARCH_RESULTS).min(MAX_SEARCH_RESULTS) as usize
+}
+
+/// Let `write` fill a temp file beside `path`, then move it into place
+///
+/// The temp file is removed if `write` or the rename fails, leaving whatever
+/// was at `path` untouched.
+async fn write_via_temp_file<F, Fut>(path: &Path, write: F) -> Result<u64>
+where
+    F: FnOnce(PathBuf) -> Fut,
+    Fut: Future<Output = Result<u64>>,
+{
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp_path = dir.join(format!(".{}.{}.part", file_name, uuid::Uuid::new_v4().simple()));
+
+    let outcome = match write(tmp_path.clone()).await {
+        Ok(size) => std::fs::rename(&tmp_path, path).map(|()| size).map_err(GmailMcpError::from),
+        Err(e) => Err(e),
+    };
+    if outcome.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    outcome
+}
+
+/// Exponential backoff before attachment retry number `attempt + 1`, capped
+fn attachment_retry_delay(attempt: u32) -> Duration {
+    2u32.checked_pow(attempt)
+        .map_or(MAX_ATTACHMENT_RETRY_DELAY, |factor| ATTACHMENT_RETRY_DELAY.saturating_mul(factor))
+        .min(MAX_ATTACHMENT_RETRY_DELAY)
+}
+
+/// Whether a failed request is worth retrying: dropped connections, timeouts,
+/// truncated bodies, rate limiting and server errors
+fn is_transient(error: &GmailMcpError) -> bool {
+    match error {
+        GmailMcpError::Http(e) => e.is_timeout() || e.is_connect() || e.is_request() || e.is_body() || e.is_decode(),
+        GmailMcpError::Gmail(GmailApiError::RateLimited { .. } | GmailApiError::Unavailable { .. }) => true,
+        _ => false,
+    }
+}
+
+/// Build the error for a failed Gmail response, keeping 401s distinct so callers can retry
+async fn request_error(response: reqwest::Response, action: &str) -> GmailMcpError {
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+
+    if status.as_u16() == 401 {
+        GmailMcpError::Gmail(GmailApiError::Unauthorized { message: text })
+    } else {
+        GmailMcpError::Gmail(GmailApiError::RequestFailed {
+            message: format!("Failed to {} ({}): {}", action, status, text),
+        })
+    }
+}
+
+/// Parse a fetched message into its headers, body and attachments
+///
+/// A message without a payload has no headers or body to parse; the result
+/// then carries only the snippet and is flagged `content_unavailable`.
+pub(crate) fn read_message_from(message: Message) -> ReadMessageResult {
+
+    let payload = message.payload.as_ref();
+    let snippet = message.snippet.clone();
+
+    let subject = payload
+        .and_then(|p| find_header(p, "subject"))
+        .unwrap_or("")
+        .to_string();
+
+    let from = payload
+        .and_then(|p| find_header(p, "from"))
+        .unwrap_or("")
+        .to_string();
+
+    let to = payload
+        .and_then(|p| find_header(p, "to"))
+        .unwrap_or("")
+        .to_string();
+
+    let date = payload
+        .and_then(|p| find_header(p, "date"))
+        .unwrap_or("")
+        .to_string();
+
+    let headers = payload.map(get_message_headers).unwrap_or_default();
+
+    let content = payload
+        .map(extract_email_content)
+        .unwrap_or_default();
+
+    let attachments = payload
+        .map(extract_attachments)
+        .unwrap_or_default();
+
+    let delivery_status = payload
+        .map(extract_delivery_status)
+        .unwrap_or_default();
+
+    // Check if body extraction failed (for logging)
+    let extraction_failed = content.text.is_empty() && content.html.is_empty();
+    
+    // Determine body content with fallback to snippet
+    let is_html_only = content.text.is_empty() && !content.html.is_empty();
+    let (body, html_body) = if !content.text.is_empty() {
+        let html = if content.html.is_empty() { None } else { Some(content.html) };
+        (content.text, html)
+    } else if !content.html.is_empty() {
+        // Convert HTML to readable text for the body
+        let text_from_html = html_to_text(&content.html);
+        (text_from_html, Some(content.html))
+    } else {
+        // Fallback to snippet if body extraction failed
+        (snippet.unwrap_or_default(), None)
+    };
+
+    // Log if we had to fall back to snippet
+    if extraction_failed {
+        tracing::debug!(
+            "Email {} body extraction returned empty, using snippet fallback",
+            message.id
+        );
+    }
+
+    let content_unavailable = message.payload.is_none();
+
+    ReadMessageResult {
+        id: message.id,
+        thread_id: message.thread_id.unwrap_or_default(),
+        subject,
+        from,
+        to,
+        date,
+        body,
+        html_body,
+        is_html_only,
+        attachments,
+        headers,
+        label_ids: message.label_ids,
+        size_estimate: message.size_estimate,
+        received: message.internal_date.as_deref().and_then(internal_date_to_rfc3339),
+        delivery_status,
+        content_unavailable,
+    }
+}
+
+/// Threading headers, recipients and subject for replying to `message`
+pub(crate) fn reply_context_for(message: &Message) -> Result<ReplyContext> {
+    let payload = message.payload.as_ref();
+    let header = |name: &str| payload.and_then(|p| find_header(p, name));
+
+    let original_id = header("message-id").ok_or_else(|| {
+        GmailMcpError::Gmail(GmailApiError::RequestFailed {
+            message: format!("Message {} has no Message-ID header to reply to", message.id),
+        })
+    })?;
+
+    Ok(ReplyContext {
+        in_reply_to: original_id.to_string(),
+        references: build_references(original_id, header("references"), header("in-reply-to")),
+        thread_id: message.thread_id.clone(),
+        reply_recipients: header("reply-to")
+            .or(header("from"))
+            .map(extract_addresses)
+            .unwrap_or_default(),
+        subject: reply_subject(header("subject").unwrap_or_default()),
+    })
+}
+
+/// Newest message of a thread that isn't a draft
+pub(crate) fn latest_reply_target(messages: &[Message]) -> Option<&Message> {
+    messages
+        .iter()
+        .rev()
+        .find(|m| !m.label_ids.iter().any(|label| label == "DRAFT"))
+}
+
+/// Reject a message Gmail would refuse for size, returning its size otherwise
+pub(crate) fn check_email_size(raw_message: &str) -> Result<usize> {
+    let size = raw_message.len();
+    if size > MAX_EMAIL_BYTES {
+        return Err(GmailMcpError::Gmail(GmailApiError::MessageTooLarge {
+            size,
+            limit: MAX_EMAIL_BYTES,
+        }));
+    }
+    Ok(size)
+}
+
+/// Build the error for a failed send or draft, recognizing oversized messages
+///
+/// Gmail reports these as a 413, or as a 400 whose message mentions the size.
+async fn send_error(response: reqwest::Response, action: &str, size: usize) -> GmailMcpError {
+    let status = response.status();
+    if !matches!(status.as_u16(), 400 | 413) {
+        return request_error(response, action).await;
+    }
+
+    let text = response.text().await.unwrap_or_default();
+    if status.as_u16() == 413 || is_message_too_large(&text) {
+        GmailMcpError::Gmail(GmailApiError::MessageTooLarge {
+            size,
+            limit: MAX_EMAIL_BYTES,
+        })
+    } else {
+        GmailMcpError::Gmail(GmailApiError::RequestFailed {
+            message: format!("Failed to {} ({}): {}", action, status, text),
+        })
+    }
+}
+
+/// Whether a Gmail error body says the message exceeded the size limit
+fn is_message_too_large(body: &str) -> bool {
+    let body = body.to_ascii_lowercase();
+    ["too large", "exceeds the limit", "exceeds the maximum", "size limit"]
+        .iter()
+        .any(|phrase| body.contains(phrase))
+}
+
+/// Token check requested by `run_batch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenCheck {
+    /// Refresh only if the token is close to expiry
+    BeforeChunk,
+    /// Force a refresh after the API rejected the token
+    AfterUnauthorized,
+}
+
+/// Run `op` over every message id in chunks of `batch_size`
+///
+/// `refresh` is asked to check the token before each chunk, and to force a
+/// refresh when an item fails with a 401; the failed item is then retried once.
+/// Once the control's token is cancelled, the remaining messages are skipped
+/// and the partial result is returned. Progress is reported after each chunk.
+async fn run_batch<Op, OpFut, Refresh, RefreshFut>(
+    message_ids: &[String],
+    batch_size: usize,
+    control: &BatchControl,
+    mut op: Op,
+    mut refresh: Refresh,
+) -> BatchOperationResult
+where
+    Op: FnMut(String) -> OpFut,
+    OpFut: Future<Output = Result<()>>,
+    Refresh: FnMut(TokenCheck) -> RefreshFut,
+    RefreshFut: Future<Output = Result<()>>,
+{
+    let mut successes = Vec::new();
+    let mut failures = Vec::new();
+    let mut cancelled = false;
+
+    'chunks: for chunk in message_ids.chunks(batch_size.max(1)) {
+        if let Err(e) = refresh(TokenCheck::BeforeChunk).await {
+            tracing::warn!("Token check before batch chunk failed: {}", e);
+        }
+
+        for message_id in chunk {
+            if control.cancel.is_cancelled() {
+                cancelled = true;
+                break 'chunks;
+            }
+
+            let mut outcome = op(message_id.clone()).await;
+
+            if let Err(GmailMcpError::Gmail(GmailApiError::Unauthorized { .. })) = outcome {
+                outcome = match refresh(TokenCheck::AfterUnauthorized).await {
+                    Ok(()) => op(message_id.clone()).await,
+                    Err(e) => Err(e),
+                };
+            }
+
+            match outcome {
+                Ok(()) => successes.push(message_id.clone()),
+                Err(e) => failures.push((message_id.clone(), e.to_string())),
+            }
+        }
+
+        if let Some(ref progress) = control.progress {
+            progress(successes.len() + failures.len(), message_ids.len());
+        }
+    }
+
+    BatchOperationResult {
+        success_count: successes.len(),
+        failure_count: failures.len(),
+        succeeded: successes,
+        failures,
+        cancelled,
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    // Integration tests would go here
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_public_host_client_refuses_local_hosts() {
+        for url in ["http://127.0.0.1:8080/a.pdf", "http://[::1]/a.pdf", "http://169.254.169.254/", "http://localhost/"] {
+            let err = public_host_client(&reqwest::Url::parse(url).unwrap()).await.unwrap_err();
+            assert!(err.contains("non-public address"), "{}: {}", url, err);
+        }
+        assert!(public_host_client(&reqwest::Url::parse("https://93.184.216.34/").unwrap()).await.is_ok());
+    }
+
+    #[test]
+    fn test_read_message_without_payload() {
+        let message: Message = serde_json::from_value(serde_json::json!({
+            "id": "m1",
+            "threadId": "t1",
+            "labelIds": ["INBOX"],
+            "snippet": "See you at noon"
+        }))
+        .unwrap();
+
+        let result = read_message_from(message);
+        assert!(result.content_unavailable);
+        assert_eq!(result.body, "See you at noon");
+        assert_eq!(result.subject, "");
+        assert!(result.headers.is_empty());
+        assert_eq!(result.label_ids, vec!["INBOX"]);
+    }
+
+    #[test]
+    fn test_thread_reply_context_skips_drafts() {
+        let thread: Thread = serde_json::from_value(serde_json::json!({
+            "id": "t1",
+            "messages": [
+                {
+                    "id": "m1",
+                    "threadId": "t1",
+                    "labelIds": ["INBOX"],
+                    "payload": {"headers": [
+                        {"name": "Message-ID", "value": "<first@example.com>"},
+                        {"name": "From", "value": "Alice <alice@example.com>"},
+                        {"name": "Subject", "value": "Plans"}
+                    ]}
+                },
+                {
+                    "id": "m2",
+                    "threadId": "t1",
+                    "labelIds": ["SENT"],
+                    "payload": {"headers": [
+                        {"name": "Message-ID", "value": "<second@example.com>"},
+                        {"name": "In-Reply-To", "value": "<first@example.com>"},
+                        {"name": "References", "value": "<first@example.com>"},
+                        {"name": "From", "value": "me@example.com"},
+                        {"name": "Subject", "value": "Re: Plans"}
+                    ]}
+                },
+                {"id": "m3", "threadId": "t1", "labelIds": ["DRAFT"]}
+            ]
+        }))
+        .unwrap();
+
+        let latest = latest_reply_target(&thread.messages).unwrap();
+        assert_eq!(latest.id, "m2");
+
+        let ctx = reply_context_for(latest).unwrap();
+        assert_eq!(ctx.thread_id.as_deref(), Some("t1"));
+        assert_eq!(ctx.in_reply_to, "<second@example.com>");
+        assert_eq!(ctx.references, "<first@example.com> <second@example.com>");
+        assert_eq!(ctx.subject, "Re: Plans");
+    }
+
+    #[tokio::test]
+    async fn test_paginate_follows_tokens_up_to_cap() {
+        // Three pages of three items: 0..3, 3..6, 6..9
+        let fetch = |requested: &Mutex<Vec<(Option<String>, u32)>>, token: Option<String>, size: u32| {
+            requested.lock().unwrap().push((token.clone(), size));
+            let start: usize = token.as_deref().map_or(0, |t| t.parse().unwrap());
+            let end = (start + 3).min(9);
+            async move {
+                Ok(Page {
+                    items: (start..end).collect::<Vec<_>>(),
+                    next_page_token: (end < 9).then(|| end.to_string()),
+                })
+            }
+        };
+
+        let requested = Mutex::new(Vec::new());
+        let all = paginate(None, |token, size| fetch(&requested, token, size)).await.unwrap();
+        assert_eq!(all, (0..9).collect::<Vec<_>>());
+        assert_eq!(requested.lock().unwrap().len(), 3);
+
+        let requested = Mutex::new(Vec::new());
+        let capped = paginate(Some(5), |token, size| fetch(&requested, token, size)).await.unwrap();
+        assert_eq!(capped, vec![0, 1, 2, 3, 4]);
+        assert_eq!(
+            *requested.lock().unwrap(),
+            vec![(None, 5), (Some("3".to_string()), 2)]
+        );
+
+        let none = paginate(Some(0), |token, size| fetch(&requested, token, size)).await.unwrap();
+        assert!(none.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_download_keeps_existing_file() {
+        let dir = std::env::temp_dir().join(format!("gmail-mcp-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("report.pdf");
+        std::fs::write(&path, b"existing").unwrap();
+        let failed = || GmailMcpError::Gmail(GmailApiError::AttachmentNotFound { attachment_id: "a1".to_string() });
+
+        // Failing before anything is written, and after a partial write
+        let before = write_via_temp_file(&path, |_| async { Err(failed()) }).await;
+        assert!(before.is_err());
+        let partial = write_via_temp_file(&path, |tmp| async move {
+            std::fs::write(tmp, b"partial")?;
+            Err(failed())
+        })
+        .await;
+        assert!(partial.is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"existing");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let saved = write_via_temp_file(&path, |tmp| async move {
+            std::fs::write(tmp, b"new")?;
+            Ok(3)
+        })
+        .await;
+        assert_eq!(saved.unwrap(), 3);
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_attachment_retry_delay_is_capped() {
+        assert_eq!(attachment_retry_delay(0), Duration::from_millis(500));
+        assert_eq!(attachment_retry_delay(2), Duration::from_secs(2));
+        assert_eq!(attachment_retry_delay(10), MAX_ATTACHMENT_RETRY_DELAY);
+        assert_eq!(attachment_retry_delay(40), MAX_ATTACHMENT_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_is_message_too_large() {
+        assert!(is_message_too_large(
+            r#"{"error": {"code": 400, "message": "Request payload size exceeds the limit: 36700160 bytes."}}"#
+        ));
+        assert!(is_message_too_large(r#"{"error": {"message": "Message too large"}}"#));
+        assert!(!is_message_too_large(r#"{"error": {"message": "Invalid To header"}}"#));
+    }
+
+    fn ids(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("msg{}", i)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_run_batch_refreshes_and_retries_after_expiry() {
+        // The token "expires" once the third message has been processed
+        let processed = AtomicUsize::new(0);
+        let token_valid = AtomicBool::new(true);
+        let expired = AtomicBool::new(false);
+        let forced_refreshes = AtomicUsize::new(0);
+
+        let result = run_batch(
+            &ids(6),
+            2,
+            &BatchControl::default(),
+            |_id| {
+                let processed = &processed;
+                let token_valid = &token_valid;
+                let expired = &expired;
+                async move {
+                    if processed.load(Ordering::SeqCst) == 3 && !expired.swap(true, Ordering::SeqCst) {
+                        token_valid.store(false, Ordering::SeqCst);
+                    }
+                    if !token_valid.load(Ordering::SeqCst) {
+                        return Err(GmailMcpError::Gmail(GmailApiError::Unauthorized {
+                            message: "expired".to_string(),
+                        }));
+                    }
+                    processed.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            },
+            |mode| {
+                let token_valid = &token_valid;
+                let forced_refreshes = &forced_refreshes;
+                async move {
+                    if mode == TokenCheck::AfterUnauthorized {
+                        forced_refreshes.fetch_add(1, Ordering::SeqCst);
+                        token_valid.store(true, Ordering::SeqCst);
+                    }
+                    Ok(())
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(result.success_count, 6);
+        assert_eq!(result.failure_count, 0);
+        assert_eq!(forced_refreshes.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_batch_reports_failure_when_refresh_fails() {
+        let result = run_batch(
+            &ids(2),
+            50,
+            &BatchControl::default(),
+            |_id| async {
+                Err(GmailMcpError::Gmail(GmailApiError::Unauthorized {
+                    message: "revoked".to_string(),
+                }))
+            },
+            |mode| async move {
+                match mode {
+                    TokenCheck::BeforeChunk => Ok(()),
+                    TokenCheck::AfterUnauthorized => {
+                        Err(GmailMcpError::Gmail(GmailApiError::RequestFailed {
+                            message: "refresh failed".to_string(),
+                        }))
+                    }
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(result.success_count, 0);
+        assert_eq!(result.failure_count, 2);
+        assert!(result.failures[0].1.contains("refresh failed"));
+    }
+
+    #[tokio::test]
+    async fn test_run_batch_checks_token_before_each_chunk() {
+        let checks = AtomicUsize::new(0);
+
+        run_batch(
+            &ids(5),
+            2,
+            &BatchControl::default(),
+            |_id| async { Ok(()) },
+            |mode| {
+                let checks = &checks;
+                async move {
+                    if mode == TokenCheck::BeforeChunk {
+                        checks.fetch_add(1, Ordering::SeqCst);
+                    }
+                    Ok(())
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(checks.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_run_batch_stops_when_cancelled() {
+        let control = BatchControl::default();
+        let processed = AtomicUsize::new(0);
+
+        let result = run_batch(
+            &ids(10),
+            3,
+            &control,
+            |_id| {
+                let processed = &processed;
+                let cancel = &control.cancel;
+                async move {
+                    if processed.fetch_add(1, Ordering::SeqCst) == 3 {
+                        cancel.cancel();
+                    }
+                    Ok(())
+                }
+            },
+            |_mode| async { Ok(()) },
+        )
+        .await;
+
+        assert!(result.cancelled);
+        assert_eq!(result.success_count, 4);
+        assert_eq!(result.failure_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_run_batch_reports_progress_per_chunk() {
+        let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let control = BatchControl {
+            progress: Some(Arc::new(move |processed, total| {
+                sink.lock().unwrap().push((processed, total));
+            })),
+            ..Default::default()
+        };
+
+        run_batch(&ids(5), 2, &control, |_id| async { Ok(()) }, |_mode| async { Ok(()) }).await;
+
+        assert_eq!(*reports.lock().unwrap(), vec![(2, 5), (4, 5), (5, 5)]);
+    }
 }
 
@@ -3,12 +3,16 @@
 //! Provides comprehensive filter management functionality.
 
 use crate::error::{GmailApiError, GmailMcpError, Result};
+use crate::gmail::quota::{ApiMethod, QuotaTracker};
 use crate::gmail::types::{Filter, FilterAction, FilterCriteria, FilterList, SizeComparison};
+use crate::gmail::utils::QueryBuilder;
 
 /// Filter manager for Gmail operations
 pub struct FilterManager<'a> {
     client: &'a reqwest::Client,
     access_token: &'a str,
+    quota: Option<&'a QuotaTracker>,
+    user_id: &'a str,
 }
 
 impl<'a> FilterManager<'a> {
@@ -17,14 +21,35 @@ impl<'a> FilterManager<'a> {
         Self {
             client,
             access_token,
+            quota: None,
+            user_id: crate::config::gmail::USER_ID,
+        }
+    }
+
+    /// Operate on `user_id`'s mailbox instead of the authenticated user's
+    pub fn with_user_id(mut self, user_id: &'a str) -> Self {
+        self.user_id = user_id;
+        self
+    }
+
+    /// Count and rate limit this manager's requests against `quota`
+    pub fn with_quota(mut self, quota: &'a QuotaTracker) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    async fn acquire(&self, method: ApiMethod) {
+        if let Some(quota) = self.quota {
+            quota.acquire(method).await;
         }
     }
 
     /// Base URL for filters API
-    fn base_url() -> String {
+    fn base_url(&self) -> String {
         format!(
-            "{}/users/me/settings/filters",
-            crate::config::gmail::API_BASE_URL
+            "{}/users/{}/settings/filters",
+            crate::config::gmail::API_BASE_URL,
+            urlencoding::encode(self.user_id)
         )
     }
 
@@ -36,9 +61,10 @@ impl<'a> FilterManager<'a> {
             action,
         };
 
+        self.acquire(ApiMethod::FiltersCreate).await;
         let response = self
             .client
-            .post(Self::base_url())
+            .post(self.base_url())
             .bearer_auth(self.access_token)
             .json(&filter)
             .send()
@@ -64,9 +90,10 @@ impl<'a> FilterManager<'a> {
 
     /// List all Gmail filters
     pub async fn list(&self) -> Result<FilterListResult> {
+        self.acquire(ApiMethod::FiltersList).await;
         let response = self
             .client
-            .get(Self::base_url())
+            .get(self.base_url())
             .bearer_auth(self.access_token)
             .send()
             .await?;
@@ -106,8 +133,9 @@ impl<'a> FilterManager<'a> {
 
     /// Get a specific filter by ID
     pub async fn get(&self, filter_id: &str) -> Result<Filter> {
-        let url = format!("{}/{}", Self::base_url(), filter_id);
+        let url = format!("{}/{}", self.base_url(), filter_id);
 
+        self.acquire(ApiMethod::FiltersGet).await;
         let response = self
             .client
             .get(&url)
@@ -132,8 +160,9 @@ impl<'a> FilterManager<'a> {
 
     /// Delete a Gmail filter
     pub async fn delete(&self, filter_id: &str) -> Result<()> {
-        let url = format!("{}/{}", Self::base_url(), filter_id);
+        let url = format!("{}/{}", self.base_url(), filter_id);
 
+        self.acquire(ApiMethod::FiltersDelete).await;
         let response = self
             .client
             .delete(&url)
@@ -167,9 +196,171 @@ pub struct FilterListResult {
     pub count: usize,
 }
 
+/// Whether two filters match the same mail and act the same way
+///
+/// IDs are ignored, and label lists compare regardless of order.
+pub fn same_filter(a: &Filter, b: &Filter) -> bool {
+    let sorted = |labels: &Option<Vec<String>>| {
+        labels.clone().map(|mut labels| {
+            labels.sort();
+            labels
+        })
+    };
+    a.criteria == b.criteria
+        && a.action.forward == b.action.forward
+        && sorted(&a.action.add_label_ids) == sorted(&b.action.add_label_ids)
+        && sorted(&a.action.remove_label_ids) == sorted(&b.action.remove_label_ids)
+}
+
+/// Gmail search query for the mail a filter with `criteria` would catch
+///
+/// Criteria values already use search syntax, so multi-word values are
+/// grouped rather than quoted, as Gmail's "Search with these criteria" does.
+/// `negatedQuery` excludes mail containing any of its words. Blank values,
+/// and a size without a `larger`/`smaller` comparison, match everything and
+/// are left out.
+pub fn criteria_to_query(criteria: &FilterCriteria) -> String {
+    let present = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+
+    let mut query = QueryBuilder::new();
+    for (field, value) in [("from", &criteria.from), ("to", &criteria.to), ("subject", &criteria.subject)] {
+        if let Some(value) = present(value) {
+            query = query.raw(format!("{}:{}", field, group(&value, '(', ')')));
+        }
+    }
+    if let Some(text) = present(&criteria.query) {
+        query = query.raw(group(&text, '(', ')'));
+    }
+    if let Some(text) = present(&criteria.negated_query) {
+        query = query.raw(format!("-{}", group(&text, '{', '}')));
+    }
+    if criteria.has_attachment == Some(true) {
+        query = query.has_attachment();
+    }
+    match (criteria.size.filter(|&size| size > 0), criteria.size_comparison) {
+        (Some(size), Some(SizeComparison::Larger)) => query = query.raw(format!("larger:{}", size)),
+        (Some(size), Some(SizeComparison::Smaller)) => query = query.raw(format!("smaller:{}", size)),
+        _ => {}
+    }
+    if criteria.exclude_chats == Some(true) {
+        query = query.raw("-in:chats");
+    }
+    query.build()
+}
+
+/// A value as a single search term, bracketed when it has several words
+///
+/// A lone quoted phrase or an already bracketed group is one term as is.
+fn group(value: &str, open: char, close: char) -> String {
+    let single_term = !value.contains(char::is_whitespace)
+        || enclosed(value, '"', '"')
+        || enclosed(value, '(', ')')
+        || enclosed(value, '{', '}');
+    if single_term {
+        value.to_string()
+    } else {
+        format!("{}{}{}", open, value, close)
+    }
+}
+
+/// Whether `value` is one `open ... close` span with no other delimiters inside
+fn enclosed(value: &str, open: char, close: char) -> bool {
+    value.len() >= 2
+        && value.starts_with(open)
+        && value.ends_with(close)
+        && !value[1..value.len() - 1].contains([open, close])
+}
+
 /// Pre-defined filter templates for common scenarios
 pub struct FilterTemplates;
 
+/// Convenience switches for filter actions backed by system labels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilterActionFlags {
+    /// Add `IMPORTANT`
+    pub mark_important: bool,
+    /// Remove `SPAM`
+    pub never_spam: bool,
+    /// Remove `UNREAD`
+    pub mark_read: bool,
+    /// Remove `INBOX`
+    pub archive: bool,
+    /// Add `STARRED`
+    pub star: bool,
+    /// Add `TRASH`
+    pub delete: bool,
+}
+
+impl FilterActionFlags {
+    /// Merge the flags into an action's label sets, skipping labels already present
+    pub fn apply(self, mut action: FilterAction) -> FilterAction {
+        let adds = [
+            (self.mark_important, "IMPORTANT"),
+            (self.star, "STARRED"),
+            (self.delete, "TRASH"),
+        ];
+        let removes = [
+            (self.never_spam, "SPAM"),
+            (self.mark_read, "UNREAD"),
+            (self.archive, "INBOX"),
+        ];
+
+        merge_labels(&mut action.add_label_ids, &adds);
+        merge_labels(&mut action.remove_label_ids, &removes);
+        action
+    }
+}
+
+fn merge_labels(labels: &mut Option<Vec<String>>, flags: &[(bool, &str)]) {
+    for &(enabled, label) in flags {
+        if !enabled {
+            continue;
+        }
+        let list = labels.get_or_insert_with(Vec::new);
+        if !list.iter().any(|l| l == label) {
+            list.push(label.to_string());
+        }
+    }
+}
+
+/// Part of a message searched by the `containingText` template
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextField {
+    /// Anywhere in the message (default)
+    #[default]
+    Anywhere,
+    /// Subject line only
+    Subject,
+    /// Message body, approximated as "anywhere but the subject"
+    ///
+    /// Messages with the text in both the body and the subject are not matched.
+    Body,
+    /// Sender
+    From,
+}
+
+impl std::str::FromStr for TextField {
+    type Err = GmailMcpError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "anywhere" => Ok(Self::Anywhere),
+            "subject" => Ok(Self::Subject),
+            "body" => Ok(Self::Body),
+            "from" => Ok(Self::From),
+            _ => Err(GmailMcpError::Gmail(GmailApiError::InvalidFilterCriteria {
+                message: format!("Unknown field '{}' (expected anywhere, subject, body or from)", s),
+            })),
+        }
+    }
+}
+
 impl FilterTemplates {
     /// Filter emails from a specific sender
     pub fn from_sender(
@@ -259,8 +450,31 @@ impl FilterTemplates {
         label_ids: Option<Vec<String>>,
         mark_important: bool,
     ) -> (FilterCriteria, FilterAction) {
+        Self::containing_text_in(search_text, TextField::Anywhere, label_ids, mark_important)
+    }
+
+    /// Filter emails containing specific text in one part of the message
+    ///
+    /// Gmail has no body-only operator, so `TextField::Body` matches the text
+    /// anywhere except the subject: a message with the text in both its body
+    /// and its subject is missed.
+    pub fn containing_text_in(
+        search_text: &str,
+        field: TextField,
+        label_ids: Option<Vec<String>>,
+        mark_important: bool,
+    ) -> (FilterCriteria, FilterAction) {
+        let query = match field {
+            TextField::Anywhere => QueryBuilder::new().text(search_text),
+            TextField::Subject => QueryBuilder::new().subject(search_text),
+            TextField::From => QueryBuilder::new().from(search_text),
+            TextField::Body => QueryBuilder::new()
+                .text(search_text)
+                .raw(format!("-{}", QueryBuilder::new().subject(search_text).build())),
+        };
+
         let criteria = FilterCriteria {
-            query: Some(format!("\"{}\"", search_text)),
+            query: Some(query.build()),
             ..Default::default()
         };
 
@@ -282,16 +496,38 @@ impl FilterTemplates {
     }
 
     /// Filter mailing list emails
+    ///
+    /// Matches the list ID or a `[name]` subject tag. An identifier in
+    /// `List-Id` form (`My List <my-list.example.com>`) matches on the
+    /// bracketed ID and tags with the display name; otherwise the tag is the
+    /// ID up to its first `.` or `@` (`rust-users.example.org` → `[rust-users]`).
     pub fn mailing_list(
         list_identifier: &str,
         label_ids: Option<Vec<String>>,
         archive: bool,
     ) -> (FilterCriteria, FilterAction) {
+        let (display_name, list_id) = match list_identifier
+            .rsplit_once('<')
+            .and_then(|(name, rest)| rest.split_once('>').map(|(id, _)| (name.trim(), id.trim())))
+        {
+            Some((name, id)) if !id.is_empty() => (name, id),
+            _ => ("", list_identifier.trim()),
+        };
+        let list_name = if display_name.is_empty() {
+            list_id.split(['.', '@']).next().unwrap_or(list_id)
+        } else {
+            display_name
+        };
+
         let criteria = FilterCriteria {
-            query: Some(format!(
-                "list:{} OR subject:[{}]",
-                list_identifier, list_identifier
-            )),
+            query: Some(
+                QueryBuilder::new()
+                    .any_of([
+                        QueryBuilder::new().list(list_id),
+                        QueryBuilder::new().subject(&format!("[{}]", list_name)),
+                    ])
+                    .build(),
+            ),
             ..Default::default()
         };
 
@@ -307,12 +543,143 @@ impl FilterTemplates {
 
         (criteria, action)
     }
+
+    /// Send all mail from a sender straight to trash
+    ///
+    /// Gmail filters express "Delete it" as adding the `TRASH` label.
+    pub fn delete_from_sender(sender_email: &str) -> (FilterCriteria, FilterAction) {
+        let criteria = FilterCriteria {
+            from: Some(sender_email.to_string()),
+            ..Default::default()
+        };
+
+        let action = FilterAction {
+            add_label_ids: Some(vec!["TRASH".to_string()]),
+            remove_label_ids: Some(vec!["INBOX".to_string()]),
+            ..Default::default()
+        };
+
+        (criteria, action)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_same_filter_ignores_id_and_label_order() {
+        let labels = |ids: &[&str]| Some(ids.iter().map(|id| id.to_string()).collect());
+        let (criteria, mut action) = FilterTemplates::from_sender("news@example.com", labels(&["Label_1", "Label_2"]), true);
+        let existing = Filter {
+            id: Some("abc".to_string()),
+            criteria: criteria.clone(),
+            action: action.clone(),
+        };
+
+        action.add_label_ids = labels(&["Label_2", "Label_1"]);
+        let imported = Filter { id: None, criteria, action };
+        assert!(same_filter(&existing, &imported));
+
+        let other = Filter {
+            action: FilterAction {
+                forward: Some("archive@example.com".to_string()),
+                ..imported.action.clone()
+            },
+            ..imported
+        };
+        assert!(!same_filter(&existing, &other));
+    }
+
+    #[test]
+    fn test_criteria_to_query() {
+        let criteria = FilterCriteria {
+            from: Some("alerts@example.com".to_string()),
+            subject: Some("build failed".to_string()),
+            negated_query: Some("nightly canary".to_string()),
+            has_attachment: Some(true),
+            size: Some(1_000_000),
+            size_comparison: Some(SizeComparison::Larger),
+            ..Default::default()
+        };
+        assert_eq!(
+            criteria_to_query(&criteria),
+            "from:alerts@example.com subject:(build failed) -{nightly canary} has:attachment larger:1000000"
+        );
+        assert_eq!(criteria_to_query(&FilterCriteria::default()), "");
+    }
+
+    fn query_for(criteria: FilterCriteria) -> String {
+        criteria_to_query(&criteria)
+    }
+
+    #[test]
+    fn test_criteria_to_query_from() {
+        let from = |value: &str| query_for(FilterCriteria { from: Some(value.to_string()), ..Default::default() });
+        assert_eq!(from("alice@example.com"), "from:alice@example.com");
+        assert_eq!(from("alice@example.com OR bob@example.com"), "from:(alice@example.com OR bob@example.com)");
+        assert_eq!(from("  "), "");
+    }
+
+    #[test]
+    fn test_criteria_to_query_to() {
+        let to = |value: &str| query_for(FilterCriteria { to: Some(value.to_string()), ..Default::default() });
+        assert_eq!(to("team@example.com"), "to:team@example.com");
+        assert_eq!(to(" (a@example.com b@example.com) "), "to:(a@example.com b@example.com)");
+    }
+
+    #[test]
+    fn test_criteria_to_query_subject() {
+        let subject = |value: &str| query_for(FilterCriteria { subject: Some(value.to_string()), ..Default::default() });
+        assert_eq!(subject("invoice"), "subject:invoice");
+        assert_eq!(subject("weekly report"), "subject:(weekly report)");
+        assert_eq!(subject("\"weekly report\""), "subject:\"weekly report\"");
+    }
+
+    #[test]
+    fn test_criteria_to_query_query() {
+        let query = |value: &str| query_for(FilterCriteria { query: Some(value.to_string()), ..Default::default() });
+        assert_eq!(query("list:dev.example.com"), "list:dev.example.com");
+        assert_eq!(query("unsubscribe OR newsletter"), "(unsubscribe OR newsletter)");
+    }
+
+    #[test]
+    fn test_criteria_to_query_negated_query() {
+        let negated =
+            |value: &str| query_for(FilterCriteria { negated_query: Some(value.to_string()), ..Default::default() });
+        assert_eq!(negated("canary"), "-canary");
+        assert_eq!(negated("nightly canary"), "-{nightly canary}");
+        assert_eq!(negated("\"release notes\""), "-\"release notes\"");
+    }
+
+    #[test]
+    fn test_criteria_to_query_has_attachment() {
+        let has_attachment =
+            |value: bool| query_for(FilterCriteria { has_attachment: Some(value), ..Default::default() });
+        assert_eq!(has_attachment(true), "has:attachment");
+        assert_eq!(has_attachment(false), "");
+    }
+
+    #[test]
+    fn test_criteria_to_query_size() {
+        let size = |size: i64, comparison: Option<SizeComparison>| {
+            query_for(FilterCriteria { size: Some(size), size_comparison: comparison, ..Default::default() })
+        };
+        assert_eq!(size(5_000_000, Some(SizeComparison::Larger)), "larger:5000000");
+        assert_eq!(size(1024, Some(SizeComparison::Smaller)), "smaller:1024");
+        assert_eq!(size(1024, Some(SizeComparison::Unspecified)), "");
+        assert_eq!(size(1024, None), "");
+        assert_eq!(size(0, Some(SizeComparison::Larger)), "");
+    }
+
+    #[test]
+    fn test_criteria_to_query_exclude_chats() {
+        let exclude_chats =
+            |value: bool| query_for(FilterCriteria { exclude_chats: Some(value), ..Default::default() });
+        assert_eq!(exclude_chats(true), "-in:chats");
+        assert_eq!(exclude_chats(false), "");
+    }
+
     #[test]
     fn test_filter_template_from_sender() {
         let (criteria, action) =
@@ -340,5 +707,105 @@ mod tests {
 
         assert_eq!(criteria.has_attachment, Some(true));
     }
-}
 
+    #[test]
+    fn test_filter_template_delete_from_sender() {
+        let (criteria, action) = FilterTemplates::delete_from_sender("spam@example.com");
+
+        assert_eq!(criteria.from, Some("spam@example.com".to_string()));
+        assert_eq!(action.add_label_ids, Some(vec!["TRASH".to_string()]));
+        assert_eq!(action.remove_label_ids, Some(vec!["INBOX".to_string()]));
+    }
+
+    #[test]
+    fn test_mailing_list_query_plain_identifier() {
+        let (criteria, _) = FilterTemplates::mailing_list("rust-users", None, false);
+        assert_eq!(
+            criteria.query.as_deref(),
+            Some("(list:rust-users OR subject:\"[rust-users]\")")
+        );
+    }
+
+    #[test]
+    fn test_mailing_list_query_escapes_special_characters() {
+        let (criteria, _) = FilterTemplates::mailing_list("my list <foo@bar>", None, false);
+        assert_eq!(
+            criteria.query.as_deref(),
+            Some("(list:foo@bar OR subject:\"[my list]\")")
+        );
+    }
+
+    #[test]
+    fn test_mailing_list_query_tags_with_list_name() {
+        let (criteria, _) = FilterTemplates::mailing_list("rust-users.lists.example.org", None, false);
+        assert_eq!(
+            criteria.query.as_deref(),
+            Some("(list:rust-users.lists.example.org OR subject:\"[rust-users]\")")
+        );
+
+        let (criteria, _) = FilterTemplates::mailing_list("<announce.example.org>", None, false);
+        assert_eq!(
+            criteria.query.as_deref(),
+            Some("(list:announce.example.org OR subject:\"[announce]\")")
+        );
+    }
+
+    #[test]
+    fn test_containing_text_field_scoping() {
+        let query = |field| {
+            FilterTemplates::containing_text_in("invoice due", field, None, false)
+                .0
+                .query
+                .unwrap()
+        };
+
+        assert_eq!(query(TextField::Anywhere), "\"invoice due\"");
+        assert_eq!(query(TextField::Subject), "subject:\"invoice due\"");
+        assert_eq!(query(TextField::From), "from:\"invoice due\"");
+        assert_eq!(query(TextField::Body), "\"invoice due\" -subject:\"invoice due\"");
+    }
+
+    #[test]
+    fn test_containing_text_escapes_quotes() {
+        let (criteria, _) = FilterTemplates::containing_text("say \"hi\"", None, false);
+        assert_eq!(criteria.query.as_deref(), Some("\"say  hi\""));
+    }
+
+    #[test]
+    fn test_text_field_from_str() {
+        assert_eq!("body".parse::<TextField>().unwrap(), TextField::Body);
+        assert!("headers".parse::<TextField>().is_err());
+    }
+
+    #[test]
+    fn test_filter_action_flags() {
+        let flags = FilterActionFlags {
+            mark_important: true,
+            never_spam: true,
+            mark_read: true,
+            archive: true,
+            star: true,
+            delete: false,
+        };
+        let action = flags.apply(FilterAction {
+            add_label_ids: Some(vec!["Label_1".to_string(), "IMPORTANT".to_string()]),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            action.add_label_ids,
+            Some(vec!["Label_1".to_string(), "IMPORTANT".to_string(), "STARRED".to_string()])
+        );
+        assert_eq!(
+            action.remove_label_ids,
+            Some(vec!["SPAM".to_string(), "UNREAD".to_string(), "INBOX".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_filter_action_flags_default_is_noop() {
+        let action = FilterActionFlags::default().apply(FilterAction::default());
+        assert_eq!(action.add_label_ids, None);
+        assert_eq!(action.remove_label_ids, None);
+    }
+}
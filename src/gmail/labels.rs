@@ -3,12 +3,15 @@
 //! Provides comprehensive label management functionality.
 
 use crate::error::{GmailApiError, GmailMcpError, Result};
+use crate::gmail::quota::{ApiMethod, QuotaTracker};
 use crate::gmail::types::{CreateLabelRequest, Label, LabelList, UpdateLabelRequest};
 
 /// Label manager for Gmail operations
 pub struct LabelManager<'a> {
     client: &'a reqwest::Client,
     access_token: &'a str,
+    quota: Option<&'a QuotaTracker>,
+    user_id: &'a str,
 }
 
 impl<'a> LabelManager<'a> {
@@ -17,12 +20,36 @@ impl<'a> LabelManager<'a> {
         Self {
             client,
             access_token,
+            quota: None,
+            user_id: crate::config::gmail::USER_ID,
+        }
+    }
+
+    /// Operate on `user_id`'s mailbox instead of the authenticated user's
+    pub fn with_user_id(mut self, user_id: &'a str) -> Self {
+        self.user_id = user_id;
+        self
+    }
+
+    /// Count and rate limit this manager's requests against `quota`
+    pub fn with_quota(mut self, quota: &'a QuotaTracker) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    async fn acquire(&self, method: ApiMethod) {
+        if let Some(quota) = self.quota {
+            quota.acquire(method).await;
         }
     }
 
     /// Base URL for labels API
-    fn base_url() -> String {
-        format!("{}/users/me/labels", crate::config::gmail::API_BASE_URL)
+    fn base_url(&self) -> String {
+        format!(
+            "{}/users/{}/labels",
+            crate::config::gmail::API_BASE_URL,
+            urlencoding::encode(self.user_id)
+        )
     }
 
     /// Create a new Gmail label
@@ -38,9 +65,10 @@ impl<'a> LabelManager<'a> {
             label_list_visibility: label_list_visibility.map(|s| s.to_string()),
         };
 
+        self.acquire(ApiMethod::LabelsCreate).await;
         let response = self
             .client
-            .post(Self::base_url())
+            .post(self.base_url())
             .bearer_auth(self.access_token)
             .json(&request)
             .send()
@@ -66,11 +94,12 @@ impl<'a> LabelManager<'a> {
 
     /// Update an existing Gmail label
     pub async fn update(&self, label_id: &str, updates: UpdateLabelRequest) -> Result<Label> {
-        let url = format!("{}/{}", Self::base_url(), label_id);
+        let url = format!("{}/{}", self.base_url(), label_id);
 
         // First verify the label exists
         self.get(label_id).await?;
 
+        self.acquire(ApiMethod::LabelsUpdate).await;
         let response = self
             .client
             .put(&url)
@@ -108,8 +137,9 @@ impl<'a> LabelManager<'a> {
             }));
         }
 
-        let url = format!("{}/{}", Self::base_url(), label_id);
+        let url = format!("{}/{}", self.base_url(), label_id);
 
+        self.acquire(ApiMethod::LabelsDelete).await;
         let response = self
             .client
             .delete(&url)
@@ -137,8 +167,9 @@ impl<'a> LabelManager<'a> {
 
     /// Get a specific label by ID
     pub async fn get(&self, label_id: &str) -> Result<Label> {
-        let url = format!("{}/{}", Self::base_url(), label_id);
+        let url = format!("{}/{}", self.base_url(), label_id);
 
+        self.acquire(ApiMethod::LabelsGet).await;
         let response = self
             .client
             .get(&url)
@@ -161,41 +192,25 @@ impl<'a> LabelManager<'a> {
         }
     }
 
+    /// Get a label by ID or name, including its message counts
+    pub async fn get_by_id_or_name(&self, id_or_name: &str) -> Result<Label> {
+        let ids = self.resolve_ids(&[id_or_name.to_string()]).await?;
+        self.get(&ids[0]).await
+    }
+
     /// List all Gmail labels
     pub async fn list(&self) -> Result<LabelListResult> {
+        self.acquire(ApiMethod::LabelsList).await;
         let response = self
             .client
-            .get(Self::base_url())
+            .get(self.base_url())
             .bearer_auth(self.access_token)
             .send()
             .await?;
 
         if response.status().is_success() {
             let label_list: LabelList = response.json().await?;
-            let labels = label_list.labels;
-
-            let system_labels: Vec<Label> = labels
-                .iter()
-                .filter(|l| l.label_type.as_deref() == Some("system"))
-                .cloned()
-                .collect();
-
-            let user_labels: Vec<Label> = labels
-                .iter()
-                .filter(|l| l.label_type.as_deref() == Some("user"))
-                .cloned()
-                .collect();
-
-            Ok(LabelListResult {
-                all: labels,
-                system: system_labels.clone(),
-                user: user_labels.clone(),
-                count: LabelCount {
-                    total: system_labels.len() + user_labels.len(),
-                    system: system_labels.len(),
-                    user: user_labels.len(),
-                },
-            })
+            Ok(LabelListResult::from_labels(label_list.labels))
         } else {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
@@ -205,6 +220,24 @@ impl<'a> LabelManager<'a> {
         }
     }
 
+    /// List all labels, with message counts filled in
+    ///
+    /// `labels.list` never returns counts, so this costs one extra `labels.get`
+    /// request per label. A label whose details can't be fetched is kept
+    /// without counts.
+    pub async fn list_with_counts(&self) -> Result<LabelListResult> {
+        let mut labels = self.list().await?.all;
+
+        for label in labels.iter_mut() {
+            match self.get(&label.id).await {
+                Ok(detailed) => *label = detailed,
+                Err(e) => tracing::warn!("Could not fetch counts for label {}: {}", label.id, e),
+            }
+        }
+
+        Ok(LabelListResult::from_labels(labels))
+    }
+
     /// Find a label by name (case-insensitive)
     pub async fn find_by_name(&self, name: &str) -> Result<Option<Label>> {
         let result = self.list().await?;
@@ -216,6 +249,43 @@ impl<'a> LabelManager<'a> {
             .find(|l| l.name.to_lowercase() == name_lower))
     }
 
+    /// Resolve a mix of label names and IDs to label IDs
+    ///
+    /// Values that already look like IDs are passed through; labels are only
+    /// listed when at least one name needs resolving.
+    pub async fn resolve_ids(&self, names_or_ids: &[String]) -> Result<Vec<String>> {
+        if names_or_ids.iter().all(|v| is_label_id(v)) {
+            return Ok(names_or_ids.to_vec());
+        }
+
+        let labels = self.list().await?.all;
+        resolve_label_ids(names_or_ids, &labels)
+    }
+
+    /// Resolve label names or IDs to label IDs, creating labels that don't exist yet
+    pub async fn resolve_or_create_ids(&self, names_or_ids: &[String]) -> Result<Vec<String>> {
+        if names_or_ids.iter().all(|v| is_label_id(v)) {
+            return Ok(names_or_ids.to_vec());
+        }
+
+        let mut labels = self.list().await?.all;
+        let mut ids = Vec::with_capacity(names_or_ids.len());
+
+        for value in names_or_ids {
+            match resolve_label_ids(std::slice::from_ref(value), &labels) {
+                Ok(mut resolved) => ids.append(&mut resolved),
+                Err(GmailMcpError::Gmail(GmailApiError::LabelNotFound { .. })) => {
+                    let label = self.create(value, None, None).await?;
+                    ids.push(label.id.clone());
+                    labels.push(label);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(ids)
+    }
+
     /// Get or create a label by name
     pub async fn get_or_create(
         &self,
@@ -234,6 +304,42 @@ impl<'a> LabelManager<'a> {
     }
 }
 
+/// System label IDs that may be passed where a label is expected
+const SYSTEM_LABEL_IDS: &[&str] = &[
+    "INBOX", "SENT", "TRASH", "SPAM", "STARRED", "IMPORTANT", "UNREAD", "DRAFT", "CHAT",
+];
+
+/// Whether a value is a label ID rather than a label name
+///
+/// User label IDs look like `Label_42`; system labels use fixed upper-case IDs
+/// such as `INBOX` or `CATEGORY_PROMOTIONS`.
+pub fn is_label_id(value: &str) -> bool {
+    value.starts_with("Label_")
+        || value.starts_with("CATEGORY_")
+        || SYSTEM_LABEL_IDS.contains(&value)
+}
+
+/// Resolve label names (case-insensitive) or IDs against a known set of labels
+pub fn resolve_label_ids(names_or_ids: &[String], labels: &[Label]) -> Result<Vec<String>> {
+    names_or_ids
+        .iter()
+        .map(|value| {
+            if is_label_id(value) {
+                return Ok(value.clone());
+            }
+            labels
+                .iter()
+                .find(|l| l.name.eq_ignore_ascii_case(value) || l.id == *value)
+                .map(|l| l.id.clone())
+                .ok_or_else(|| {
+                    GmailMcpError::Gmail(GmailApiError::LabelNotFound {
+                        label_id: value.clone(),
+                    })
+                })
+        })
+        .collect()
+}
+
 /// Result of listing labels
 #[derive(Debug, Clone)]
 pub struct LabelListResult {
@@ -250,6 +356,36 @@ pub struct LabelListResult {
     pub count: LabelCount,
 }
 
+impl LabelListResult {
+    /// Partition labels into system and user labels
+    pub fn from_labels(labels: Vec<Label>) -> Self {
+        let system: Vec<Label> = labels
+            .iter()
+            .filter(|l| l.label_type.as_deref() == Some("system"))
+            .cloned()
+            .collect();
+
+        let user: Vec<Label> = labels
+            .iter()
+            .filter(|l| l.label_type.as_deref() == Some("user"))
+            .cloned()
+            .collect();
+
+        let count = LabelCount {
+            total: system.len() + user.len(),
+            system: system.len(),
+            user: user.len(),
+        };
+
+        Self {
+            all: labels,
+            system,
+            user,
+            count,
+        }
+    }
+}
+
 /// Label count statistics
 #[derive(Debug, Clone)]
 pub struct LabelCount {
@@ -281,5 +417,60 @@ mod tests {
         };
         assert_eq!(result.count.total, 0);
     }
-}
 
+    fn label(id: &str, name: &str) -> Label {
+        Label {
+            id: id.to_string(),
+            name: name.to_string(),
+            label_type: None,
+            message_list_visibility: None,
+            label_list_visibility: None,
+            messages_total: None,
+            messages_unread: None,
+            color: None,
+        }
+    }
+
+    #[test]
+    fn test_label_list_result_from_labels() {
+        let mut inbox = label("INBOX", "INBOX");
+        inbox.label_type = Some("system".to_string());
+        let mut news = label("Label_1", "Newsletters");
+        news.label_type = Some("user".to_string());
+
+        let result = LabelListResult::from_labels(vec![inbox, news]);
+
+        assert_eq!(result.all.len(), 2);
+        assert_eq!(result.system[0].id, "INBOX");
+        assert_eq!(result.user[0].id, "Label_1");
+        assert_eq!((result.count.total, result.count.system, result.count.user), (2, 1, 1));
+    }
+
+    #[test]
+    fn test_is_label_id() {
+        assert!(is_label_id("Label_42"));
+        assert!(is_label_id("INBOX"));
+        assert!(is_label_id("CATEGORY_PROMOTIONS"));
+        assert!(!is_label_id("Newsletters"));
+        assert!(!is_label_id("Inbox"));
+    }
+
+    #[test]
+    fn test_resolve_label_ids_by_name() {
+        let labels = vec![label("INBOX", "INBOX"), label("Label_7", "Newsletters")];
+        let values = vec!["newsletters".to_string(), "Label_9".to_string(), "inbox".to_string()];
+
+        let ids = resolve_label_ids(&values, &labels).unwrap();
+        assert_eq!(ids, vec!["Label_7", "Label_9", "INBOX"]);
+    }
+
+    #[test]
+    fn test_resolve_label_ids_unknown_name() {
+        let labels = vec![label("Label_7", "Newsletters")];
+        let result = resolve_label_ids(&["Receipts".to_string()], &labels);
+        assert!(matches!(
+            result,
+            Err(GmailMcpError::Gmail(GmailApiError::LabelNotFound { ref label_id })) if label_id == "Receipts"
+        ));
+    }
+}
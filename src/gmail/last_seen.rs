@@ -0,0 +1,121 @@
+//! Last-seen marker for new mail polling
+//!
+//! `get_new_messages` records how far it has read the mailbox in
+//! `last_seen.json` in the config directory, so each call only returns mail
+//! that arrived since the previous one:
+//!
+//! ```json
+//! {
+//!   "historyId": "1234567",
+//!   "checkedAt": 1767225600
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::config::write_private_file;
+use crate::error::{ConfigError, GmailMcpError, Result};
+
+/// Position of the last check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Marker {
+    /// Mailbox history ID at the last check
+    pub history_id: String,
+
+    /// Time of the last check, in seconds since the Unix epoch
+    pub checked_at: i64,
+}
+
+/// The last-seen marker, persisted to a JSON file whenever it advances
+#[derive(Debug, Default)]
+pub struct LastSeen {
+    /// File the marker is written to; `None` keeps it in memory only
+    path: Option<PathBuf>,
+
+    /// Marker from the previous check, if there was one
+    marker: Mutex<Option<Marker>>,
+}
+
+impl LastSeen {
+    /// Load the marker from `path`; a missing file means no check happened yet
+    pub fn load(path: &Path) -> Result<Self> {
+        let marker = match std::fs::read_to_string(path) {
+            Ok(contents) => Some(serde_json::from_str(&contents).map_err(|e| {
+                GmailMcpError::Config(ConfigError::InvalidConfig {
+                    message: format!("{}: {}", path.display(), e),
+                })
+            })?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(GmailMcpError::Io(e)),
+        };
+
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            marker: Mutex::new(marker),
+        })
+    }
+
+    /// Lock the marker for a check
+    ///
+    /// Held for the whole check so concurrent polls don't both report the
+    /// same messages.
+    pub async fn lock(&self) -> LastSeenGuard<'_> {
+        LastSeenGuard {
+            path: self.path.as_deref(),
+            marker: self.marker.lock().await,
+        }
+    }
+}
+
+/// Exclusive access to the marker during a check
+pub struct LastSeenGuard<'a> {
+    path: Option<&'a Path>,
+    marker: MutexGuard<'a, Option<Marker>>,
+}
+
+impl LastSeenGuard<'_> {
+    /// Marker from the previous check
+    pub fn marker(&self) -> Option<&Marker> {
+        self.marker.as_ref()
+    }
+
+    /// Advance the marker and write it to disk
+    pub async fn advance(&mut self, marker: Marker) -> Result<()> {
+        if let Some(path) = self.path {
+            let contents = serde_json::to_string_pretty(&marker)?;
+            write_private_file(path, contents.as_bytes()).await?;
+        }
+        *self.marker = Some(marker);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_advance_and_reload() {
+        let dir = std::env::temp_dir().join(format!("gmail-mcp-last-seen-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("last_seen.json");
+
+        let last_seen = LastSeen::load(&path).unwrap();
+        assert!(last_seen.lock().await.marker().is_none());
+
+        let marker = Marker {
+            history_id: "1234".to_string(),
+            checked_at: 1_767_225_600,
+        };
+        last_seen.lock().await.advance(marker.clone()).await.unwrap();
+
+        let reloaded = LastSeen::load(&path).unwrap();
+        assert_eq!(reloaded.lock().await.marker(), Some(&marker));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
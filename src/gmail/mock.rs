@@ -0,0 +1,1203 @@
+//! In-memory Gmail backend
+//!
+//! `MockGmailClient` implements `GmailApi` against a mailbox held in memory,
+//! so tool handlers and the MCP protocol path can be exercised without
+//! network access or credentials. It backs `--test-mode` and the handler
+//! tests.
+//!
+//! Outgoing and imported messages are parsed from the same MIME text the real
+//! client would upload, so what the tools read back matches what they wrote.
+//! Message and thread IDs, dates and history IDs come from counters, which
+//! keeps results deterministic. Search understands the common operators
+//! (`in:`, `label:`, `is:`, `from:`, `to:`, `cc:`, `subject:`,
+//! `has:attachment`, `rfc822msgid:`), negation and free text; other operators
+//! such as dates and sizes are ignored rather than rejected.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::error::{GmailApiError, GmailMcpError, Result, ValidationError};
+use crate::gmail::api::GmailApi;
+use crate::gmail::auth::scope_covers;
+use crate::gmail::client::{
+    check_email_size, latest_reply_target, read_message_from, reply_context_for, search_cap, BatchControl,
+    BatchOperationResult, HistoryChanges, ImportOptions, MessageSummary, ReadMessageResult, ReplyContext,
+    SearchMessageResult, SearchSnippetResult, SendEmailResult,
+};
+use crate::gmail::filters::FilterListResult;
+use crate::gmail::labels::{resolve_label_ids, LabelListResult};
+use crate::gmail::quota::{QuotaTracker, QuotaUsage};
+use crate::gmail::types::*;
+use crate::gmail::utils::{
+    self, create_email_message, encode_raw_bytes, extract_attachments, extract_email_content, find_header,
+    generate_message_id, html_to_text, internal_date_to_rfc3339, parse_list_unsubscribe, validate_label_changes,
+    EmailParams, UnsubscribeInfo,
+};
+
+/// Address of the mock mailbox's owner
+pub const MOCK_SENDER: &str = "me@example.com";
+
+/// `internalDate` of the first message (2026-01-01T00:00:00Z), in milliseconds
+const FIRST_INTERNAL_DATE: i64 = 1_767_225_600_000;
+
+/// Gap between consecutive messages' `internalDate`
+const MESSAGE_INTERVAL_MS: i64 = 60_000;
+
+/// Longest snippet generated for a message
+const SNIPPET_CHARS: usize = 100;
+
+/// System labels every mailbox starts with
+const SYSTEM_LABELS: &[&str] = &["INBOX", "SENT", "DRAFT", "TRASH", "SPAM", "STARRED", "IMPORTANT", "UNREAD"];
+
+/// Gmail backend that keeps its mailbox in memory
+pub struct MockGmailClient {
+    /// The authenticated user's address
+    sender: String,
+
+    /// Mailbox contents
+    state: Mutex<MockState>,
+
+    /// Always empty; nothing is sent to Gmail
+    quota: QuotaTracker,
+
+    /// Space-separated scopes the token was granted, or `None` if unknown
+    granted_scopes: Option<String>,
+}
+
+/// Mailbox contents of a `MockGmailClient`
+#[derive(Default)]
+struct MockState {
+    /// Messages, oldest first
+    messages: Vec<Message>,
+
+    /// Attachment bodies by attachment ID
+    attachments: HashMap<String, Vec<u8>>,
+
+    /// History ID at which each message was added
+    history: Vec<(u64, String)>,
+
+    labels: Vec<Label>,
+    filters: Vec<Filter>,
+
+    /// Last ID handed out for a message, thread, draft, label, filter or attachment
+    last_id: u64,
+
+    /// Current mailbox history ID
+    history_id: u64,
+}
+
+impl Default for MockGmailClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockGmailClient {
+    /// Create an empty mailbox owned by `MOCK_SENDER`
+    pub fn new() -> Self {
+        let labels = SYSTEM_LABELS
+            .iter()
+            .map(|id| Label {
+                id: id.to_string(),
+                name: id.to_string(),
+                label_type: Some("system".to_string()),
+                message_list_visibility: None,
+                label_list_visibility: None,
+                messages_total: None,
+                messages_unread: None,
+                color: None,
+            })
+            .collect();
+
+        Self {
+            sender: MOCK_SENDER.to_string(),
+            state: Mutex::new(MockState {
+                labels,
+                ..MockState::default()
+            }),
+            quota: QuotaTracker::new(),
+            granted_scopes: None,
+        }
+    }
+
+    /// A mailbox with a few inbox messages, used by `--test-mode`
+    pub fn sample() -> Self {
+        let client = Self::new();
+        client.add_message(
+            "Alice Example <alice@example.com>",
+            MOCK_SENDER,
+            "Project kickoff",
+            "Hi,\n\nThe kickoff meeting is on Monday at 10:00.\n\nAlice",
+        );
+        client.add_message(
+            "Bob Example <bob@example.com>",
+            MOCK_SENDER,
+            "Invoice #1042",
+            "Please find the invoice for March below.\n\nTotal: $120.00",
+        );
+        client.add_message(
+            "Newsletter <news@example.org>",
+            MOCK_SENDER,
+            "Weekly digest",
+            "This week's top stories.",
+        );
+        client
+    }
+
+    /// Refuse tools whose scope `scopes` doesn't cover, like a real stored grant
+    pub fn with_granted_scopes(mut self, scopes: &[&str]) -> Self {
+        self.granted_scopes = Some(scopes.join(" "));
+        self
+    }
+
+    /// Set the mailbox owner's address
+    pub fn with_sender(mut self, sender: impl Into<String>) -> Self {
+        self.sender = sender.into();
+        self
+    }
+
+    /// Deliver a plain text message to the inbox as unread, returning its ID
+    pub fn add_message(&self, from: &str, to: &str, subject: &str, body: &str) -> String {
+        let raw = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nMessage-ID: {}\r\n\r\n{}",
+            from,
+            to,
+            subject,
+            generate_message_id(from),
+            body
+        );
+        self.state()
+            .deliver(&self.sender, raw.as_bytes(), vec!["INBOX".to_string(), "UNREAD".to_string()], None)
+            .id
+    }
+
+    /// A message as Gmail would return it with `format=full`
+    pub fn message(&self, message_id: &str) -> Option<Message> {
+        self.state().message(message_id).ok().cloned()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl MockState {
+    fn next_id(&mut self) -> u64 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn message(&self, message_id: &str) -> Result<&Message> {
+        self.messages
+            .iter()
+            .find(|m| m.id == message_id)
+            .ok_or_else(|| message_not_found(message_id))
+    }
+
+    fn message_mut(&mut self, message_id: &str) -> Result<&mut Message> {
+        self.messages
+            .iter_mut()
+            .find(|m| m.id == message_id)
+            .ok_or_else(|| message_not_found(message_id))
+    }
+
+    fn attachment(&self, message_id: &str, attachment_id: &str) -> Result<Vec<u8>> {
+        self.message(message_id)?;
+        self.attachments.get(attachment_id).cloned().ok_or_else(|| {
+            GmailMcpError::Gmail(GmailApiError::AttachmentNotFound {
+                attachment_id: attachment_id.to_string(),
+            })
+        })
+    }
+
+    /// Parse a raw RFC822 message and add it to the mailbox
+    ///
+    /// Without an explicit thread, a message replying to one already in the
+    /// mailbox joins its thread.
+    fn deliver(&mut self, sender: &str, raw: &[u8], label_ids: Vec<String>, thread_id: Option<String>) -> Message {
+        let id = format!("{:016x}", self.next_id());
+        let internal_date = FIRST_INTERNAL_DATE + self.history_id as i64 * MESSAGE_INTERVAL_MS;
+
+        let text = String::from_utf8_lossy(raw).replace("\r\n", "\n");
+        let mut payload = self.parse_part(&text);
+        for header in payload.headers.iter_mut() {
+            if header.name.eq_ignore_ascii_case("from") && header.value == "me" {
+                header.value = sender.to_string();
+            }
+        }
+        if find_header(&payload, "date").is_none() {
+            payload.headers.push(Header {
+                name: "Date".to_string(),
+                value: format_date(internal_date),
+            });
+        }
+
+        let thread_id = thread_id
+            .or_else(|| {
+                let in_reply_to = find_header(&payload, "in-reply-to")?;
+                self.messages
+                    .iter()
+                    .find(|m| m.payload.as_ref().and_then(|p| find_header(p, "message-id")) == Some(in_reply_to))
+                    .and_then(|m| m.thread_id.clone())
+            })
+            .unwrap_or_else(|| id.clone());
+
+        let message = Message {
+            snippet: Some(snippet(&payload)),
+            id: id.clone(),
+            thread_id: Some(thread_id),
+            label_ids,
+            payload: Some(payload),
+            size_estimate: Some(raw.len() as i64),
+            raw: None,
+            internal_date: Some(internal_date.to_string()),
+        };
+
+        self.history_id += 1;
+        self.history.push((self.history_id, id));
+        self.messages.push(message.clone());
+        message
+    }
+
+    /// Parse a MIME entity, storing attachment bodies so they can be fetched by ID
+    fn parse_part(&mut self, text: &str) -> MessagePart {
+        let (head, body) = text.split_once("\n\n").unwrap_or((text, ""));
+        let headers = parse_headers(head);
+        let find = |name: &str| {
+            headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case(name))
+                .map(|h| h.value.as_str())
+        };
+
+        let content_type = find("content-type").unwrap_or("text/plain");
+        let mime_type = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        let filename = find("content-disposition")
+            .and_then(|v| header_param(v, "filename"))
+            .or_else(|| header_param(content_type, "name"));
+
+        if let Some(boundary) = mime_type
+            .starts_with("multipart/")
+            .then(|| header_param(content_type, "boundary"))
+            .flatten()
+        {
+            let delimiter = format!("--{}", boundary);
+            let mut parts = Vec::new();
+            // Delimiters start a line; the body itself usually opens with one
+            for section in format!("\n{}", body).split(&format!("\n{}", delimiter)).skip(1) {
+                if section.starts_with("--") {
+                    break;
+                }
+                let section = section.split_once('\n').map_or("", |(_, rest)| rest);
+                parts.push(self.parse_part(section));
+            }
+
+            return MessagePart {
+                mime_type: Some(mime_type),
+                headers,
+                parts,
+                ..MessagePart::default()
+            };
+        }
+
+        let data = match find("content-transfer-encoding") {
+            Some(encoding) if encoding.eq_ignore_ascii_case("base64") => {
+                let compact: String = body.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+                STANDARD.decode(compact).unwrap_or_default()
+            }
+            _ => body.trim_end_matches('\n').as_bytes().to_vec(),
+        };
+
+        let body = if filename.is_some() {
+            let attachment_id = format!("ANGjdJ{}", self.next_id());
+            let size = data.len() as i64;
+            self.attachments.insert(attachment_id.clone(), data);
+            MessagePartBody {
+                attachment_id: Some(attachment_id),
+                size,
+                data: None,
+            }
+        } else {
+            MessagePartBody {
+                attachment_id: None,
+                size: data.len() as i64,
+                data: Some(encode_raw_bytes(&data)),
+            }
+        };
+
+        MessagePart {
+            mime_type: Some(mime_type),
+            filename,
+            headers,
+            body: Some(body),
+            ..MessagePart::default()
+        }
+    }
+
+    /// Messages matching a search, newest first
+    fn search(&self, query: &str) -> Vec<&Message> {
+        let terms = tokenize(query);
+        let lowered = query.to_ascii_lowercase();
+        let includes_hidden = ["in:trash", "in:spam", "in:anywhere", "label:trash", "label:spam"]
+            .iter()
+            .any(|scope| lowered.contains(scope));
+
+        self.messages
+            .iter()
+            .rev()
+            .filter(|m| includes_hidden || !m.label_ids.iter().any(|l| l == "TRASH" || l == "SPAM"))
+            .filter(|m| terms.iter().all(|(negated, term)| self.matches(m, term) != *negated))
+            .collect()
+    }
+
+    /// Whether a message matches one search term
+    fn matches(&self, message: &Message, term: &str) -> bool {
+        let payload = message.payload.as_ref();
+        let header_contains = |name: &str, value: &str| {
+            payload
+                .and_then(|p| find_header(p, name))
+                .is_some_and(|h| h.to_lowercase().contains(&value.to_lowercase()))
+        };
+        let has_label = |value: &str| {
+            let value = value.replace('-', " ");
+            message.label_ids.iter().any(|id| {
+                id.eq_ignore_ascii_case(&value)
+                    || self
+                        .labels
+                        .iter()
+                        .any(|l| l.id == *id && l.name.eq_ignore_ascii_case(&value))
+            })
+        };
+
+        match term.split_once(':') {
+            Some((operator, value)) if operator.chars().all(|c| c.is_ascii_alphanumeric()) => {
+                match operator.to_ascii_lowercase().as_str() {
+                    "in" | "label" => value.eq_ignore_ascii_case("anywhere") || has_label(value),
+                    "is" => match value.to_ascii_lowercase().as_str() {
+                        "read" => !has_label("UNREAD"),
+                        other => has_label(other),
+                    },
+                    "from" | "to" | "cc" | "subject" => header_contains(operator, value),
+                    "rfc822msgid" => header_contains("message-id", value),
+                    "has" => {
+                        value.eq_ignore_ascii_case("attachment")
+                            && payload.is_some_and(|p| !extract_attachments(p).is_empty())
+                    }
+                    _ => true,
+                }
+            }
+            _ => {
+                let term = term.to_lowercase();
+                let body = payload.map(extract_email_content).unwrap_or_default();
+                ["subject", "from", "to"].iter().any(|h| header_contains(h, &term))
+                    || body.text.to_lowercase().contains(&term)
+                    || body.html.to_lowercase().contains(&term)
+            }
+        }
+    }
+
+    /// A label with its message and unread counts filled in
+    fn counted(&self, label: &Label) -> Label {
+        let tagged = || self.messages.iter().filter(|m| m.label_ids.contains(&label.id));
+        Label {
+            messages_total: Some(tagged().count() as i32),
+            messages_unread: Some(tagged().filter(|m| m.label_ids.iter().any(|l| l == "UNREAD")).count() as i32),
+            ..label.clone()
+        }
+    }
+
+    fn label(&self, label_id: &str) -> Result<&Label> {
+        self.labels
+            .iter()
+            .find(|l| l.id == label_id)
+            .ok_or_else(|| label_not_found(label_id))
+    }
+
+    fn create_label(
+        &mut self,
+        name: &str,
+        message_list_visibility: Option<&str>,
+        label_list_visibility: Option<&str>,
+    ) -> Result<Label> {
+        if self.labels.iter().any(|l| l.name.eq_ignore_ascii_case(name)) {
+            return Err(GmailMcpError::Gmail(GmailApiError::LabelAlreadyExists {
+                name: name.to_string(),
+            }));
+        }
+
+        let label = Label {
+            id: format!("Label_{}", self.next_id()),
+            name: name.to_string(),
+            label_type: Some("user".to_string()),
+            message_list_visibility: message_list_visibility.map(str::to_string),
+            label_list_visibility: label_list_visibility.map(str::to_string),
+            messages_total: None,
+            messages_unread: None,
+            color: None,
+        };
+        self.labels.push(label.clone());
+        Ok(label)
+    }
+
+    fn modify(
+        &mut self,
+        message_id: &str,
+        add_label_ids: &[String],
+        remove_label_ids: &[String],
+    ) -> Result<Message> {
+        for id in add_label_ids {
+            self.label(id)?;
+        }
+
+        let message = self.message_mut(message_id)?;
+        message.label_ids.retain(|l| !remove_label_ids.contains(l));
+        for id in add_label_ids {
+            if !message.label_ids.contains(id) {
+                message.label_ids.push(id.clone());
+            }
+        }
+        Ok(message.clone())
+    }
+
+    fn trash(&mut self, message_id: &str) -> Result<()> {
+        self.modify(message_id, &["TRASH".to_string()], &["INBOX".to_string()])
+            .map(|_| ())
+    }
+}
+
+fn message_not_found(message_id: &str) -> GmailMcpError {
+    GmailMcpError::Gmail(GmailApiError::MessageNotFound {
+        message_id: message_id.to_string(),
+    })
+}
+
+fn label_not_found(label_id: &str) -> GmailMcpError {
+    GmailMcpError::Gmail(GmailApiError::LabelNotFound {
+        label_id: label_id.to_string(),
+    })
+}
+
+/// Parse a header block, unfolding continuation lines
+fn parse_headers(head: &str) -> Vec<Header> {
+    let mut headers: Vec<Header> = Vec::new();
+    for line in head.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some(last) = headers.last_mut() {
+                last.value.push(' ');
+                last.value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push(Header {
+                name: name.trim().to_string(),
+                value: value.trim().to_string(),
+            });
+        }
+    }
+    headers
+}
+
+/// A `name=value` parameter of a structured header such as `Content-Type`
+fn header_param(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Split a query into `(negated, term)` pairs, keeping quoted phrases together
+fn tokenize(query: &str) -> Vec<(bool, String)> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in query.chars().chain(std::iter::once(' ')) {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                let term = std::mem::take(&mut current);
+                match term.strip_prefix('-') {
+                    Some(rest) if !rest.is_empty() => terms.push((true, rest.to_string())),
+                    _ if !term.is_empty() => terms.push((false, term)),
+                    _ => {}
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    terms
+}
+
+/// First characters of a message's text, with whitespace collapsed
+fn snippet(payload: &MessagePart) -> String {
+    let content = extract_email_content(payload);
+    let text = if content.text.is_empty() {
+        html_to_text(&content.html)
+    } else {
+        content.text
+    };
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(SNIPPET_CHARS)
+        .collect()
+}
+
+/// RFC 2822 `Date` header for an `internalDate`
+fn format_date(internal_date: i64) -> String {
+    time::OffsetDateTime::from_unix_timestamp(internal_date / 1000)
+        .ok()
+        .and_then(|date| date.format(&time::format_description::well_known::Rfc2822).ok())
+        .unwrap_or_default()
+}
+
+/// Apply `op` to each message in chunks, honouring cancellation and reporting progress
+fn run_batch(
+    message_ids: &[String],
+    batch_size: usize,
+    control: &BatchControl,
+    mut op: impl FnMut(&str) -> Result<()>,
+) -> BatchOperationResult {
+    let mut succeeded = Vec::new();
+    let mut failures = Vec::new();
+    let mut cancelled = false;
+
+    for chunk in message_ids.chunks(batch_size.max(1)) {
+        if control.cancel.is_cancelled() {
+            cancelled = true;
+            break;
+        }
+        for id in chunk {
+            match op(id) {
+                Ok(()) => succeeded.push(id.clone()),
+                Err(e) => failures.push((id.clone(), e.to_string())),
+            }
+        }
+        if let Some(ref progress) = control.progress {
+            progress(succeeded.len() + failures.len(), message_ids.len());
+        }
+    }
+
+    BatchOperationResult {
+        success_count: succeeded.len(),
+        failure_count: failures.len(),
+        succeeded,
+        failures,
+        cancelled,
+    }
+}
+
+#[async_trait]
+impl GmailApi for MockGmailClient {
+    fn quota_usage(&self) -> QuotaUsage {
+        self.quota.usage()
+    }
+
+    async fn check_scope(&self, required: &str) -> Result<()> {
+        match self.granted_scopes {
+            Some(ref granted) if !scope_covers(granted, required) => {
+                Err(GmailMcpError::Gmail(GmailApiError::InsufficientPermissions {
+                    scope: required.to_string(),
+                }))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn get_profile(&self) -> Result<Profile> {
+        let state = self.state();
+        let mut threads: Vec<_> = state.messages.iter().filter_map(|m| m.thread_id.as_ref()).collect();
+        threads.sort();
+        threads.dedup();
+
+        Ok(Profile {
+            email_address: self.sender.clone(),
+            messages_total: Some(state.messages.len() as i64),
+            threads_total: Some(threads.len() as i64),
+            history_id: Some(state.history_id.to_string()),
+        })
+    }
+
+    async fn sender_email(&self) -> Result<&str> {
+        Ok(&self.sender)
+    }
+
+    async fn send_email(&self, mut params: EmailParams) -> Result<SendEmailResult> {
+        if params.message_id.is_none() {
+            params.message_id = Some(generate_message_id(&self.sender));
+        }
+        let raw_message = create_email_message(&params)?;
+        check_email_size(&raw_message)?;
+
+        let message = self.state().deliver(
+            &self.sender,
+            raw_message.as_bytes(),
+            vec!["SENT".to_string()],
+            params.thread_id.clone(),
+        );
+        Ok(SendEmailResult {
+            message,
+            message_id_header: params.message_id.unwrap_or_default(),
+        })
+    }
+
+    async fn create_draft(&self, params: EmailParams) -> Result<Draft> {
+        let raw_message = create_email_message(&params)?;
+        check_email_size(&raw_message)?;
+
+        let mut state = self.state();
+        let message = state.deliver(
+            &self.sender,
+            raw_message.as_bytes(),
+            vec!["DRAFT".to_string()],
+            params.thread_id.clone(),
+        );
+        Ok(Draft {
+            id: format!("r{}", state.next_id()),
+            message,
+        })
+    }
+
+    async fn import_message(&self, raw: &[u8], options: &ImportOptions) -> Result<Message> {
+        Ok(self.state().deliver(&self.sender, raw, options.label_ids.clone(), None))
+    }
+
+    async fn insert_message(&self, raw: &[u8], label_ids: &[String]) -> Result<Message> {
+        Ok(self.state().deliver(&self.sender, raw, label_ids.to_vec(), None))
+    }
+
+    async fn get_message(&self, message_id: &str) -> Result<Message> {
+        Ok(self.state().message(message_id)?.clone())
+    }
+
+    async fn get_message_headers(&self, message_id: &str, names: &[String]) -> Result<Vec<Header>> {
+        let state = self.state();
+        Ok(state
+            .message(message_id)?
+            .payload
+            .iter()
+            .flat_map(|p| p.headers.iter())
+            .filter(|h| names.iter().any(|n| n.eq_ignore_ascii_case(&h.name)))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_label_ids(&self, message_id: &str) -> Result<Vec<String>> {
+        Ok(self.state().message(message_id)?.label_ids.clone())
+    }
+
+    async fn get_message_summary(&self, message_id: &str) -> Result<MessageSummary> {
+        let message = self.state().message(message_id)?.clone();
+        let snippet = message.snippet.clone().unwrap_or_default();
+        let read = read_message_from(message);
+        Ok(MessageSummary {
+            id: read.id,
+            thread_id: read.thread_id,
+            subject: read.subject,
+            from: read.from,
+            to: read.to,
+            date: read.date,
+            label_ids: read.label_ids,
+            snippet,
+        })
+    }
+
+    async fn read_message(&self, message_id: &str) -> Result<ReadMessageResult> {
+        Ok(read_message_from(self.state().message(message_id)?.clone()))
+    }
+
+    async fn get_unsubscribe_info(&self, message_id: &str) -> Result<UnsubscribeInfo> {
+        let state = self.state();
+        let Some(payload) = state.message(message_id)?.payload.as_ref() else {
+            return Ok(UnsubscribeInfo::default());
+        };
+
+        Ok(find_header(payload, "list-unsubscribe")
+            .map(|value| parse_list_unsubscribe(value, find_header(payload, "list-unsubscribe-post")))
+            .unwrap_or_default())
+    }
+
+    async fn unsubscribe_one_click(&self, url: &str) -> Result<()> {
+        if !url.to_ascii_lowercase().starts_with("https://") {
+            return Err(GmailMcpError::Gmail(GmailApiError::RequestFailed {
+                message: format!("One-click unsubscribe requires an https URL: {}", url),
+            }));
+        }
+        Ok(())
+    }
+
+    async fn download_url_attachment(&self, url: &str) -> Result<utils::AttachmentData> {
+        Err(GmailMcpError::Validation(ValidationError::InvalidParameter {
+            name: "attachments".to_string(),
+            message: format!("URL attachments are not downloaded by the mock client: {}", url),
+        }))
+    }
+
+    async fn reply_context(&self, message_id: &str) -> Result<ReplyContext> {
+        reply_context_for(self.state().message(message_id)?)
+    }
+
+    async fn thread_reply_context(&self, thread_id: &str) -> Result<ReplyContext> {
+        let state = self.state();
+        let messages: Vec<Message> = state
+            .messages
+            .iter()
+            .filter(|m| m.thread_id.as_deref() == Some(thread_id))
+            .cloned()
+            .collect();
+        if messages.is_empty() {
+            return Err(thread_not_found(thread_id));
+        }
+
+        let latest = latest_reply_target(&messages).ok_or_else(|| {
+            GmailMcpError::Gmail(GmailApiError::RequestFailed {
+                message: format!("Thread {} has no sent or received messages to reply to", thread_id),
+            })
+        })?;
+        reply_context_for(latest)
+    }
+
+    async fn list_history(&self, start_history_id: &str, label_id: &str) -> Result<Option<HistoryChanges>> {
+        let state = self.state();
+        let Ok(start) = start_history_id.parse::<u64>() else {
+            return Ok(None);
+        };
+
+        let message_ids = state
+            .history
+            .iter()
+            .filter(|(history_id, _)| *history_id > start)
+            .filter(|(_, id)| state.message(id).is_ok_and(|m| m.label_ids.iter().any(|l| l == label_id)))
+            .map(|(_, id)| id.clone())
+            .collect();
+
+        Ok(Some(HistoryChanges {
+            message_ids,
+            history_id: state.history_id.to_string(),
+        }))
+    }
+
+    async fn read_thread(&self, thread_id: &str) -> Result<Vec<ReadMessageResult>> {
+        let messages: Vec<ReadMessageResult> = self
+            .state()
+            .messages
+            .iter()
+            .filter(|m| m.thread_id.as_deref() == Some(thread_id))
+            .cloned()
+            .map(read_message_from)
+            .collect();
+        if messages.is_empty() {
+            return Err(thread_not_found(thread_id));
+        }
+        Ok(messages)
+    }
+
+    async fn list_attachments(&self, message_id: &str) -> Result<Vec<EmailAttachment>> {
+        Ok(self
+            .state()
+            .message(message_id)?
+            .payload
+            .as_ref()
+            .map(extract_attachments)
+            .unwrap_or_default())
+    }
+
+    async fn search_messages(&self, query: &str, max_results: Option<u32>) -> Result<Vec<SearchMessageResult>> {
+        let state = self.state();
+        Ok(state
+            .search(query)
+            .into_iter()
+            .take(search_cap(max_results))
+            .map(|m| {
+                let header = |name: &str| {
+                    m.payload
+                        .as_ref()
+                        .and_then(|p| find_header(p, name))
+                        .unwrap_or("")
+                        .to_string()
+                };
+                SearchMessageResult {
+                    id: m.id.clone(),
+                    thread_id: m.thread_id.clone().unwrap_or_default(),
+                    subject: header("subject"),
+                    from: header("from"),
+                    date: header("date"),
+                    received: m.internal_date.as_deref().and_then(internal_date_to_rfc3339),
+                }
+            })
+            .collect())
+    }
+
+    async fn search_message_snippets(
+        &self,
+        query: &str,
+        max_results: Option<u32>,
+    ) -> Result<Vec<SearchSnippetResult>> {
+        let state = self.state();
+        Ok(state
+            .search(query)
+            .into_iter()
+            .take(search_cap(max_results))
+            .map(|m| SearchSnippetResult {
+                id: m.id.clone(),
+                thread_id: m.thread_id.clone().unwrap_or_default(),
+                snippet: m.snippet.clone().unwrap_or_default(),
+                received: m.internal_date.as_deref().and_then(internal_date_to_rfc3339),
+            })
+            .collect())
+    }
+
+    async fn modify_message(
+        &self,
+        message_id: &str,
+        add_label_ids: Option<Vec<String>>,
+        remove_label_ids: Option<Vec<String>>,
+    ) -> Result<Message> {
+        validate_label_changes(add_label_ids.as_deref(), remove_label_ids.as_deref())?;
+        self.state().modify(
+            message_id,
+            add_label_ids.as_deref().unwrap_or_default(),
+            remove_label_ids.as_deref().unwrap_or_default(),
+        )
+    }
+
+    async fn delete_message(&self, message_id: &str) -> Result<()> {
+        self.state().trash(message_id)
+    }
+
+    async fn list_message_ids(&self, query: &str, limit: usize) -> Result<Vec<String>> {
+        Ok(self.state().search(query).into_iter().take(limit).map(|m| m.id.clone()).collect())
+    }
+
+    async fn list_all_message_ids(&self, query: &str) -> Result<Vec<String>> {
+        Ok(self.state().search(query).into_iter().map(|m| m.id.clone()).collect())
+    }
+
+    async fn batch_delete_messages_permanently(
+        &self,
+        message_ids: &[String],
+        batch_size: usize,
+        control: &BatchControl,
+    ) -> Result<BatchOperationResult> {
+        let mut state = self.state();
+        Ok(run_batch(message_ids, batch_size, control, |id| {
+            state.message(id)?;
+            state.messages.retain(|m| m.id != id);
+            Ok(())
+        }))
+    }
+
+    async fn get_attachment(&self, message_id: &str, attachment_id: &str) -> Result<AttachmentData> {
+        let data = self.state().attachment(message_id, attachment_id)?;
+        Ok(AttachmentData {
+            size: data.len() as i64,
+            data: encode_raw_bytes(&data),
+        })
+    }
+
+    async fn save_attachment(&self, message_id: &str, attachment_id: &str, path: &Path) -> Result<u64> {
+        let data = self.state().attachment(message_id, attachment_id)?;
+        std::fs::write(path, &data)?;
+        Ok(data.len() as u64)
+    }
+
+    async fn batch_modify_messages(
+        &self,
+        message_ids: &[String],
+        add_label_ids: Option<Vec<String>>,
+        remove_label_ids: Option<Vec<String>>,
+        batch_size: usize,
+        control: &BatchControl,
+    ) -> Result<BatchOperationResult> {
+        validate_label_changes(add_label_ids.as_deref(), remove_label_ids.as_deref())?;
+        let add = add_label_ids.unwrap_or_default();
+        let remove = remove_label_ids.unwrap_or_default();
+
+        let mut state = self.state();
+        Ok(run_batch(message_ids, batch_size, control, |id| {
+            state.modify(id, &add, &remove).map(|_| ())
+        }))
+    }
+
+    async fn batch_delete_messages(
+        &self,
+        message_ids: &[String],
+        batch_size: usize,
+        control: &BatchControl,
+    ) -> Result<BatchOperationResult> {
+        let mut state = self.state();
+        Ok(run_batch(message_ids, batch_size, control, |id| state.trash(id)))
+    }
+
+    /// Sends immediately one after another; `interval` only paces real sends
+    async fn send_email_batch(
+        &self,
+        messages: Vec<EmailParams>,
+        _interval: Duration,
+        control: &BatchControl,
+    ) -> BatchOperationResult {
+        let total = messages.len();
+        let mut succeeded = Vec::new();
+        let mut failures = Vec::new();
+        let mut cancelled = false;
+
+        for (index, params) in messages.into_iter().enumerate() {
+            if control.cancel.is_cancelled() {
+                cancelled = true;
+                break;
+            }
+
+            let item = format!("#{} to {}", index + 1, params.to.join(", "));
+            match self.send_email(params).await {
+                Ok(sent) => succeeded.push(sent.message.id),
+                Err(e) => failures.push((item, e.to_string())),
+            }
+
+            if let Some(ref progress) = control.progress {
+                progress(succeeded.len() + failures.len(), total);
+            }
+        }
+
+        BatchOperationResult {
+            success_count: succeeded.len(),
+            failure_count: failures.len(),
+            succeeded,
+            failures,
+            cancelled,
+        }
+    }
+
+    async fn list_labels(&self) -> Result<LabelListResult> {
+        Ok(LabelListResult::from_labels(self.state().labels.clone()))
+    }
+
+    async fn list_labels_with_counts(&self) -> Result<LabelListResult> {
+        let state = self.state();
+        let labels = state.labels.iter().map(|l| state.counted(l)).collect();
+        Ok(LabelListResult::from_labels(labels))
+    }
+
+    async fn get_label(&self, id_or_name: &str) -> Result<Label> {
+        let state = self.state();
+        let ids = resolve_label_ids(&[id_or_name.to_string()], &state.labels)?;
+        Ok(state.counted(state.label(&ids[0])?))
+    }
+
+    async fn create_label(
+        &self,
+        name: &str,
+        message_list_visibility: Option<&str>,
+        label_list_visibility: Option<&str>,
+    ) -> Result<Label> {
+        self.state()
+            .create_label(name, message_list_visibility, label_list_visibility)
+    }
+
+    async fn update_label(&self, label_id: &str, updates: UpdateLabelRequest) -> Result<Label> {
+        let mut state = self.state();
+        let label = state
+            .labels
+            .iter_mut()
+            .find(|l| l.id == label_id)
+            .ok_or_else(|| label_not_found(label_id))?;
+
+        if let Some(name) = updates.name {
+            label.name = name;
+        }
+        if let Some(visibility) = updates.message_list_visibility {
+            label.message_list_visibility = Some(visibility);
+        }
+        if let Some(visibility) = updates.label_list_visibility {
+            label.label_list_visibility = Some(visibility);
+        }
+        if let Some(color) = updates.color {
+            label.color = Some(color);
+        }
+        Ok(label.clone())
+    }
+
+    async fn delete_label(&self, label_id: &str) -> Result<()> {
+        let mut state = self.state();
+        if state.label(label_id)?.label_type.as_deref() == Some("system") {
+            return Err(GmailMcpError::Gmail(GmailApiError::CannotDeleteSystemLabel {
+                label_id: label_id.to_string(),
+            }));
+        }
+
+        state.labels.retain(|l| l.id != label_id);
+        for message in state.messages.iter_mut() {
+            message.label_ids.retain(|l| l != label_id);
+        }
+        Ok(())
+    }
+
+    async fn resolve_label_ids(&self, names_or_ids: Option<Vec<String>>) -> Result<Option<Vec<String>>> {
+        let Some(values) = names_or_ids else {
+            return Ok(None);
+        };
+        resolve_label_ids(&values, &self.state().labels).map(Some)
+    }
+
+    async fn resolve_or_create_label_ids(&self, names_or_ids: Option<Vec<String>>) -> Result<Option<Vec<String>>> {
+        let Some(values) = names_or_ids else {
+            return Ok(None);
+        };
+
+        let mut state = self.state();
+        let mut ids = Vec::with_capacity(values.len());
+        for value in &values {
+            match resolve_label_ids(std::slice::from_ref(value), &state.labels) {
+                Ok(mut resolved) => ids.append(&mut resolved),
+                Err(GmailMcpError::Gmail(GmailApiError::LabelNotFound { .. })) => {
+                    ids.push(state.create_label(value, None, None)?.id);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Some(ids))
+    }
+
+    async fn get_or_create_label(
+        &self,
+        name: &str,
+        message_list_visibility: Option<&str>,
+        label_list_visibility: Option<&str>,
+    ) -> Result<Label> {
+        let mut state = self.state();
+        if let Some(label) = state.labels.iter().find(|l| l.name.eq_ignore_ascii_case(name)) {
+            return Ok(label.clone());
+        }
+        state.create_label(name, message_list_visibility, label_list_visibility)
+    }
+
+    async fn list_filters(&self) -> Result<FilterListResult> {
+        let filters = self.state().filters.clone();
+        Ok(FilterListResult {
+            count: filters.len(),
+            filters,
+        })
+    }
+
+    async fn get_filter(&self, filter_id: &str) -> Result<Filter> {
+        self.state()
+            .filters
+            .iter()
+            .find(|f| f.id.as_deref() == Some(filter_id))
+            .cloned()
+            .ok_or_else(|| filter_not_found(filter_id))
+    }
+
+    async fn create_filter(&self, criteria: FilterCriteria, action: FilterAction) -> Result<Filter> {
+        let mut state = self.state();
+        let filter = Filter {
+            id: Some(format!("ANe1Bm{}", state.next_id())),
+            criteria,
+            action,
+        };
+        state.filters.push(filter.clone());
+        Ok(filter)
+    }
+
+    async fn delete_filter(&self, filter_id: &str) -> Result<()> {
+        let mut state = self.state();
+        let before = state.filters.len();
+        state.filters.retain(|f| f.id.as_deref() != Some(filter_id));
+        if state.filters.len() == before {
+            return Err(filter_not_found(filter_id));
+        }
+        Ok(())
+    }
+}
+
+fn thread_not_found(thread_id: &str) -> GmailMcpError {
+    GmailMcpError::Gmail(GmailApiError::RequestFailed {
+        message: format!("Thread not found: {}", thread_id),
+    })
+}
+
+fn filter_not_found(filter_id: &str) -> GmailMcpError {
+    GmailMcpError::Gmail(GmailApiError::FilterNotFound {
+        filter_id: filter_id.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gmail::utils::AttachmentData;
+
+    fn params(to: &str, subject: &str, body: &str) -> EmailParams {
+        EmailParams {
+            to: vec![to.to_string()],
+            subject: subject.to_string(),
+            body: body.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sent_message_reads_back() {
+        let client = MockGmailClient::new();
+        let mut outgoing = params("bob@example.com", "Report", "See attached.");
+        outgoing.attachments = Some(vec![AttachmentData {
+            filename: "report.txt".to_string(),
+            mime_type: "text/plain".to_string(),
+            data: b"numbers".to_vec(),
+        }]);
+
+        let sent = client.send_email(outgoing).await.unwrap();
+        let read = client.read_message(&sent.message.id).await.unwrap();
+        assert_eq!(read.subject, "Report");
+        assert_eq!(read.from, MOCK_SENDER);
+        assert_eq!(read.body, "See attached.");
+        assert_eq!(read.label_ids, vec!["SENT"]);
+        assert_eq!(read.attachments.len(), 1);
+        assert_eq!(read.attachments[0].filename, "report.txt");
+
+        let path = std::env::temp_dir().join(format!("gmail-mcp-mock-{}", uuid::Uuid::new_v4()));
+        let id = read.attachments[0].id.clone();
+        let fetched = client.get_attachment(&sent.message.id, &id).await.unwrap();
+        assert_eq!((fetched.size, fetched.data.as_str()), (7, "bnVtYmVycw"));
+        assert!(client.get_attachment(&sent.message.id, "missing").await.is_err());
+        assert_eq!(client.save_attachment(&sent.message.id, &id, &path).await.unwrap(), 7);
+        assert_eq!(std::fs::read(&path).unwrap(), b"numbers");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_search_operators() {
+        let client = MockGmailClient::sample();
+        let invoice = client.search_messages("from:bob is:unread invoice", None).await.unwrap();
+        assert_eq!(invoice.len(), 1);
+        assert_eq!(invoice[0].subject, "Invoice #1042");
+
+        client.delete_message(&invoice[0].id).await.unwrap();
+        assert_eq!(client.search_messages("in:inbox", None).await.unwrap().len(), 2);
+        assert_eq!(client.search_messages("in:trash", None).await.unwrap().len(), 1);
+        assert_eq!(client.search_messages("-from:alice", Some(1)).await.unwrap()[0].subject, "Weekly digest");
+    }
+
+    #[tokio::test]
+    async fn test_reply_joins_thread() {
+        let client = MockGmailClient::sample();
+        let original = client.search_messages("kickoff", None).await.unwrap().remove(0);
+        let context = client.reply_context(&original.id).await.unwrap();
+
+        let mut reply = params(&context.reply_recipients[0], &context.subject, "Works for me.");
+        reply.in_reply_to = Some(context.in_reply_to);
+        let sent = client.send_email(reply).await.unwrap();
+
+        let thread = client.read_thread(&original.thread_id).await.unwrap();
+        assert_eq!(thread.len(), 2);
+        assert_eq!(thread[1].id, sent.message.id);
+        assert_eq!(thread[1].subject, "Re: Project kickoff");
+    }
+}
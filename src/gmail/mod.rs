@@ -2,9 +2,16 @@
 //!
 //! Contains types, authentication, and client for interacting with the Gmail API.
 
+pub mod aliases;
+pub mod api;
 pub mod auth;
 pub mod client;
 pub mod filters;
 pub mod labels;
+pub mod last_seen;
+pub mod mock;
+pub mod quota;
+pub mod saved_queries;
+pub mod stream;
 pub mod types;
 pub mod utils;
@@ -0,0 +1,277 @@
+//! Quota accounting for Gmail API calls
+//!
+//! Gmail meters each user's traffic in quota units, with a documented cost per
+//! API method (250 units/second per user). Gmail doesn't report what has been
+//! used, so the client tallies the estimated cost of every request it issues,
+//! and can pace requests through a token bucket so bursts stay under the limit.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Gmail's per-user rate limit, in quota units per second
+pub const USER_UNITS_PER_SECOND: u64 = 250;
+
+/// Gmail API methods issued by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ApiMethod {
+    GetProfile,
+    MessagesSend,
+    DraftsCreate,
+    MessagesImport,
+    MessagesInsert,
+    MessagesGet,
+    MessagesList,
+    MessagesModify,
+    MessagesTrash,
+    MessagesBatchDelete,
+    ThreadsGet,
+    HistoryList,
+    AttachmentsGet,
+    LabelsList,
+    LabelsGet,
+    LabelsCreate,
+    LabelsUpdate,
+    LabelsDelete,
+    FiltersList,
+    FiltersGet,
+    FiltersCreate,
+    FiltersDelete,
+}
+
+impl ApiMethod {
+    /// Method name as it appears in the Gmail API reference
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::GetProfile => "users.getProfile",
+            Self::MessagesSend => "messages.send",
+            Self::DraftsCreate => "drafts.create",
+            Self::MessagesImport => "messages.import",
+            Self::MessagesInsert => "messages.insert",
+            Self::MessagesGet => "messages.get",
+            Self::MessagesList => "messages.list",
+            Self::MessagesModify => "messages.modify",
+            Self::MessagesTrash => "messages.trash",
+            Self::MessagesBatchDelete => "messages.batchDelete",
+            Self::ThreadsGet => "threads.get",
+            Self::HistoryList => "history.list",
+            Self::AttachmentsGet => "messages.attachments.get",
+            Self::LabelsList => "labels.list",
+            Self::LabelsGet => "labels.get",
+            Self::LabelsCreate => "labels.create",
+            Self::LabelsUpdate => "labels.update",
+            Self::LabelsDelete => "labels.delete",
+            Self::FiltersList => "settings.filters.list",
+            Self::FiltersGet => "settings.filters.get",
+            Self::FiltersCreate => "settings.filters.create",
+            Self::FiltersDelete => "settings.filters.delete",
+        }
+    }
+
+    /// Quota units charged per call, from Gmail's usage limits documentation
+    pub fn cost(self) -> u64 {
+        match self {
+            Self::MessagesSend => 100,
+            Self::MessagesBatchDelete => 50,
+            Self::MessagesImport | Self::MessagesInsert => 25,
+            Self::DraftsCreate | Self::ThreadsGet => 10,
+            Self::MessagesGet
+            | Self::MessagesList
+            | Self::MessagesModify
+            | Self::MessagesTrash
+            | Self::AttachmentsGet
+            | Self::LabelsCreate
+            | Self::LabelsUpdate
+            | Self::LabelsDelete
+            | Self::FiltersCreate
+            | Self::FiltersDelete => 5,
+            Self::HistoryList => 2,
+            Self::GetProfile
+            | Self::LabelsList
+            | Self::LabelsGet
+            | Self::FiltersList
+            | Self::FiltersGet => 1,
+        }
+    }
+}
+
+/// Calls made to one API method and the units they cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MethodUsage {
+    pub calls: u64,
+    pub units: u64,
+}
+
+/// Snapshot of the estimated quota used so far
+#[derive(Debug, Clone)]
+pub struct QuotaUsage {
+    /// Time since the client was created
+    pub elapsed: Duration,
+    pub total_calls: u64,
+    pub total_units: u64,
+    /// Per-method breakdown, most expensive first
+    pub methods: Vec<(ApiMethod, MethodUsage)>,
+}
+
+/// Token bucket refilled at a fixed number of quota units per second
+///
+/// The bucket holds at most one second's worth of units. A call takes its cost
+/// up front and, if that leaves the bucket in debt, waits until the debt is
+/// repaid; callers are served in arrival order and a single call costing more
+/// than the whole bucket still goes through.
+#[derive(Debug)]
+pub struct RateLimiter {
+    units_per_second: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    /// Create a full bucket refilled at `units_per_second`
+    pub fn new(units_per_second: u64) -> Self {
+        let units_per_second = units_per_second.max(1) as f64;
+        Self {
+            units_per_second,
+            state: Mutex::new(BucketState {
+                tokens: units_per_second,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Take `units` from the bucket, returning how long to wait before using them
+    fn reserve(&self, units: u64) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let refill = now.duration_since(state.refilled_at).as_secs_f64() * self.units_per_second;
+        state.tokens = (state.tokens + refill).min(self.units_per_second);
+        state.refilled_at = now;
+        state.tokens -= units as f64;
+
+        if state.tokens < 0.0 {
+            Duration::from_secs_f64(-state.tokens / self.units_per_second)
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// Wait until `units` are available
+    pub async fn acquire(&self, units: u64) {
+        let wait = self.reserve(units);
+        if !wait.is_zero() {
+            tracing::debug!("Rate limit: waiting {}ms for {} quota units", wait.as_millis(), units);
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Running tally of estimated quota units, shared by all requests of a client
+#[derive(Debug)]
+pub struct QuotaTracker {
+    started: Instant,
+    usage: Mutex<BTreeMap<ApiMethod, MethodUsage>>,
+    limiter: Option<RateLimiter>,
+}
+
+impl QuotaTracker {
+    /// Create an empty tracker that doesn't limit the request rate
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            usage: Mutex::new(BTreeMap::new()),
+            limiter: None,
+        }
+    }
+
+    /// Pace requests to `units_per_second`; 0 disables limiting
+    pub fn with_rate_limit(mut self, units_per_second: u64) -> Self {
+        self.limiter = (units_per_second > 0).then(|| RateLimiter::new(units_per_second));
+        self
+    }
+
+    /// Wait for rate limit capacity for `method`, then count the call
+    pub async fn acquire(&self, method: ApiMethod) {
+        if let Some(ref limiter) = self.limiter {
+            limiter.acquire(method.cost()).await;
+        }
+        self.record(method);
+    }
+
+    /// Count one call to `method`
+    pub fn record(&self, method: ApiMethod) {
+        let mut usage = self.usage.lock().unwrap();
+        let entry = usage.entry(method).or_default();
+        entry.calls += 1;
+        entry.units += method.cost();
+    }
+
+    /// Current totals
+    pub fn usage(&self) -> QuotaUsage {
+        let usage = self.usage.lock().unwrap();
+        let mut methods: Vec<(ApiMethod, MethodUsage)> =
+            usage.iter().map(|(method, used)| (*method, *used)).collect();
+        methods.sort_by_key(|(_, used)| std::cmp::Reverse(used.units));
+
+        QuotaUsage {
+            elapsed: self.started.elapsed(),
+            total_calls: methods.iter().map(|(_, used)| used.calls).sum(),
+            total_units: methods.iter().map(|(_, used)| used.units).sum(),
+            methods,
+        }
+    }
+}
+
+impl Default for QuotaTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_tracker_accumulates_costs() {
+        let tracker = QuotaTracker::new();
+        tracker.record(ApiMethod::MessagesGet);
+        tracker.record(ApiMethod::MessagesGet);
+        tracker.record(ApiMethod::MessagesSend);
+        tracker.record(ApiMethod::LabelsList);
+
+        let usage = tracker.usage();
+        assert_eq!(usage.total_calls, 4);
+        assert_eq!(usage.total_units, 5 + 5 + 100 + 1);
+        assert_eq!(usage.methods[0], (ApiMethod::MessagesSend, MethodUsage { calls: 1, units: 100 }));
+        assert_eq!(usage.methods[1], (ApiMethod::MessagesGet, MethodUsage { calls: 2, units: 10 }));
+    }
+
+    #[test]
+    fn test_rate_limiter_reserves_against_bucket() {
+        let limiter = RateLimiter::new(1000);
+
+        // A full bucket covers one second's worth of units without waiting
+        assert_eq!(limiter.reserve(600), Duration::ZERO);
+        assert_eq!(limiter.reserve(400), Duration::ZERO);
+
+        // Beyond that, callers wait for the refill, even above the bucket size
+        let wait = limiter.reserve(100);
+        assert!(wait > Duration::from_millis(90) && wait <= Duration::from_millis(100), "{:?}", wait);
+        let wait = limiter.reserve(2000);
+        assert!(wait > Duration::from_millis(2000), "{:?}", wait);
+    }
+
+    #[tokio::test]
+    async fn test_quota_tracker_acquire_records_usage() {
+        let tracker = QuotaTracker::new().with_rate_limit(USER_UNITS_PER_SECOND);
+        tracker.acquire(ApiMethod::MessagesSend).await;
+        tracker.acquire(ApiMethod::MessagesSend).await;
+
+        assert_eq!(tracker.usage().total_units, 200);
+    }
+}
@@ -0,0 +1,122 @@
+//! Saved search queries
+//!
+//! Named Gmail queries kept in `saved_queries.json` in the config directory,
+//! so a carefully built query can be rerun by name instead of retyped:
+//!
+//! ```json
+//! {
+//!   "unpaid-invoices": "from:billing@example.com subject:invoice -label:paid"
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use tokio::sync::Mutex;
+
+use crate::config::write_private_file;
+use crate::error::{ConfigError, GmailMcpError, Result, ValidationError};
+
+/// Named queries, persisted to a JSON file on every change
+#[derive(Debug, Default)]
+pub struct SavedQueries {
+    /// File the queries are written to; `None` keeps them in memory only
+    path: Option<PathBuf>,
+
+    /// Query strings keyed by name
+    queries: Mutex<BTreeMap<String, String>>,
+}
+
+impl SavedQueries {
+    /// Load saved queries from `path`; a missing file means none are saved yet
+    pub fn load(path: &Path) -> Result<Self> {
+        let queries = match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| {
+                GmailMcpError::Config(ConfigError::InvalidConfig {
+                    message: format!("{}: {}", path.display(), e),
+                })
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(GmailMcpError::Io(e)),
+        };
+
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            queries: Mutex::new(queries),
+        })
+    }
+
+    /// All saved queries as `(name, query)`, sorted by name
+    pub async fn list(&self) -> Vec<(String, String)> {
+        self.queries
+            .lock()
+            .await
+            .iter()
+            .map(|(name, query)| (name.clone(), query.clone()))
+            .collect()
+    }
+
+    /// The query saved under `name`
+    pub async fn get(&self, name: &str) -> Option<String> {
+        self.queries.lock().await.get(name.trim()).cloned()
+    }
+
+    /// Save `query` under `name`, returning whether an existing query was replaced
+    ///
+    /// An existing name is only replaced when `overwrite` is set.
+    pub async fn save(&self, name: &str, query: &str, overwrite: bool) -> Result<bool> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(GmailMcpError::Validation(ValidationError::MissingField {
+                field: "name".to_string(),
+            }));
+        }
+        if query.trim().is_empty() {
+            return Err(GmailMcpError::Validation(ValidationError::MissingField {
+                field: "query".to_string(),
+            }));
+        }
+
+        let mut queries = self.queries.lock().await;
+        if queries.contains_key(name) && !overwrite {
+            return Err(GmailMcpError::Validation(ValidationError::InvalidParameter {
+                name: "name".to_string(),
+                message: format!("a query named '{}' already exists; pass overwrite: true to replace it", name),
+            }));
+        }
+
+        let replaced = queries.insert(name.to_string(), query.trim().to_string()).is_some();
+
+        // Written while still holding the lock so concurrent saves land in order
+        if let Some(ref path) = self.path {
+            let contents = serde_json::to_string_pretty(&*queries)?;
+            write_private_file(path, contents.as_bytes()).await?;
+        }
+
+        Ok(replaced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_save_and_reload() {
+        let dir = std::env::temp_dir().join(format!("gmail-mcp-saved-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("saved_queries.json");
+
+        let saved = SavedQueries::load(&path).unwrap();
+        assert!(saved.list().await.is_empty());
+        assert!(!saved.save("invoices", "subject:invoice is:unread", false).await.unwrap());
+        assert!(saved.save("invoices", "subject:invoice", false).await.is_err());
+        assert!(saved.save("invoices", "subject:invoice", true).await.unwrap());
+
+        let reloaded = SavedQueries::load(&path).unwrap();
+        assert_eq!(reloaded.get(" invoices ").await.as_deref(), Some("subject:invoice"));
+        assert_eq!(reloaded.list().await.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,231 @@
+//! Streaming attachment decoding
+//!
+//! An attachment response is `{"size": N, "data": "<base64url>"}` with the
+//! whole file in one JSON string. Reading it as a `String` and decoding that
+//! into a `Vec<u8>` holds roughly twice the attachment in memory; these types
+//! instead pick the `data` value out of the response as it arrives and decode
+//! it into a writer a few bytes at a time.
+
+use std::io::Write;
+
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine;
+
+use crate::error::{GmailMcpError, Result, ValidationError};
+
+/// URL-safe engine accepting both padded and unpadded input
+const ENGINE: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::URL_SAFE,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Incremental base64url decoder writing decoded bytes to `W`
+///
+/// Standard-alphabet input (`+`, `/`) is accepted too, and whitespace is
+/// skipped. At most three characters are carried over between calls.
+pub struct Base64UrlDecoder<W: Write> {
+    writer: W,
+    /// Characters of an incomplete 4-character group from the previous call
+    pending: Vec<u8>,
+    /// Scratch buffer for the normalized characters of the current call
+    buffer: Vec<u8>,
+    written: u64,
+}
+
+impl<W: Write> Base64UrlDecoder<W> {
+    /// Decode into `writer`
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            pending: Vec::with_capacity(4),
+            buffer: Vec::new(),
+            written: 0,
+        }
+    }
+
+    /// Decode the next piece of base64 text
+    pub fn write(&mut self, input: &[u8]) -> Result<()> {
+        self.buffer.clear();
+        self.buffer.extend_from_slice(&self.pending);
+        self.buffer.extend(input.iter().filter(|b| !b.is_ascii_whitespace()).map(|&b| match b {
+            b'+' => b'-',
+            b'/' => b'_',
+            b => b,
+        }));
+
+        let complete = self.buffer.len() - self.buffer.len() % 4;
+        self.pending.clear();
+        self.pending.extend_from_slice(&self.buffer[complete..]);
+        if complete > 0 {
+            let decoded = ENGINE.decode(&self.buffer[..complete]).map_err(invalid_base64)?;
+            self.writer.write_all(&decoded)?;
+            self.written += decoded.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Decode any trailing unpadded characters, returning the writer and the bytes written
+    pub fn finish(mut self) -> Result<(W, u64)> {
+        if !self.pending.is_empty() {
+            let decoded = ENGINE.decode(&self.pending).map_err(invalid_base64)?;
+            self.writer.write_all(&decoded)?;
+            self.written += decoded.len() as u64;
+        }
+        Ok((self.writer, self.written))
+    }
+}
+
+fn invalid_base64(e: base64::DecodeError) -> GmailMcpError {
+    GmailMcpError::Validation(ValidationError::InvalidParameter {
+        name: "base64 data".to_string(),
+        message: e.to_string(),
+    })
+}
+
+/// Where `AttachmentDataExtractor` is in the response
+enum State {
+    /// Looking for the `"data"` key; holds how many of its bytes matched so far
+    Key(usize),
+    /// After the key, expecting `:`
+    Colon,
+    /// After the colon, expecting the opening quote
+    Quote,
+    /// Inside the value
+    Value,
+    /// Past the closing quote
+    Done,
+}
+
+/// Feeds the `data` string of an attachment response to a `Base64UrlDecoder`
+///
+/// Base64 text never contains quotes or escapes, so the value ends at the
+/// next `"`; the other fields are skipped.
+pub struct AttachmentDataExtractor<W: Write> {
+    decoder: Base64UrlDecoder<W>,
+    state: State,
+}
+
+const DATA_KEY: &[u8] = b"\"data\"";
+
+impl<W: Write> AttachmentDataExtractor<W> {
+    /// Decode the attachment into `writer`
+    pub fn new(writer: W) -> Self {
+        Self {
+            decoder: Base64UrlDecoder::new(writer),
+            state: State::Key(0),
+        }
+    }
+
+    /// Process the next chunk of the response body
+    pub fn write(&mut self, mut chunk: &[u8]) -> Result<()> {
+        while let Some((&byte, rest)) = chunk.split_first() {
+            match self.state {
+                State::Key(matched) => {
+                    self.state = if byte == DATA_KEY[matched] {
+                        if matched + 1 == DATA_KEY.len() {
+                            State::Colon
+                        } else {
+                            State::Key(matched + 1)
+                        }
+                    } else {
+                        State::Key(usize::from(byte == b'"'))
+                    };
+                }
+                State::Colon if byte.is_ascii_whitespace() => {}
+                // A `"data"` string that isn't a key, e.g. a value; keep looking
+                State::Colon if byte != b':' => self.state = State::Key(usize::from(byte == b'"')),
+                State::Colon => self.state = State::Quote,
+                State::Quote if byte.is_ascii_whitespace() => {}
+                State::Quote if byte != b'"' => self.state = State::Key(0),
+                State::Quote => self.state = State::Value,
+                State::Value => {
+                    let end = chunk.iter().position(|&b| b == b'"');
+                    self.decoder.write(&chunk[..end.unwrap_or(chunk.len())])?;
+                    match end {
+                        Some(end) => {
+                            self.state = State::Done;
+                            chunk = &chunk[end + 1..];
+                        }
+                        None => chunk = &[],
+                    }
+                    continue;
+                }
+                State::Done => return Ok(()),
+            }
+            chunk = rest;
+        }
+        Ok(())
+    }
+
+    /// Finish decoding, returning the writer and the decoded size
+    ///
+    /// Fails if the response held no complete `data` value.
+    pub fn finish(self) -> Result<(W, u64)> {
+        if !matches!(self.state, State::Done) {
+            return Err(GmailMcpError::Validation(ValidationError::InvalidParameter {
+                name: "attachment response".to_string(),
+                message: "no complete data field".to_string(),
+            }));
+        }
+        self.decoder.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::{STANDARD, URL_SAFE, URL_SAFE_NO_PAD};
+
+    /// Feed `input` in `size`-byte pieces
+    fn extract(input: &[u8], size: usize) -> Result<(Vec<u8>, u64)> {
+        let mut extractor = AttachmentDataExtractor::new(Vec::new());
+        for piece in input.chunks(size) {
+            extractor.write(piece)?;
+        }
+        extractor.finish()
+    }
+
+    #[test]
+    fn test_decoder_accepts_any_split() {
+        let raw: Vec<u8> = (0..=255).collect();
+        for encoded in [URL_SAFE_NO_PAD.encode(&raw), URL_SAFE.encode(&raw), STANDARD.encode(&raw)] {
+            for size in [1, 3, 4, 7, 1000] {
+                let mut decoder = Base64UrlDecoder::new(Vec::new());
+                for piece in encoded.as_bytes().chunks(size) {
+                    decoder.write(piece).unwrap();
+                }
+                assert_eq!(decoder.finish().unwrap(), (raw.clone(), raw.len() as u64));
+            }
+        }
+
+        let mut decoder = Base64UrlDecoder::new(Vec::new());
+        assert!(decoder.write(b"no*base64").is_err());
+    }
+
+    #[test]
+    fn test_extractor_finds_data_field() {
+        let body = br#"{ "attachmentId": "data", "size" : 5, "data" : "aGVsbG8" }"#;
+        assert_eq!(extract(body, 2).unwrap(), (b"hello".to_vec(), 5));
+        assert!(extract(br#"{"size": 5, "data": "aGVs"#, 4).is_err());
+        assert!(extract(br#"{"size": 0}"#, 4).is_err());
+    }
+
+    #[test]
+    fn test_large_attachment_stays_bounded() {
+        // 30 MiB, larger than any attachment Gmail accepts
+        let raw: Vec<u8> = (0..30 * 1024 * 1024).map(|i: u32| (i.wrapping_mul(2654435761) >> 24) as u8).collect();
+        let body = format!(r#"{{"size": {}, "data": "{}"}}"#, raw.len(), URL_SAFE_NO_PAD.encode(&raw));
+
+        // Network-sized chunks; the scratch buffer never grows past one of them
+        let mut extractor = AttachmentDataExtractor::new(Vec::with_capacity(raw.len()));
+        for piece in body.as_bytes().chunks(16 * 1024 + 1) {
+            extractor.write(piece).unwrap();
+            assert!(extractor.decoder.pending.len() < 4);
+            assert!(extractor.decoder.buffer.capacity() <= 2 * (16 * 1024 + 4));
+        }
+        let (decoded, written) = extractor.finish().unwrap();
+        assert_eq!(written, raw.len() as u64);
+        assert!(decoded == raw);
+    }
+}
@@ -124,6 +124,53 @@ pub struct MessageRef {
     pub thread_id: String,
 }
 
+/// A Gmail thread
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Thread {
+    /// Thread ID
+    pub id: String,
+
+    /// Messages in the thread, oldest first
+    #[serde(default)]
+    pub messages: Vec<Message>,
+}
+
+/// Response from history.list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryListResponse {
+    /// Changes since the start history ID, oldest first
+    #[serde(default)]
+    pub history: Vec<HistoryRecord>,
+
+    /// Next page token
+    pub next_page_token: Option<String>,
+
+    /// The mailbox's current history ID
+    pub history_id: Option<String>,
+}
+
+/// One mailbox change in a history listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryRecord {
+    /// History ID of the change
+    pub id: String,
+
+    /// Messages added to the mailbox by this change
+    #[serde(default)]
+    pub messages_added: Vec<HistoryMessageAdded>,
+}
+
+/// A message added to the mailbox
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryMessageAdded {
+    /// The added message (ID, thread ID and labels only)
+    pub message: Message,
+}
+
 /// A Gmail label
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -211,6 +258,10 @@ pub struct UpdateLabelRequest {
     /// Label list visibility
     #[serde(skip_serializing_if = "Option::is_none")]
     pub label_list_visibility: Option<String>,
+
+    /// Text and background color
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<LabelColor>,
 }
 
 /// Request to modify message labels
@@ -227,7 +278,7 @@ pub struct ModifyMessageRequest {
 }
 
 /// Gmail filter criteria
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct FilterCriteria {
     /// Sender email to match
@@ -277,7 +328,7 @@ pub enum SizeComparison {
 }
 
 /// Gmail filter action
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct FilterAction {
     /// Label IDs to add
@@ -315,6 +366,26 @@ pub struct FilterList {
     pub filter: Vec<Filter>,
 }
 
+/// Mailbox profile of the authenticated user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    /// The user's email address
+    pub email_address: String,
+
+    /// Total messages in the mailbox
+    #[serde(default)]
+    pub messages_total: Option<i64>,
+
+    /// Total threads in the mailbox
+    #[serde(default)]
+    pub threads_total: Option<i64>,
+
+    /// Current mailbox history ID
+    #[serde(default)]
+    pub history_id: Option<String>,
+}
+
 /// Gmail draft
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Draft {
@@ -337,6 +408,25 @@ pub struct SendMessageRequest {
     pub thread_id: Option<String>,
 }
 
+/// Request to permanently delete messages in bulk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchDeleteRequest {
+    /// Message IDs to delete
+    pub ids: Vec<String>,
+}
+
+/// Request to import or insert a raw message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawMessageRequest {
+    /// Raw RFC822 message (base64url encoded)
+    pub raw: String,
+
+    /// Label IDs to apply to the message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub label_ids: Vec<String>,
+}
+
 /// Request to create a draft
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateDraftRequest {
@@ -364,6 +454,22 @@ pub struct EmailContent {
     pub html: String,
 }
 
+/// Per-recipient delivery status from a bounce report (RFC 3464)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeliveryStatus {
+    /// Recipient the report is about (`Final-Recipient`)
+    pub recipient: String,
+
+    /// Delivery action: failed, delayed, delivered, relayed or expanded
+    pub action: String,
+
+    /// Enhanced status code, e.g. `5.1.1`
+    pub status: String,
+
+    /// Remote server's diagnostic, if given
+    pub diagnostic_code: Option<String>,
+}
+
 /// Email attachment info
 #[derive(Debug, Clone)]
 pub struct EmailAttachment {
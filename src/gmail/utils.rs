@@ -5,7 +5,7 @@
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 
 use crate::error::{Result, ValidationError};
-use crate::gmail::types::{EmailAttachment, EmailContent, MessagePart};
+use crate::gmail::types::{DeliveryStatus, EmailAttachment, EmailContent, Header, MessagePart};
 
 /// Validate an email address
 pub fn validate_email(email: &str) -> bool {
@@ -17,7 +17,8 @@ pub fn validate_email(email: &str) -> bool {
     let (local, domain) = (parts[0], parts[1]);
 
     // Check basic requirements
-    !local.is_empty()
+    !email.chars().any(char::is_control)
+        && !local.is_empty()
         && !domain.is_empty()
         && !local.contains(' ')
         && !domain.contains(' ')
@@ -40,9 +41,44 @@ pub fn encode_mime_header(text: &str) -> String {
     )
 }
 
+/// Format a `From` mailbox with a display name: `"Name" <address>`
+///
+/// Non-ASCII names are sent as an RFC 2047 encoded word, which can't appear
+/// inside a quoted string; ASCII names are quoted with `"` and `\` escaped.
+pub fn format_from(name: &str, address: &str) -> String {
+    let name = sanitize_header_value(name);
+    if name.is_empty() {
+        return address.to_string();
+    }
+
+    let display = if name.is_ascii() {
+        format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        encode_mime_header(&name)
+    };
+    format!("{} <{}>", display, address)
+}
+
+/// Collapse CR/LF in a header value so it cannot start a new header line
+pub fn sanitize_header_value(value: &str) -> String {
+    value
+        .split(['\r', '\n'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// Encode a raw email message for Gmail API (base64url, no padding)
 pub fn encode_raw_message(message: &str) -> String {
-    URL_SAFE_NO_PAD.encode(message.as_bytes())
+    encode_raw_bytes(message.as_bytes())
+}
+
+/// Encode raw message bytes for Gmail API (base64url, no padding)
+///
+/// Use this for MIME sources read from disk, which need not be valid UTF-8.
+pub fn encode_raw_bytes(message: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(message)
 }
 
 /// Decode base64url data from Gmail API
@@ -77,96 +113,102 @@ pub fn decode_base64url_string(data: &str) -> Result<String> {
 }
 
 /// Convert HTML to readable plain text
-/// Strips tags and decodes common HTML entities
+///
+/// Tags are stripped (along with `<head>`, `<style>` and `<script>` content),
+/// block elements and `<br>` become line breaks, list items become bullets,
+/// links are written as `text (url)`, and entities are decoded.
 pub fn html_to_text(html: &str) -> String {
-    let mut text = html.to_string();
-    
-    // Replace common block elements with newlines
-    let block_tags = ["<br>", "<br/>", "<br />", "</p>", "</div>", "</tr>", "</li>", "</h1>", "</h2>", "</h3>", "</h4>", "</h5>", "</h6>"];
-    for tag in block_tags {
-        text = text.replace(tag, "\n");
-    }
-    
-    // Replace list items with bullet points
-    text = text.replace("<li>", "• ");
-    
-    // Remove all remaining HTML tags
-    let mut result = String::new();
-    let mut in_tag = false;
-    let mut in_style = false;
-    let mut in_script = false;
-    
-    let chars: Vec<char> = text.chars().collect();
-    let mut i = 0;
-    
-    while i < chars.len() {
-        let c = chars[i];
-        
-        // Check for style/script start
-        if i + 6 < chars.len() {
-            let slice: String = chars[i..i+7].iter().collect();
-            if slice.to_lowercase() == "<style>" || slice.to_lowercase() == "<style " {
-                in_style = true;
+    let mut out = String::new();
+    let mut rest = html;
+    let mut skipping: Option<&'static str> = None;
+    let mut link: Option<(String, usize)> = None;
+
+    while let Some(lt) = rest.find('<') {
+        if skipping.is_none() {
+            out.push_str(&rest[..lt]);
+        }
+        let after = &rest[lt..];
+
+        // Comments may contain '>', so they end at "-->"
+        if after.starts_with("<!--") {
+            rest = after.find("-->").map(|end| &after[end + 3..]).unwrap_or("");
+            continue;
+        }
+
+        let Some(gt) = after.find('>') else {
+            if skipping.is_none() {
+                out.push_str(after);
             }
-            if slice.to_lowercase() == "<script" {
-                in_script = true;
+            rest = "";
+            break;
+        };
+        let tag = &after[1..gt];
+        rest = &after[gt + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        if let Some(skipped) = skipping {
+            if closing && name == skipped {
+                skipping = None;
             }
+            continue;
         }
-        
-        // Check for style/script end
-        if i + 7 < chars.len() {
-            let slice: String = chars[i..i+8].iter().collect();
-            if slice.to_lowercase() == "</style>" {
-                in_style = false;
-                i += 8;
-                continue;
+
+        match name.as_str() {
+            "head" | "style" | "script" | "title" if !closing && !tag.ends_with('/') => {
+                skipping = Some(match name.as_str() {
+                    "head" => "head",
+                    "style" => "style",
+                    "script" => "script",
+                    _ => "title",
+                });
             }
-        }
-        if i + 8 < chars.len() {
-            let slice: String = chars[i..i+9].iter().collect();
-            if slice.to_lowercase() == "</script>" {
-                in_script = false;
-                i += 9;
-                continue;
+            "br" | "hr" => out.push('\n'),
+            "p" | "div" | "tr" | "table" | "ul" | "ol" | "blockquote" | "pre" | "h1" | "h2" | "h3"
+            | "h4" | "h5" | "h6" => out.push('\n'),
+            "li" if !closing => out.push_str("\n• "),
+            "li" => out.push('\n'),
+            "td" | "th" if closing => out.push(' '),
+            "a" if !closing => {
+                link = html_attribute(tag, "href").map(|href| (href, out.len()));
+            }
+            "a" => {
+                if let Some((href, start)) = link.take() {
+                    let text = out[start..].trim();
+                    let target = href.strip_prefix("mailto:").unwrap_or(&href);
+                    if !href.is_empty() && !href.starts_with('#') && text != target && !text.is_empty() {
+                        out.push_str(&format!(" ({})", href));
+                    } else if text.is_empty() && !href.starts_with('#') {
+                        out.push_str(&href);
+                    }
+                }
             }
+            _ => {}
         }
-        
-        if c == '<' {
-            in_tag = true;
-        } else if c == '>' {
-            in_tag = false;
-        } else if !in_tag && !in_style && !in_script {
-            result.push(c);
-        }
-        
-        i += 1;
-    }
-    
-    // Decode common HTML entities
-    result = result
-        .replace("&nbsp;", " ")
-        .replace("&amp;", "&")
-        .replace("&lt;", "<")
-        .replace("&gt;", ">")
-        .replace("&quot;", "\"")
-        .replace("&#39;", "'")
-        .replace("&apos;", "'")
-        .replace("&#x27;", "'")
-        .replace("&mdash;", "—")
-        .replace("&ndash;", "–")
-        .replace("&hellip;", "...")
-        .replace("&copy;", "©")
-        .replace("&reg;", "®")
-        .replace("&trade;", "™");
-    
-    // Collapse multiple whitespace/newlines
+    }
+    if skipping.is_none() {
+        out.push_str(rest);
+    }
+
+    let result = decode_html_entities(&out);
+
+    // Collapse runs of whitespace; keep single line breaks between blocks
     let mut prev_newline = false;
     let mut prev_space = false;
     let mut cleaned = String::new();
-    
+
     for c in result.chars() {
         if c == '\n' || c == '\r' {
             if !prev_newline {
+                if prev_space {
+                    cleaned.pop();
+                }
                 cleaned.push('\n');
                 prev_newline = true;
             }
@@ -182,21 +224,120 @@ pub fn html_to_text(html: &str) -> String {
             prev_space = false;
         }
     }
-    
+
     cleaned.trim().to_string()
 }
 
+/// Value of attribute `name` in the inside of a start tag (`a href="..."`)
+fn html_attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find(name) {
+        let start = from + pos;
+        from = start + name.len();
+
+        let preceded_by_space = lower[..start].ends_with(|c: char| c.is_ascii_whitespace());
+        let value = lower[from..].trim_start();
+        if !preceded_by_space || !value.starts_with('=') {
+            continue;
+        }
+
+        let value_start = tag.len() - value.len() + 1;
+        let value = tag[value_start..].trim_start();
+        return Some(match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or("").to_string(),
+            _ => value.split_whitespace().next().unwrap_or("").to_string(),
+        });
+    }
+    None
+}
+
+/// Decode named and numeric HTML character references
+pub fn decode_html_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        let after = &rest[amp..];
+        let end = after[1..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '#'))
+            .map(|i| i + 1)
+            .unwrap_or(after.len());
+        let entity = &after[1..end];
+
+        let decoded = if let Some(num) = entity.strip_prefix('#') {
+            let code = match num.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => num.parse().ok(),
+            };
+            code.and_then(char::from_u32).map(String::from)
+        } else {
+            match entity {
+                "nbsp" => Some(" "),
+                "amp" => Some("&"),
+                "lt" => Some("<"),
+                "gt" => Some(">"),
+                "quot" => Some("\""),
+                "apos" => Some("'"),
+                "mdash" => Some("—"),
+                "ndash" => Some("–"),
+                "hellip" => Some("..."),
+                "copy" => Some("©"),
+                "reg" => Some("®"),
+                "trade" => Some("™"),
+                "rsquo" | "lsquo" => Some("'"),
+                "rdquo" | "ldquo" => Some("\""),
+                "bull" => Some("•"),
+                "euro" => Some("€"),
+                _ => None,
+            }
+            .map(String::from)
+        };
+
+        match decoded {
+            Some(decoded) => {
+                out.push_str(&decoded);
+                rest = after[end..].strip_prefix(';').unwrap_or(&after[end..]);
+            }
+            None => {
+                out.push('&');
+                rest = &after[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
 /// Recursively extract email body content from MIME message parts
+///
+/// Like a mail client, this picks one representation per container instead of
+/// concatenating every text part: the richest child of `multipart/alternative`,
+/// the root of `multipart/related` and the signed content of `multipart/signed`.
+/// Only `multipart/mixed` (and unknown multiparts) join their children in order.
 pub fn extract_email_content(message_part: &MessagePart) -> EmailContent {
     let mut content = EmailContent::default();
 
-    let mime_type = message_part.mime_type.as_deref().unwrap_or("");
-    
+    let mime_type = message_part
+        .mime_type
+        .as_deref()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    // Text parts with a filename are attachments, not body
+    let is_attachment = message_part
+        .filename
+        .as_deref()
+        .map(|f| !f.is_empty())
+        .unwrap_or(false);
+
     // If the part has a body with data, process it based on MIME type
     if let Some(ref body) = message_part.body {
         if let Some(ref data) = body.data {
             // Only decode text-based content, skip binary attachments
-            if mime_type.starts_with("text/") {
+            if mime_type.starts_with("text/") && !is_attachment {
                 match decode_base64url_string(data) {
                     Ok(decoded) => {
                         if mime_type == "text/plain" {
@@ -214,21 +355,35 @@ pub fn extract_email_content(message_part: &MessagePart) -> EmailContent {
         }
     }
 
-    // If the part has nested parts, recursively process them
-    // This handles multipart/alternative, multipart/mixed, multipart/related, etc.
-    for part in &message_part.parts {
-        let nested = extract_email_content(part);
-        if !nested.text.is_empty() {
-            if content.text.is_empty() {
-                content.text = nested.text;
-            } else {
-                content.text.push_str(&nested.text);
+    if message_part.parts.is_empty() {
+        return content;
+    }
+
+    match mime_type.as_str() {
+        "multipart/alternative" => {
+            // Alternatives are ordered from plainest to richest; keep the last
+            // non-empty representation of each kind
+            for part in &message_part.parts {
+                let nested = extract_email_content(part);
+                if !nested.text.is_empty() {
+                    content.text = nested.text;
+                }
+                if !nested.html.is_empty() {
+                    content.html = nested.html;
+                }
+            }
+        }
+        "multipart/related" | "multipart/signed" => {
+            // The first part is the root document / the signed content
+            if let Some(first) = message_part.parts.first() {
+                content = extract_email_content(first);
             }
         }
-        if !nested.html.is_empty() {
-            if content.html.is_empty() {
-                content.html = nested.html;
-            } else {
+        _ => {
+            // multipart/mixed and friends: sequential parts, join them
+            for part in &message_part.parts {
+                let nested = extract_email_content(part);
+                content.text.push_str(&nested.text);
                 content.html.push_str(&nested.html);
             }
         }
@@ -237,6 +392,80 @@ pub fn extract_email_content(message_part: &MessagePart) -> EmailContent {
     content
 }
 
+/// Extract delivery status reports from a `multipart/report` bounce message
+///
+/// Returns one entry per recipient found in `message/delivery-status` parts;
+/// empty for ordinary messages.
+pub fn extract_delivery_status(message_part: &MessagePart) -> Vec<DeliveryStatus> {
+    let mime_type = message_part.mime_type.as_deref().unwrap_or("");
+
+    if mime_type.eq_ignore_ascii_case("message/delivery-status") {
+        return message_part
+            .body
+            .as_ref()
+            .and_then(|b| b.data.as_deref())
+            .and_then(|data| decode_base64url(data).ok())
+            .map(|bytes| parse_delivery_status(&String::from_utf8_lossy(&bytes)))
+            .unwrap_or_default();
+    }
+
+    message_part
+        .parts
+        .iter()
+        .flat_map(extract_delivery_status)
+        .collect()
+}
+
+/// Parse the body of a `message/delivery-status` part
+///
+/// The body is a per-message field block followed by one block per
+/// recipient, separated by blank lines.
+pub fn parse_delivery_status(body: &str) -> Vec<DeliveryStatus> {
+    let normalized = body.replace("\r\n", "\n");
+
+    normalized
+        .split("\n\n")
+        .filter_map(|block| {
+            // Unfold continuation lines before splitting into fields
+            let mut fields: Vec<(String, String)> = Vec::new();
+            for line in block.lines() {
+                if line.starts_with([' ', '\t']) {
+                    if let Some((_, value)) = fields.last_mut() {
+                        value.push(' ');
+                        value.push_str(line.trim());
+                    }
+                } else if let Some((name, value)) = line.split_once(':') {
+                    fields.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+                }
+            }
+
+            let field = |name: &str| {
+                fields
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, v)| v.clone())
+            };
+
+            // Values like "rfc822; bob@example.com" carry a type prefix
+            let strip_type = |value: String| match value.split_once(';') {
+                Some((_, rest)) => rest.trim().to_string(),
+                None => value,
+            };
+
+            let recipient = field("final-recipient")
+                .or_else(|| field("original-recipient"))
+                .map(strip_type)?;
+
+            Some(DeliveryStatus {
+                recipient,
+                action: field("action").unwrap_or_default().to_ascii_lowercase(),
+                status: field("status").unwrap_or_default(),
+                diagnostic_code: field("diagnostic-code"),
+            })
+        })
+        .collect()
+}
+
 /// Extract attachment information from message parts
 pub fn extract_attachments(message_part: &MessagePart) -> Vec<EmailAttachment> {
     let mut attachments = Vec::new();
@@ -269,6 +498,39 @@ fn extract_attachments_recursive(part: &MessagePart, attachments: &mut Vec<Email
     }
 }
 
+/// Whether an attachment matches any of `mime_types` or `extensions`
+///
+/// MIME types compare case-insensitively with the declared type, and one
+/// ending in `/*` matches the whole family (`image/*`). Extensions compare with
+/// the filename, with or without the leading dot. With both lists empty every
+/// attachment matches.
+pub fn attachment_matches(attachment: &EmailAttachment, mime_types: &[String], extensions: &[String]) -> bool {
+    if mime_types.is_empty() && extensions.is_empty() {
+        return true;
+    }
+
+    let declared = attachment.mime_type.to_ascii_lowercase();
+    let type_matches = mime_types.iter().any(|pattern| {
+        let pattern = pattern.trim().to_ascii_lowercase();
+        match pattern.strip_suffix("/*") {
+            Some(family) => declared.split('/').next() == Some(family),
+            None => declared == pattern,
+        }
+    });
+
+    let extension = std::path::Path::new(&attachment.filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    let extension_matches = extension.is_some_and(|extension| {
+        extensions
+            .iter()
+            .any(|wanted| wanted.trim().trim_start_matches('.').eq_ignore_ascii_case(&extension))
+    });
+
+    type_matches || extension_matches
+}
+
 /// Find header value by name (case-insensitive)
 pub fn find_header<'a>(part: &'a MessagePart, name: &str) -> Option<&'a str> {
     part.headers
@@ -277,6 +539,227 @@ pub fn find_header<'a>(part: &'a MessagePart, name: &str) -> Option<&'a str> {
         .map(|h| h.value.as_str())
 }
 
+/// Get all headers of a message part in their original order
+///
+/// Unlike `find_header`, repeated headers such as `Received` are all kept.
+pub fn get_message_headers(part: &MessagePart) -> Vec<Header> {
+    part.headers.clone()
+}
+
+/// Find all values of a header by name (case-insensitive), in order
+pub fn find_headers<'a>(part: &'a MessagePart, name: &str) -> Vec<&'a str> {
+    part.headers
+        .iter()
+        .filter(|h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str())
+        .collect()
+}
+
+/// Validate a label change: at least one label must be given, and no label
+/// may be both added and removed
+pub fn validate_label_changes(add: Option<&[String]>, remove: Option<&[String]>) -> Result<()> {
+    let add = add.unwrap_or_default();
+    let remove = remove.unwrap_or_default();
+
+    if add.is_empty() && remove.is_empty() {
+        return Err(crate::error::GmailMcpError::Validation(ValidationError::InvalidParameter {
+            name: "labelIds".to_string(),
+            message: "at least one label to add or remove is required".to_string(),
+        }));
+    }
+
+    let overlap: Vec<&str> = add
+        .iter()
+        .filter(|id| remove.contains(id))
+        .map(String::as_str)
+        .collect();
+    if !overlap.is_empty() {
+        return Err(crate::error::GmailMcpError::Validation(ValidationError::InvalidParameter {
+            name: "labelIds".to_string(),
+            message: format!("labels cannot be both added and removed: {}", overlap.join(", ")),
+        }));
+    }
+
+    Ok(())
+}
+
+/// Builder for Gmail search queries
+///
+/// Values are quoted when they contain spaces or query syntax, so user input
+/// cannot change the meaning of the query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryBuilder {
+    terms: Vec<String>,
+}
+
+impl QueryBuilder {
+    /// Create an empty query
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match the sender
+    pub fn from(self, value: &str) -> Self {
+        self.field("from", value)
+    }
+
+    /// Match a recipient
+    pub fn to(self, value: &str) -> Self {
+        self.field("to", value)
+    }
+
+    /// Match words in the subject
+    pub fn subject(self, value: &str) -> Self {
+        self.field("subject", value)
+    }
+
+    /// Match messages with a label (by name)
+    pub fn label(self, value: &str) -> Self {
+        self.field("label", value)
+    }
+
+    /// Match messages in a folder such as `inbox`, `trash` or `spam`
+    pub fn in_folder(self, value: &str) -> Self {
+        self.field("in", value)
+    }
+
+    /// Match messages from a mailing list
+    pub fn list(self, value: &str) -> Self {
+        self.field("list", value)
+    }
+
+    /// Match messages received after a date
+    pub fn after(self, date: time::Date) -> Self {
+        self.raw(format!("after:{}", format_query_date(date)))
+    }
+
+    /// Match messages received before a date
+    pub fn before(self, date: time::Date) -> Self {
+        self.raw(format!("before:{}", format_query_date(date)))
+    }
+
+    /// Match messages with attachments
+    pub fn has_attachment(self) -> Self {
+        self.raw("has:attachment")
+    }
+
+    /// Match unread messages
+    pub fn is_unread(self) -> Self {
+        self.raw("is:unread")
+    }
+
+    /// Match an exact phrase anywhere in the message
+    pub fn text(self, phrase: &str) -> Self {
+        self.raw(format!("\"{}\"", strip_quotes(phrase)))
+    }
+
+    /// Match any one of several sub-queries
+    pub fn any_of(self, alternatives: impl IntoIterator<Item = QueryBuilder>) -> Self {
+        let parts: Vec<String> = alternatives
+            .into_iter()
+            .map(|q| q.build())
+            .filter(|q| !q.is_empty())
+            .collect();
+        match parts.len() {
+            0 => self,
+            1 => self.raw(parts.into_iter().next().unwrap_or_default()),
+            _ => self.raw(format!("({})", parts.join(" OR "))),
+        }
+    }
+
+    /// Match a `field:value` term, quoting the value when needed
+    pub fn field(self, field: &str, value: &str) -> Self {
+        self.raw(format!("{}:{}", field, quote_query_value(value)))
+    }
+
+    /// Append a term verbatim, for syntax the builder doesn't cover
+    pub fn raw(mut self, term: impl Into<String>) -> Self {
+        self.terms.push(term.into());
+        self
+    }
+
+    /// Render the query string
+    pub fn build(&self) -> String {
+        self.terms.join(" ")
+    }
+}
+
+/// Quote a search value if it contains whitespace or query syntax
+///
+/// Gmail has no escape for `"` inside a quoted phrase, so embedded quotes are dropped.
+pub fn quote_query_value(value: &str) -> String {
+    let is_plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '@' | '.' | '_' | '-' | '+' | '/'));
+    if is_plain {
+        value.to_string()
+    } else {
+        format!("\"{}\"", strip_quotes(value))
+    }
+}
+
+fn strip_quotes(value: &str) -> String {
+    value.replace('"', " ").trim().to_string()
+}
+
+fn format_query_date(date: time::Date) -> String {
+    format!("{}/{:02}/{:02}", date.year(), u8::from(date.month()), date.day())
+}
+
+/// Unsubscribe targets parsed from `List-Unsubscribe` headers (RFC 2369 / RFC 8058)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnsubscribeInfo {
+    /// `mailto:` targets
+    pub mailto: Vec<String>,
+
+    /// `http:` / `https:` targets
+    pub http: Vec<String>,
+
+    /// Whether the sender supports one-click unsubscribe via POST
+    pub one_click: bool,
+}
+
+impl UnsubscribeInfo {
+    /// The https endpoint to POST to for one-click unsubscribe, if supported
+    pub fn one_click_url(&self) -> Option<&str> {
+        if !self.one_click {
+            return None;
+        }
+        self.http
+            .iter()
+            .map(String::as_str)
+            .find(|u| u.to_ascii_lowercase().starts_with("https://"))
+    }
+}
+
+/// Parse `List-Unsubscribe` and `List-Unsubscribe-Post` header values
+///
+/// Targets are the angle-bracketed URIs in `List-Unsubscribe`; anything that
+/// is neither mailto nor http(s) is ignored.
+pub fn parse_list_unsubscribe(list_unsubscribe: &str, list_unsubscribe_post: Option<&str>) -> UnsubscribeInfo {
+    let mut info = UnsubscribeInfo::default();
+
+    for segment in list_unsubscribe.split('<').skip(1) {
+        let Some(end) = segment.find('>') else {
+            continue;
+        };
+        let uri: String = segment[..end].chars().filter(|c| !c.is_whitespace()).collect();
+        let lower = uri.to_ascii_lowercase();
+        if lower.starts_with("mailto:") {
+            info.mailto.push(uri);
+        } else if lower.starts_with("https://") || lower.starts_with("http://") {
+            info.http.push(uri);
+        }
+    }
+
+    info.one_click = list_unsubscribe_post
+        .map(|v| v.trim().eq_ignore_ascii_case("List-Unsubscribe=One-Click"))
+        .unwrap_or(false);
+
+    info
+}
+
 /// Email content types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MimeType {
@@ -312,7 +795,7 @@ pub struct AttachmentData {
 }
 
 /// Parameters for creating an email message
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct EmailParams {
     pub to: Vec<String>,
     pub subject: String,
@@ -321,33 +804,217 @@ pub struct EmailParams {
     pub mime_type: Option<MimeType>,
     pub cc: Option<Vec<String>>,
     pub bcc: Option<Vec<String>>,
+    /// Address replies should go to, if different from the sender
+    pub reply_to: Option<String>,
     pub thread_id: Option<String>,
     pub in_reply_to: Option<String>,
+    /// Full `References` chain; defaults to `in_reply_to` when unset
+    pub references: Option<String>,
+    /// `Message-ID` header to write; left to Gmail when unset
+    pub message_id: Option<String>,
     pub attachments: Option<Vec<AttachmentData>>,
+    /// Extra headers such as `X-Priority`; see `validate_custom_header`
+    pub headers: Option<Vec<Header>>,
+    /// Leave the `Bcc:` header out of the serialized message.
+    ///
+    /// Gmail derives recipients from the raw headers and strips `Bcc:` itself
+    /// when sending, and drafts need it to remember their Bcc recipients, so the
+    /// header is kept by default. Set this when the MIME output is exported or
+    /// shared, where the header would reveal the Bcc recipients; the Gmail API
+    /// offers no separate envelope, so such a message won't reach them if sent.
+    pub omit_bcc_header: bool,
+    /// Signature appended to the plain and HTML bodies
+    pub signature: Option<Signature>,
+    /// `From` header value, e.g. from `format_from`; `me` when unset
+    pub from: Option<String>,
 }
 
-/// Load an attachment from a file path
-pub fn load_attachment(path: &str) -> Result<AttachmentData> {
-    use std::path::Path;
+/// Signature appended to outgoing mail
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// Plain text version
+    pub text: String,
+    /// HTML version; derived from `text` when not given
+    pub html: String,
+}
 
-    let path = Path::new(path);
-    if !path.exists() {
-        return Err(crate::error::GmailMcpError::Validation(
-            ValidationError::FileNotFound {
-                path: path.display().to_string(),
-            },
-        ));
+impl Signature {
+    /// Build a signature, deriving the HTML version from the text if needed
+    ///
+    /// Returns `None` when both versions are blank.
+    pub fn new(text: Option<String>, html: Option<String>) -> Option<Self> {
+        let text = text.filter(|t| !t.trim().is_empty());
+        let html = html.filter(|h| !h.trim().is_empty());
+
+        match (text, html) {
+            (None, None) => None,
+            (Some(text), None) => Some(Self {
+                html: escape_html(&text).replace('\n', "<br>"),
+                text,
+            }),
+            (text, Some(html)) => Some(Self {
+                text: text.unwrap_or_else(|| html_to_text(&html)),
+                html,
+            }),
+        }
     }
 
-    let filename = path
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| "attachment".to_string());
+    /// Append after a plain body, behind the conventional "-- " separator line
+    pub fn append_to_text(&self, body: &str) -> String {
+        format!("{}\n\n-- \n{}", body.trim_end(), self.text)
+    }
 
-    let data = std::fs::read(path)?;
+    /// Append to an HTML body, inside `</body>` when there is one
+    pub fn append_to_html(&self, html: &str) -> String {
+        let block = format!("<br><br>-- <br>\n<div class=\"signature\">{}</div>", self.html);
+        match html.to_ascii_lowercase().rfind("</body>") {
+            Some(pos) => format!("{}{}{}", &html[..pos], block, &html[pos..]),
+            None => format!("{}{}", html, block),
+        }
+    }
+}
+
+/// Escape text for inclusion in HTML
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Headers that `create_email_message` manages and callers may not override
+const PROTECTED_HEADERS: &[&str] = &[
+    "from",
+    "to",
+    "cc",
+    "bcc",
+    "reply-to",
+    "subject",
+    "date",
+    "message-id",
+    "in-reply-to",
+    "references",
+    "mime-version",
+    "content-type",
+    "content-transfer-encoding",
+    "content-disposition",
+];
+
+/// Validate a caller-supplied header for an outgoing message
+///
+/// Rejects protected headers, malformed names, and values containing CR or LF
+/// (which would let a value inject extra headers or a body).
+pub fn validate_custom_header(header: &Header) -> Result<()> {
+    let invalid = |message: String| {
+        crate::error::GmailMcpError::Validation(ValidationError::InvalidParameter {
+            name: "headers".to_string(),
+            message,
+        })
+    };
+
+    let name_ok = !header.name.is_empty()
+        && header
+            .name
+            .bytes()
+            .all(|b| b.is_ascii_graphic() && b != b':');
+    if !name_ok {
+        return Err(invalid(format!("invalid header name '{}'", header.name)));
+    }
+
+    if PROTECTED_HEADERS.contains(&header.name.to_ascii_lowercase().as_str()) {
+        return Err(invalid(format!("header '{}' cannot be overridden", header.name)));
+    }
+
+    if header.value.contains(['\r', '\n']) {
+        return Err(invalid(format!("value of '{}' must not contain line breaks", header.name)));
+    }
+
+    Ok(())
+}
 
-    // Guess MIME type from extension
-    let mime_type = match path.extension().and_then(|e| e.to_str()) {
+/// Generate a `Message-ID` of the form `<uuid@domain>` for a sender address
+pub fn generate_message_id(sender_email: &str) -> String {
+    let domain = sender_email
+        .rsplit_once('@')
+        .map(|(_, d)| d.trim_end_matches('>').trim())
+        .filter(|d| !d.is_empty())
+        .unwrap_or("gmail.com");
+    format!("<{}@{}>", uuid::Uuid::new_v4(), domain)
+}
+
+/// Bare addresses from an address-list header such as `From` or `Reply-To`
+///
+/// `"Doe, Jane" <jane@example.com>, bob@example.com` yields
+/// `["jane@example.com", "bob@example.com"]`.
+pub fn extract_addresses(header: &str) -> Vec<String> {
+    let mut mailboxes = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut in_angle = false;
+
+    for c in header.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '<' if !in_quotes => in_angle = true,
+            '>' if !in_quotes => in_angle = false,
+            ',' if !in_quotes && !in_angle => {
+                mailboxes.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    mailboxes.push(current);
+
+    mailboxes
+        .iter()
+        .filter_map(|mailbox| {
+            let address = match (mailbox.rfind('<'), mailbox.rfind('>')) {
+                (Some(start), Some(end)) if start < end => &mailbox[start + 1..end],
+                _ => mailbox.as_str(),
+            };
+            let address = address.trim();
+            (!address.is_empty()).then(|| address.to_string())
+        })
+        .collect()
+}
+
+/// Subject for a reply: the original's with a single "Re: " prefix
+pub fn reply_subject(subject: &str) -> String {
+    let subject = subject.trim();
+    if subject.get(..3).is_some_and(|prefix| prefix.eq_ignore_ascii_case("re:")) {
+        subject.to_string()
+    } else {
+        format!("Re: {}", subject)
+    }
+}
+
+/// Build the `References` header for a reply (RFC 5322 section 3.6.4)
+///
+/// The original's own `References` chain is kept and its `Message-ID` appended,
+/// falling back to its `In-Reply-To` when it has no `References`.
+pub fn build_references(
+    original_message_id: &str,
+    original_references: Option<&str>,
+    original_in_reply_to: Option<&str>,
+) -> String {
+    let mut ids: Vec<&str> = original_references
+        .filter(|r| !r.trim().is_empty())
+        .or(original_in_reply_to)
+        .map(|r| r.split_whitespace().collect())
+        .unwrap_or_default();
+
+    if !ids.contains(&original_message_id) {
+        ids.push(original_message_id);
+    }
+
+    ids.join(" ")
+}
+
+/// Guess a MIME type from a file extension
+pub fn mime_type_for_extension(extension: Option<&str>) -> &'static str {
+    match extension.map(|e| e.to_ascii_lowercase()).as_deref() {
         Some("pdf") => "application/pdf",
         Some("doc") => "application/msword",
         Some("docx") => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
@@ -364,7 +1031,188 @@ pub fn load_attachment(path: &str) -> Result<AttachmentData> {
         Some("zip") => "application/zip",
         _ => "application/octet-stream",
     }
-    .to_string();
+}
+
+/// Where an `attachments` entry points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentSource {
+    /// A path on the local filesystem
+    File,
+    /// An `http(s)://` URL to download
+    Url,
+    /// A URL with any other scheme (`file://`, `ftp://`, ...), which is refused
+    UnsupportedUrl,
+}
+
+/// Classify an `attachments` entry as a local path or a URL
+pub fn attachment_source(entry: &str) -> AttachmentSource {
+    let lower = entry.trim_start().to_ascii_lowercase();
+    if lower.starts_with("http://") || lower.starts_with("https://") {
+        AttachmentSource::Url
+    } else if lower.contains("://") {
+        AttachmentSource::UnsupportedUrl
+    } else {
+        AttachmentSource::File
+    }
+}
+
+/// Pick a filename for a downloaded attachment
+///
+/// A `Content-Disposition` filename wins, with the RFC 6266 `filename*=`
+/// form preferred over plain `filename=`; otherwise the last segment of the
+/// URL path is used, falling back to "attachment".
+pub fn attachment_filename_from_url(url: &reqwest::Url, content_disposition: Option<&str>) -> String {
+    let param = |name: &str| {
+        content_disposition?.split(';').map(str::trim).find_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().trim_matches('"').to_string())
+        })
+    };
+    // `filename*=UTF-8''Q3%20report.pdf`: charset, optional language, then percent-encoded
+    let extended = param("filename*").and_then(|value| {
+        let (charset, rest) = value.split_once('\'')?;
+        let (_, encoded) = rest.split_once('\'')?;
+        let decoded = urlencoding::decode(encoded).ok()?;
+        (charset.eq_ignore_ascii_case("utf-8") || decoded.is_ascii()).then(|| decoded.into_owned())
+    });
+    let from_header = extended
+        .filter(|name| !name.is_empty())
+        .or_else(|| param("filename"));
+    let from_path = || {
+        url.path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| {
+                urlencoding::decode(segment)
+                    .map(|s| s.into_owned())
+                    .unwrap_or_else(|_| segment.to_string())
+            })
+    };
+
+    from_header
+        .filter(|name| !name.is_empty())
+        .or_else(from_path)
+        .unwrap_or_else(|| "attachment".to_string())
+}
+
+/// Whether `ip` is a public address that URL attachments may be fetched from
+///
+/// Loopback, private, link-local, carrier-grade NAT and other special-purpose
+/// ranges are refused so a URL can't reach services on the local network.
+pub fn is_public_ip(ip: std::net::IpAddr) -> bool {
+    use std::net::IpAddr;
+
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_documentation()
+                || v4.is_multicast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public_ip(IpAddr::V4(v4)),
+            None => {
+                !(v6.is_loopback()
+                    || v6.is_unspecified()
+                    || v6.is_multicast()
+                    || v6.is_unique_local()
+                    || v6.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// Build an attachment from base64 content supplied by the client
+///
+/// Line breaks in the data are ignored; the MIME type defaults to a guess from
+/// the filename's extension.
+pub fn inline_attachment(filename: &str, mime_type: Option<&str>, data_base64: &str) -> Result<AttachmentData> {
+    if filename.trim().is_empty() {
+        return Err(crate::error::GmailMcpError::Validation(ValidationError::MissingField {
+            field: "filename".to_string(),
+        }));
+    }
+
+    let compact: String = data_base64.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    let data = decode_base64url(&compact)?;
+    let mime_type = mime_type
+        .filter(|m| !m.trim().is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            mime_type_for_extension(filename.rsplit_once('.').map(|(_, ext)| ext)).to_string()
+        });
+
+    Ok(AttachmentData {
+        filename: filename.to_string(),
+        mime_type,
+        data,
+    })
+}
+
+/// Approximate size of `len` bytes once base64-encoded into a MIME part
+///
+/// Base64 turns every 3 bytes into 4, and the encoded text is wrapped at 76
+/// characters per CRLF-terminated line.
+pub fn encoded_attachment_size(len: usize) -> usize {
+    let encoded = len.div_ceil(3) * 4;
+    encoded + encoded.div_ceil(76) * 2
+}
+
+/// Attachments to drop, largest first, so a message of `size` bytes fits in `limit`
+///
+/// `attachments` pairs each attachment's name with its encoded size. Returns
+/// the names to drop and the estimated message size without them; when even
+/// dropping every attachment isn't enough, all of them are listed.
+pub fn attachments_to_drop(
+    size: usize,
+    limit: usize,
+    attachments: &[(String, usize)],
+) -> (Vec<&str>, usize) {
+    let mut by_size: Vec<&(String, usize)> = attachments.iter().collect();
+    by_size.sort_by_key(|(_, encoded)| std::cmp::Reverse(*encoded));
+
+    let mut remaining = size;
+    let mut dropped = Vec::new();
+    for (name, encoded) in by_size {
+        if remaining <= limit {
+            break;
+        }
+        remaining = remaining.saturating_sub(*encoded);
+        dropped.push(name.as_str());
+    }
+
+    (dropped, remaining)
+}
+
+/// Load an attachment from a file path
+pub fn load_attachment(path: &str) -> Result<AttachmentData> {
+    use std::path::Path;
+
+    let path = Path::new(path);
+    if !path.exists() {
+        return Err(crate::error::GmailMcpError::Validation(
+            ValidationError::FileNotFound {
+                path: path.display().to_string(),
+            },
+        ));
+    }
+
+    let filename = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "attachment".to_string());
+
+    let data = std::fs::read(path)?;
+
+    let mime_type = mime_type_for_extension(path.extension().and_then(|e| e.to_str())).to_string();
 
     Ok(AttachmentData {
         filename,
@@ -386,7 +1234,25 @@ pub fn create_email_message(params: &EmailParams) -> Result<String> {
         }
     }
 
-    let encoded_subject = encode_mime_header(&params.subject);
+    if let Some(ref reply_to) = params.reply_to {
+        if !validate_email(reply_to) {
+            return Err(crate::error::GmailMcpError::Validation(
+                ValidationError::InvalidEmail {
+                    email: reply_to.clone(),
+                },
+            ));
+        }
+    }
+
+    for header in params.headers.iter().flatten() {
+        validate_custom_header(header)?;
+    }
+
+    // Every header value below goes through sanitize_header_value so untrusted
+    // input can't inject extra headers with embedded line breaks
+    let header_list = |values: &[String]| sanitize_header_value(&values.join(", "));
+
+    let encoded_subject = encode_mime_header(&sanitize_header_value(&params.subject));
     let has_attachments = params
         .attachments
         .as_ref()
@@ -397,36 +1263,65 @@ pub fn create_email_message(params: &EmailParams) -> Result<String> {
     let mime_type = params.mime_type.unwrap_or(MimeType::TextPlain);
     let use_html = params.html_body.is_some() && mime_type != MimeType::TextPlain;
 
+    // With only an HTML body, derive the plain part instead of leaving it empty
+    let mut plain_body = match params.html_body {
+        Some(ref html) if params.body.trim().is_empty() => html_to_text(html),
+        _ => params.body.clone(),
+    };
+    let mut html_body = params.html_body.clone().unwrap_or_else(|| params.body.clone());
+    if let Some(ref signature) = params.signature {
+        plain_body = signature.append_to_text(&plain_body);
+        html_body = signature.append_to_html(&html_body);
+    }
+
     let mut lines = Vec::new();
 
     // Headers
-    lines.push("From: me".to_string());
-    lines.push(format!("To: {}", params.to.join(", ")));
+    lines.push(format!(
+        "From: {}",
+        params.from.as_deref().map_or_else(|| "me".to_string(), sanitize_header_value)
+    ));
+    lines.push(format!("To: {}", header_list(&params.to)));
 
     if let Some(ref cc) = params.cc {
         if !cc.is_empty() {
-            lines.push(format!("Cc: {}", cc.join(", ")));
+            lines.push(format!("Cc: {}", header_list(cc)));
         }
     }
 
-    if let Some(ref bcc) = params.bcc {
+    if let Some(bcc) = params.bcc.as_ref().filter(|_| !params.omit_bcc_header) {
         if !bcc.is_empty() {
-            lines.push(format!("Bcc: {}", bcc.join(", ")));
+            lines.push(format!("Bcc: {}", header_list(bcc)));
         }
     }
 
+    if let Some(ref reply_to) = params.reply_to {
+        lines.push(format!("Reply-To: {}", sanitize_header_value(reply_to)));
+    }
+
     lines.push(format!("Subject: {}", encoded_subject));
 
+    if let Some(ref message_id) = params.message_id {
+        lines.push(format!("Message-ID: {}", sanitize_header_value(message_id)));
+    }
+
     if let Some(ref in_reply_to) = params.in_reply_to {
-        lines.push(format!("In-Reply-To: {}", in_reply_to));
-        lines.push(format!("References: {}", in_reply_to));
+        lines.push(format!("In-Reply-To: {}", sanitize_header_value(in_reply_to)));
+        lines.push(format!(
+            "References: {}",
+            sanitize_header_value(params.references.as_deref().unwrap_or(in_reply_to))
+        ));
+    }
+
+    for header in params.headers.iter().flatten() {
+        lines.push(format!("{}: {}", header.name, encode_mime_header(&header.value)));
     }
 
     lines.push("MIME-Version: 1.0".to_string());
 
     if has_attachments {
         // Multipart/mixed for attachments
-        let mixed_boundary = format!("----=_MixedPart_{}", generate_boundary());
+        let mixed_boundary = unique_boundary("----=_MixedPart_", &[&plain_body, &html_body]);
         lines.push(format!(
             "Content-Type: multipart/mixed; boundary=\"{}\"",
             mixed_boundary
@@ -438,7 +1333,7 @@ pub fn create_email_message(params: &EmailParams) -> Result<String> {
 
         if use_html {
             // Multipart alternative for text + HTML
-            let alt_boundary = format!("----=_AltPart_{}", generate_boundary());
+            let alt_boundary = unique_boundary("----=_AltPart_", &[&plain_body, &html_body]);
             lines.push(format!(
                 "Content-Type: multipart/alternative; boundary=\"{}\"",
                 alt_boundary
@@ -450,7 +1345,7 @@ pub fn create_email_message(params: &EmailParams) -> Result<String> {
             lines.push("Content-Type: text/plain; charset=UTF-8".to_string());
             lines.push("Content-Transfer-Encoding: 7bit".to_string());
             lines.push(String::new());
-            lines.push(params.body.clone());
+            lines.push(plain_body.clone());
             lines.push(String::new());
 
             // HTML
@@ -458,7 +1353,7 @@ pub fn create_email_message(params: &EmailParams) -> Result<String> {
             lines.push("Content-Type: text/html; charset=UTF-8".to_string());
             lines.push("Content-Transfer-Encoding: 7bit".to_string());
             lines.push(String::new());
-            lines.push(params.html_body.clone().unwrap_or_else(|| params.body.clone()));
+            lines.push(html_body.clone());
             lines.push(String::new());
 
             lines.push(format!("--{}--", alt_boundary));
@@ -466,12 +1361,12 @@ pub fn create_email_message(params: &EmailParams) -> Result<String> {
             lines.push("Content-Type: text/html; charset=UTF-8".to_string());
             lines.push("Content-Transfer-Encoding: 7bit".to_string());
             lines.push(String::new());
-            lines.push(params.html_body.clone().unwrap_or_else(|| params.body.clone()));
+            lines.push(html_body.clone());
         } else {
             lines.push("Content-Type: text/plain; charset=UTF-8".to_string());
             lines.push("Content-Transfer-Encoding: 7bit".to_string());
             lines.push(String::new());
-            lines.push(params.body.clone());
+            lines.push(plain_body.clone());
         }
         lines.push(String::new());
 
@@ -504,7 +1399,7 @@ pub fn create_email_message(params: &EmailParams) -> Result<String> {
         lines.push(format!("--{}--", mixed_boundary));
     } else if use_html {
         // Multipart alternative (no attachments)
-        let boundary = format!("----=_NextPart_{}", generate_boundary());
+        let boundary = unique_boundary("----=_NextPart_", &[&plain_body, &html_body]);
         lines.push(format!(
             "Content-Type: multipart/alternative; boundary=\"{}\"",
             boundary
@@ -516,7 +1411,7 @@ pub fn create_email_message(params: &EmailParams) -> Result<String> {
         lines.push("Content-Type: text/plain; charset=UTF-8".to_string());
         lines.push("Content-Transfer-Encoding: 7bit".to_string());
         lines.push(String::new());
-        lines.push(params.body.clone());
+        lines.push(plain_body.clone());
         lines.push(String::new());
 
         // HTML part
@@ -524,7 +1419,7 @@ pub fn create_email_message(params: &EmailParams) -> Result<String> {
         lines.push("Content-Type: text/html; charset=UTF-8".to_string());
         lines.push("Content-Transfer-Encoding: 7bit".to_string());
         lines.push(String::new());
-        lines.push(params.html_body.clone().unwrap_or_else(|| params.body.clone()));
+        lines.push(html_body.clone());
         lines.push(String::new());
 
         // Close boundary
@@ -534,26 +1429,120 @@ pub fn create_email_message(params: &EmailParams) -> Result<String> {
         lines.push("Content-Type: text/html; charset=UTF-8".to_string());
         lines.push("Content-Transfer-Encoding: 7bit".to_string());
         lines.push(String::new());
-        lines.push(params.html_body.clone().unwrap_or_else(|| params.body.clone()));
+        lines.push(html_body.clone());
     } else {
         // Plain text
         lines.push("Content-Type: text/plain; charset=UTF-8".to_string());
         lines.push("Content-Transfer-Encoding: 7bit".to_string());
         lines.push(String::new());
-        lines.push(params.body.clone());
+        lines.push(plain_body.clone());
     }
 
     Ok(lines.join("\r\n"))
 }
 
 /// Generate a random boundary string for multipart messages
+///
+/// The timestamp alone can repeat for parts built within one clock tick, so
+/// a random UUID keeps nested boundaries in the same message distinct.
 fn generate_boundary() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_nanos();
-    format!("{:x}", timestamp)
+    format!("{:x}_{}", timestamp, uuid::Uuid::new_v4().simple())
+}
+
+/// Generate a `prefix`ed boundary that occurs in none of the text `parts`
+///
+/// A body that happens to contain the delimiter would end its part early, so
+/// a colliding boundary is regenerated. Base64 attachment data can never
+/// contain the `-` and `_` boundary characters and needs no check.
+fn unique_boundary(prefix: &str, parts: &[&str]) -> String {
+    loop {
+        let boundary = format!("{}{}", prefix, generate_boundary());
+        if !parts.iter().any(|part| part.contains(&boundary)) {
+            return boundary;
+        }
+    }
+}
+
+/// Sanitize an attachment filename so it stays inside the target directory
+///
+/// Attachment names come from untrusted senders. Absolute paths are rejected;
+/// otherwise `.`/`..` components are dropped and the remaining components are
+/// joined with `_`, so `../../etc/evil` becomes `etc_evil`.
+pub fn sanitize_filename(filename: &str) -> Result<String> {
+    let invalid = |message: &str| {
+        crate::error::GmailMcpError::Validation(ValidationError::InvalidParameter {
+            name: "filename".to_string(),
+            message: format!("{}: {}", message, filename),
+        })
+    };
+
+    let bytes = filename.as_bytes();
+    let has_drive_prefix = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    if filename.starts_with('/') || filename.starts_with('\\') || has_drive_prefix {
+        return Err(invalid("absolute paths are not allowed"));
+    }
+
+    let sanitized = filename
+        .split(['/', '\\'])
+        .map(|c| c.chars().filter(|ch| !ch.is_control()).collect::<String>())
+        .filter(|c| !c.trim().is_empty() && c != "." && c != "..")
+        .collect::<Vec<_>>()
+        .join("_");
+
+    if sanitized.is_empty() {
+        return Err(invalid("filename has no usable characters"));
+    }
+
+    Ok(sanitized)
+}
+
+/// Pick a path in `dir` for `filename` that does not collide with an existing file
+///
+/// Collisions are resolved by suffixing the stem: `report.pdf`, `report (1).pdf`, ...
+pub fn unique_file_path(dir: &std::path::Path, filename: &str) -> std::path::PathBuf {
+    let candidate = dir.join(filename);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let path = std::path::Path::new(filename);
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| filename.to_string());
+    let extension = path.extension().map(|e| e.to_string_lossy().into_owned());
+
+    (1..)
+        .map(|n| match extension {
+            Some(ref ext) => dir.join(format!("{} ({}).{}", stem, n, ext)),
+            None => dir.join(format!("{} ({})", stem, n)),
+        })
+        .find(|p| !p.exists())
+        .expect("unbounded range always yields a free path")
+}
+
+/// Convert Gmail's `internalDate` (epoch milliseconds) to an RFC 3339 UTC timestamp
+pub fn internal_date_to_rfc3339(internal_date: &str) -> Option<String> {
+    let millis: i128 = internal_date.trim().parse().ok()?;
+    time::OffsetDateTime::from_unix_timestamp_nanos(millis * 1_000_000)
+        .ok()?
+        .format(&time::format_description::well_known::Rfc3339)
+        .ok()
+}
+
+/// Cut `text` to at most `max_chars` characters, ending with an ellipsis when cut
+///
+/// Returns the text and whether it was truncated.
+pub fn truncate_chars(text: &str, max_chars: usize) -> (String, bool) {
+    match text.char_indices().nth(max_chars) {
+        Some((cut, _)) => (format!("{}…", text[..cut].trim_end()), true),
+        None => (text.to_string(), false),
+    }
 }
 
 /// Format file size for display
@@ -577,6 +1566,28 @@ pub fn format_size(bytes: i64) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_attachment_matches() {
+        let attachment = |filename: &str, mime_type: &str| EmailAttachment {
+            id: "a1".to_string(),
+            filename: filename.to_string(),
+            mime_type: mime_type.to_string(),
+            size: 1,
+        };
+        let list = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let pdf = attachment("Report.PDF", "application/pdf");
+        let pixel = attachment("pixel.gif", "image/gif");
+
+        assert!(attachment_matches(&pdf, &[], &[]));
+        assert!(attachment_matches(&pdf, &list(&["Application/PDF"]), &[]));
+        assert!(!attachment_matches(&pixel, &list(&["application/pdf"]), &[]));
+        assert!(attachment_matches(&pixel, &list(&["image/*"]), &[]));
+        assert!(!attachment_matches(&pdf, &list(&["image/*"]), &[]));
+        assert!(attachment_matches(&pdf, &[], &list(&[".pdf"])));
+        assert!(attachment_matches(&pdf, &list(&["image/*"]), &list(&["pdf"])));
+        assert!(!attachment_matches(&attachment("README", "text/plain"), &[], &list(&["txt"])));
+    }
+
     #[test]
     fn test_validate_email_valid() {
         assert!(validate_email("test@example.com"));
@@ -607,6 +1618,19 @@ mod tests {
         assert!(encoded.ends_with("?="));
     }
 
+    #[test]
+    fn test_format_from() {
+        assert_eq!(
+            format_from("Jane \"JD\" Doe", "jane@example.com"),
+            "\"Jane \\\"JD\\\" Doe\" <jane@example.com>"
+        );
+        assert_eq!(format_from("  ", "jane@example.com"), "jane@example.com");
+
+        let encoded = format_from("Zoë", "zoe@example.com");
+        assert!(encoded.starts_with("=?UTF-8?B?"));
+        assert!(encoded.ends_with("?= <zoe@example.com>"));
+    }
+
     #[test]
     fn test_decode_base64url() {
         let encoded = "SGVsbG8gV29ybGQ"; // "Hello World" in base64url
@@ -614,6 +1638,405 @@ mod tests {
         assert_eq!(decoded, "Hello World");
     }
 
+    #[test]
+    fn test_encode_raw_bytes_round_trip() {
+        // Non-UTF-8 bytes (e.g. a Latin-1 .eml file) must survive encoding
+        let raw = b"Subject: caf\xe9\r\n\r\nbody";
+        let encoded = encode_raw_bytes(raw);
+        assert!(!encoded.contains('='));
+        assert_eq!(decode_base64url(&encoded).unwrap(), raw);
+    }
+
+    #[test]
+    fn test_get_message_headers_keeps_order_and_duplicates() {
+        let header = |name: &str, value: &str| Header {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+        let part = MessagePart {
+            headers: vec![
+                header("Received", "from a"),
+                header("Subject", "Hi"),
+                header("Received", "from b"),
+            ],
+            ..Default::default()
+        };
+
+        let names: Vec<_> = get_message_headers(&part).into_iter().map(|h| h.name).collect();
+        assert_eq!(names, vec!["Received", "Subject", "Received"]);
+        assert_eq!(find_headers(&part, "received"), vec!["from a", "from b"]);
+    }
+
+    #[test]
+    fn test_validate_label_changes() {
+        let ids = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert!(validate_label_changes(Some(&ids(&["STARRED"])), Some(&ids(&["UNREAD"]))).is_ok());
+        assert!(validate_label_changes(None, Some(&ids(&["INBOX"]))).is_ok());
+
+        let overlap = validate_label_changes(Some(&ids(&["STARRED", "INBOX"])), Some(&ids(&["INBOX"])));
+        assert!(matches!(
+            overlap,
+            Err(crate::error::GmailMcpError::Validation(ValidationError::InvalidParameter { ref message, .. }))
+                if message.contains("INBOX")
+        ));
+
+        assert!(validate_label_changes(None, None).is_err());
+        assert!(validate_label_changes(Some(&[]), Some(&[])).is_err());
+    }
+
+    #[test]
+    fn test_query_builder_quotes_values() {
+        let query = QueryBuilder::new()
+            .from("boss@example.com")
+            .subject("Q3 \"final\" report")
+            .label("My Projects")
+            .has_attachment()
+            .build();
+        assert_eq!(
+            query,
+            "from:boss@example.com subject:\"Q3  final  report\" label:\"My Projects\" has:attachment"
+        );
+    }
+
+    #[test]
+    fn test_query_builder_dates_and_text() {
+        let date = time::Date::from_calendar_date(2024, time::Month::March, 5).unwrap();
+        let query = QueryBuilder::new()
+            .after(date)
+            .before(date.next_day().unwrap())
+            .text("out of office")
+            .is_unread()
+            .build();
+        assert_eq!(query, "after:2024/03/05 before:2024/03/06 \"out of office\" is:unread");
+    }
+
+    #[test]
+    fn test_query_builder_any_of() {
+        let query = QueryBuilder::new()
+            .any_of([QueryBuilder::new().from("a@x.com"), QueryBuilder::new().from("b@x.com")])
+            .build();
+        assert_eq!(query, "(from:a@x.com OR from:b@x.com)");
+
+        let single = QueryBuilder::new().any_of([QueryBuilder::new().is_unread()]).build();
+        assert_eq!(single, "is:unread");
+    }
+
+    #[test]
+    fn test_quote_query_value() {
+        assert_eq!(quote_query_value("user+tag@example.com"), "user+tag@example.com");
+        assert_eq!(quote_query_value("a OR b"), "\"a OR b\"");
+        assert_eq!(quote_query_value("(x)"), "\"(x)\"");
+        assert_eq!(quote_query_value(""), "\"\"");
+    }
+
+    #[test]
+    fn test_parse_list_unsubscribe() {
+        let info = parse_list_unsubscribe(
+            "<mailto:unsub@example.com?subject=unsubscribe>, <https://example.com/u/abc>, <ftp://x>",
+            Some("List-Unsubscribe=One-Click"),
+        );
+        assert_eq!(info.mailto, vec!["mailto:unsub@example.com?subject=unsubscribe"]);
+        assert_eq!(info.http, vec!["https://example.com/u/abc"]);
+        assert_eq!(info.one_click_url(), Some("https://example.com/u/abc"));
+    }
+
+    #[test]
+    fn test_parse_list_unsubscribe_without_one_click() {
+        let info = parse_list_unsubscribe("<http://example.com/u>", None);
+        assert_eq!(info.http, vec!["http://example.com/u"]);
+        assert!(!info.one_click);
+        assert_eq!(info.one_click_url(), None);
+
+        // One-click requires an https target
+        let info = parse_list_unsubscribe("<http://example.com/u>", Some("List-Unsubscribe=One-Click"));
+        assert_eq!(info.one_click_url(), None);
+    }
+
+    #[test]
+    fn test_sanitize_filename_strips_traversal() {
+        assert_eq!(sanitize_filename("../../etc/evil").unwrap(), "etc_evil");
+        assert_eq!(sanitize_filename("..\\..\\boot.ini").unwrap(), "boot.ini");
+        assert_eq!(sanitize_filename("./report.pdf").unwrap(), "report.pdf");
+        assert!(sanitize_filename("..").is_err());
+        assert!(sanitize_filename("../").is_err());
+    }
+
+    #[test]
+    fn test_sanitize_filename_rejects_absolute_paths() {
+        assert!(sanitize_filename("/etc/passwd").is_err());
+        assert!(sanitize_filename("\\\\server\\share\\x").is_err());
+        assert!(sanitize_filename("C:\\Windows\\evil.dll").is_err());
+        assert!(sanitize_filename("c:evil.dll").is_err());
+    }
+
+    #[test]
+    fn test_sanitize_filename_embedded_slashes() {
+        assert_eq!(sanitize_filename("invoices/2024/march.pdf").unwrap(), "invoices_2024_march.pdf");
+        assert_eq!(sanitize_filename("a\\b.txt").unwrap(), "a_b.txt");
+        assert_eq!(sanitize_filename("plain name.txt").unwrap(), "plain name.txt");
+        assert_eq!(sanitize_filename("bad\0name.txt").unwrap(), "badname.txt");
+    }
+
+    #[test]
+    fn test_unique_file_path_suffixes_collisions() {
+        let dir = std::env::temp_dir().join(format!("gmail-mcp-unique-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let first = unique_file_path(&dir, "report.pdf");
+        assert_eq!(first, dir.join("report.pdf"));
+        std::fs::write(&first, b"1").unwrap();
+
+        let second = unique_file_path(&dir, "report.pdf");
+        assert_eq!(second, dir.join("report (1).pdf"));
+        std::fs::write(&second, b"2").unwrap();
+
+        assert_eq!(unique_file_path(&dir, "report.pdf"), dir.join("report (2).pdf"));
+
+        std::fs::write(dir.join("README"), b"x").unwrap();
+        assert_eq!(unique_file_path(&dir, "README"), dir.join("README (1)"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_internal_date_to_rfc3339() {
+        assert_eq!(
+            internal_date_to_rfc3339("1700000000123").as_deref(),
+            Some("2023-11-14T22:13:20.123Z")
+        );
+        assert_eq!(internal_date_to_rfc3339("0").as_deref(), Some("1970-01-01T00:00:00Z"));
+        assert_eq!(internal_date_to_rfc3339("not a number"), None);
+        assert_eq!(internal_date_to_rfc3339(""), None);
+    }
+
+    #[test]
+    fn test_parse_delivery_status() {
+        let body = "Reporting-MTA: dns; googlemail.com\r\n\
+                    Arrival-Date: Mon, 1 Jan 2024 10:00:00 -0800\r\n\
+                    \r\n\
+                    Final-Recipient: rfc822; nobody@example.com\r\n\
+                    Action: failed\r\n\
+                    Status: 5.1.1\r\n\
+                    Diagnostic-Code: smtp; 550-5.1.1 The email account that you tried to reach\r\n\
+                    \x20does not exist.\r\n";
+
+        let statuses = parse_delivery_status(body);
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].recipient, "nobody@example.com");
+        assert_eq!(statuses[0].action, "failed");
+        assert_eq!(statuses[0].status, "5.1.1");
+        assert_eq!(
+            statuses[0].diagnostic_code.as_deref(),
+            Some("smtp; 550-5.1.1 The email account that you tried to reach does not exist.")
+        );
+    }
+
+    #[test]
+    fn test_extract_delivery_status_from_report() {
+        let status_body = "Reporting-MTA: dns; mx.example.com\n\n\
+                           Final-Recipient: rfc822; a@example.com\nAction: failed\nStatus: 5.2.2\n\n\
+                           Final-Recipient: rfc822; b@example.com\nAction: delayed\nStatus: 4.4.1\n";
+        let report = MessagePart {
+            mime_type: Some("multipart/report".to_string()),
+            parts: vec![
+                MessagePart {
+                    mime_type: Some("text/plain".to_string()),
+                    body: Some(crate::gmail::types::MessagePartBody {
+                        data: Some(encode_raw_message("Delivery failed")),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                MessagePart {
+                    mime_type: Some("message/delivery-status".to_string()),
+                    body: Some(crate::gmail::types::MessagePartBody {
+                        data: Some(encode_raw_message(status_body)),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let statuses = extract_delivery_status(&report);
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[0].recipient, "a@example.com");
+        assert_eq!(statuses[1].action, "delayed");
+        assert_eq!(statuses[1].status, "4.4.1");
+    }
+
+    fn text_part(mime_type: &str, data: &str) -> MessagePart {
+        MessagePart {
+            mime_type: Some(mime_type.to_string()),
+            body: Some(crate::gmail::types::MessagePartBody {
+                data: Some(encode_raw_message(data)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn multipart(mime_type: &str, parts: Vec<MessagePart>) -> MessagePart {
+        MessagePart {
+            mime_type: Some(mime_type.to_string()),
+            parts,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_extract_alternative_inside_mixed() {
+        let mut attachment = text_part("text/plain", "attached notes");
+        attachment.filename = Some("notes.txt".to_string());
+
+        let message = multipart(
+            "multipart/mixed",
+            vec![
+                multipart(
+                    "multipart/alternative",
+                    vec![
+                        text_part("text/plain", "Hello"),
+                        text_part("text/html", "<p>Hello</p>"),
+                    ],
+                ),
+                attachment,
+            ],
+        );
+
+        let content = extract_email_content(&message);
+        assert_eq!(content.text, "Hello");
+        assert_eq!(content.html, "<p>Hello</p>");
+    }
+
+    #[test]
+    fn test_extract_alternative_picks_one_representation() {
+        let message = multipart(
+            "multipart/alternative",
+            vec![
+                text_part("text/plain", "Hello"),
+                multipart(
+                    "multipart/related",
+                    vec![text_part("text/html", "<p>Hello</p>"), text_part("text/html", "<p>Extra</p>")],
+                ),
+                text_part("text/plain", "Hello again"),
+            ],
+        );
+
+        let content = extract_email_content(&message);
+        assert_eq!(content.text, "Hello again");
+        assert_eq!(content.html, "<p>Hello</p>");
+    }
+
+    #[test]
+    fn test_extract_signed_uses_signed_content_only() {
+        let message = multipart(
+            "multipart/signed",
+            vec![
+                multipart("multipart/alternative", vec![text_part("text/plain", "Signed body")]),
+                text_part("text/plain", "-----BEGIN PGP SIGNATURE-----"),
+            ],
+        );
+
+        assert_eq!(extract_email_content(&message).text, "Signed body");
+    }
+
+    #[test]
+    fn test_build_references_appends_to_chain() {
+        assert_eq!(
+            build_references("<c@x>", Some("<a@x> <b@x>"), Some("<b@x>")),
+            "<a@x> <b@x> <c@x>"
+        );
+        // Folded header values are normalized to single spaces
+        assert_eq!(build_references("<c@x>", Some("<a@x>\r\n <b@x>"), None), "<a@x> <b@x> <c@x>");
+    }
+
+    #[test]
+    fn test_build_references_without_existing_chain() {
+        assert_eq!(build_references("<a@x>", None, None), "<a@x>");
+        assert_eq!(build_references("<b@x>", None, Some("<a@x>")), "<a@x> <b@x>");
+        assert_eq!(build_references("<a@x>", Some("<a@x>"), None), "<a@x>");
+    }
+
+    #[test]
+    fn test_generate_message_id() {
+        let id = generate_message_id("me@example.org");
+        assert!(id.starts_with('<') && id.ends_with("@example.org>"));
+        assert_ne!(id, generate_message_id("me@example.org"));
+        assert!(generate_message_id("not-an-address").ends_with("@gmail.com>"));
+        assert!(generate_message_id("").ends_with("@gmail.com>"));
+    }
+
+    #[test]
+    fn test_sanitize_header_value() {
+        assert_eq!(sanitize_header_value("Hello\r\nBcc: x@y.com"), "Hello Bcc: x@y.com");
+        assert_eq!(sanitize_header_value("a\n\nb\r"), "a b");
+        assert_eq!(sanitize_header_value("plain"), "plain");
+    }
+
+    #[test]
+    fn test_validate_email_rejects_control_characters() {
+        assert!(!validate_email("a\r\nBcc:x@y.com"));
+        assert!(!validate_email("user@example.com\n"));
+    }
+
+    #[test]
+    fn test_validate_custom_header() {
+        let header = |name: &str, value: &str| Header {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+
+        assert!(validate_custom_header(&header("X-Priority", "1")).is_ok());
+        assert!(validate_custom_header(&header("from", "evil@example.com")).is_err());
+        assert!(validate_custom_header(&header("MIME-Version", "2.0")).is_err());
+        assert!(validate_custom_header(&header("X-Bad Name", "v")).is_err());
+        assert!(validate_custom_header(&header("X-Tag", "a\r\nBcc: victim@example.com")).is_err());
+    }
+
+    #[test]
+    fn test_create_email_with_custom_headers() {
+        let params = EmailParams {
+            to: vec!["to@example.com".to_string()],
+            subject: "Test".to_string(),
+            body: "Body".to_string(),
+            headers: Some(vec![Header {
+                name: "X-Mailer".to_string(),
+                value: "gmail-mcp".to_string(),
+            }]),
+            ..Default::default()
+        };
+
+        let message = create_email_message(&params).unwrap();
+        assert!(message.contains("X-Mailer: gmail-mcp\r\n"));
+    }
+
+    #[test]
+    fn test_attachments_to_drop() {
+        assert_eq!(encoded_attachment_size(0), 0);
+        assert_eq!(encoded_attachment_size(57), 78);
+
+        let attachments = vec![
+            ("small.txt".to_string(), 1_000),
+            ("video.mp4".to_string(), 20_000),
+            ("deck.pdf".to_string(), 8_000),
+        ];
+        assert_eq!(attachments_to_drop(30_000, 25_000, &attachments), (vec!["video.mp4"], 10_000));
+        assert_eq!(
+            attachments_to_drop(40_000, 5_000, &attachments),
+            (vec!["video.mp4", "deck.pdf", "small.txt"], 11_000)
+        );
+        assert_eq!(attachments_to_drop(20_000, 25_000, &attachments), (vec![], 20_000));
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("short", 10), ("short".to_string(), false));
+        assert_eq!(truncate_chars("héllo wörld", 6), ("héllo…".to_string(), true));
+        assert_eq!(truncate_chars("abc", 3), ("abc".to_string(), false));
+    }
+
     #[test]
     fn test_format_size() {
         assert_eq!(format_size(500), "500 bytes");
@@ -628,18 +2051,232 @@ mod tests {
             to: vec!["test@example.com".to_string()],
             subject: "Test Subject".to_string(),
             body: "Test body".to_string(),
-            html_body: None,
-            mime_type: None,
-            cc: None,
-            bcc: None,
-            thread_id: None,
-            in_reply_to: None,
-            attachments: None,
+            ..Default::default()
         };
         let message = create_email_message(&params).unwrap();
         assert!(message.contains("To: test@example.com"));
         assert!(message.contains("Subject: Test Subject"));
         assert!(message.contains("Test body"));
     }
-}
 
+    #[test]
+    fn test_nested_boundaries_differ() {
+        let params = EmailParams {
+            to: vec!["test@example.com".to_string()],
+            subject: "Report".to_string(),
+            body: "Plain".to_string(),
+            html_body: Some("<p>Rich</p>".to_string()),
+            mime_type: Some(MimeType::MultipartAlternative),
+            attachments: Some(vec![AttachmentData {
+                filename: "notes.txt".to_string(),
+                mime_type: "text/plain".to_string(),
+                data: b"notes".to_vec(),
+            }]),
+            ..Default::default()
+        };
+        let message = create_email_message(&params).unwrap();
+
+        let boundaries: Vec<&str> = message
+            .split("boundary=\"")
+            .skip(1)
+            .filter_map(|rest| rest.split('"').next())
+            .collect();
+        assert_eq!(boundaries.len(), 2, "{}", message);
+        assert_ne!(boundaries[0], boundaries[1]);
+        assert_ne!(generate_boundary(), generate_boundary());
+    }
+
+    #[test]
+    fn test_unique_boundary_avoids_content() {
+        let boundary = unique_boundary("----=_NextPart_", &["plain", "<p>html</p>"]);
+        assert!(boundary.starts_with("----=_NextPart_"));
+
+        // A body quoting an earlier delimiter gets a fresh boundary
+        let quoted = format!("see --{}-- above", boundary);
+        let next = unique_boundary("----=_NextPart_", &[&quoted]);
+        assert!(!quoted.contains(&next));
+    }
+
+    #[test]
+    fn test_reply_draft_carries_threading_headers() {
+        let params = EmailParams {
+            to: vec!["alice@example.com".to_string()],
+            subject: "Re: Plans".to_string(),
+            body: "Sounds good".to_string(),
+            thread_id: Some("t1".to_string()),
+            in_reply_to: Some("<second@example.com>".to_string()),
+            references: Some("<first@example.com> <second@example.com>".to_string()),
+            ..Default::default()
+        };
+        let message = create_email_message(&params).unwrap();
+        assert!(message.contains("In-Reply-To: <second@example.com>\r\n"));
+        assert!(message.contains("References: <first@example.com> <second@example.com>\r\n"));
+
+        // Without a known chain, References falls back to the parent's ID
+        let params = EmailParams { references: None, ..params };
+        let message = create_email_message(&params).unwrap();
+        assert!(message.contains("References: <second@example.com>\r\n"));
+    }
+
+    #[test]
+    fn test_bcc_header_kept_by_default_and_omittable() {
+        let mut params = EmailParams {
+            to: vec!["to@example.com".to_string()],
+            subject: "Test".to_string(),
+            body: "Body".to_string(),
+            bcc: Some(vec!["hidden@example.com".to_string()]),
+            ..Default::default()
+        };
+
+        // Intentional: Gmail needs the header to deliver to Bcc recipients and strips it on send
+        let message = create_email_message(&params).unwrap();
+        assert!(message.contains("Bcc: hidden@example.com"));
+
+        params.omit_bcc_header = true;
+        let message = create_email_message(&params).unwrap();
+        assert!(!message.contains("Bcc:"));
+        assert!(!message.contains("hidden@example.com"));
+    }
+
+    #[test]
+    fn test_inline_attachment() {
+        let attachment = inline_attachment("notes.txt", None, "aGVsbG8g\nd29ybGQ=").unwrap();
+        assert_eq!(attachment.data, b"hello world");
+        assert_eq!(attachment.mime_type, "text/plain");
+
+        let attachment = inline_attachment("blob", Some("application/x-custom"), "AAEC").unwrap();
+        assert_eq!(attachment.data, vec![0, 1, 2]);
+        assert_eq!(attachment.mime_type, "application/x-custom");
+
+        assert!(inline_attachment(" ", None, "AAEC").is_err());
+        assert!(inline_attachment("a.bin", None, "not base64!").is_err());
+    }
+
+    #[test]
+    fn test_attachment_source() {
+        assert_eq!(attachment_source("/tmp/report.pdf"), AttachmentSource::File);
+        assert_eq!(attachment_source("C:\\Users\\me\\report.pdf"), AttachmentSource::File);
+        assert_eq!(attachment_source("https://example.com/report.pdf"), AttachmentSource::Url);
+        assert_eq!(attachment_source("HTTP://example.com/a"), AttachmentSource::Url);
+        assert_eq!(attachment_source("file:///etc/passwd"), AttachmentSource::UnsupportedUrl);
+        assert_eq!(attachment_source("ftp://example.com/a"), AttachmentSource::UnsupportedUrl);
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        let public = |ip: &str| is_public_ip(ip.parse().unwrap());
+        assert!(public("93.184.216.34"));
+        assert!(public("2606:2800:220:1::1"));
+        for ip in [
+            "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0",
+            "::1", "fe80::1", "fd00::1", "::ffff:127.0.0.1",
+        ] {
+            assert!(!public(ip), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn test_attachment_filename_from_url() {
+        let url = reqwest::Url::parse("https://example.com/files/Q3%20report.pdf?download=1").unwrap();
+        assert_eq!(attachment_filename_from_url(&url, None), "Q3 report.pdf");
+        assert_eq!(
+            attachment_filename_from_url(&url, Some("attachment; filename=\"summary.pdf\"")),
+            "summary.pdf"
+        );
+
+        assert_eq!(
+            attachment_filename_from_url(
+                &url,
+                Some("attachment; filename=\"fallback.pdf\"; filename*=UTF-8''R%C3%A9sum%C3%A9.pdf")
+            ),
+            "Résumé.pdf"
+        );
+        assert_eq!(
+            attachment_filename_from_url(&url, Some("attachment; filename*=utf-8'en'notes%20v2.txt")),
+            "notes v2.txt"
+        );
+
+        let root = reqwest::Url::parse("https://example.com/").unwrap();
+        assert_eq!(attachment_filename_from_url(&root, Some("inline")), "attachment");
+        assert_eq!(mime_type_for_extension(Some("PDF")), "application/pdf");
+    }
+
+    const NEWSLETTER_HTML: &str = r#"<!DOCTYPE html>
+<html><head><title>Weekly update</title><style>p { color: red; }</style></head>
+<body>
+  <!-- preheader > hidden -->
+  <h1>Hello&nbsp;Team</h1>
+  <P>Sales are up 10% &amp; costs are &lt;5%.<BR>See the <a href="https://example.com/report?a=1&amp;b=2">full report</a>.</P>
+  <ul><li>Ship v2</li><li>Hire &#8220;two&#8221; engineers</li></ul>
+  <p>Contact <a href="mailto:ops@example.com">ops@example.com</a></p>
+  <script>alert("x")</script>
+</body></html>"#;
+
+    #[test]
+    fn test_html_to_text_representative_email() {
+        assert_eq!(
+            html_to_text(NEWSLETTER_HTML),
+            "Hello Team\n\
+             Sales are up 10% & costs are <5%.\n\
+             See the full report (https://example.com/report?a=1&b=2).\n\
+             • Ship v2\n\
+             • Hire \u{201c}two\u{201d} engineers\n\
+             Contact ops@example.com"
+        );
+    }
+
+    #[test]
+    fn test_decode_html_entities() {
+        assert_eq!(decode_html_entities("AT&T &#39;x&#x27; &copy &unknown;"), "AT&T 'x' © &unknown;");
+    }
+
+    #[test]
+    fn test_html_only_email_gets_plain_alternative() {
+        let params = EmailParams {
+            to: vec!["test@example.com".to_string()],
+            subject: "Update".to_string(),
+            body: String::new(),
+            html_body: Some("<p>Hi <b>there</b></p><p>Bye</p>".to_string()),
+            mime_type: Some(MimeType::MultipartAlternative),
+            ..Default::default()
+        };
+
+        let message = create_email_message(&params).unwrap();
+        let plain = message
+            .split("Content-Type: text/plain; charset=UTF-8\r\nContent-Transfer-Encoding: 7bit\r\n\r\n")
+            .nth(1)
+            .unwrap();
+        assert!(plain.starts_with("Hi there\nBye\r\n"), "{}", plain);
+    }
+
+    #[test]
+    fn test_signature_appended_to_both_parts() {
+        let signature = Signature::new(Some("Jane Doe\nAcme & Co".to_string()), None).unwrap();
+        assert_eq!(signature.html, "Jane Doe<br>Acme &amp; Co");
+        assert!(Signature::new(Some("  ".to_string()), None).is_none());
+
+        assert_eq!(signature.append_to_text("Hi there\n"), "Hi there\n\n-- \nJane Doe\nAcme & Co");
+        let html = signature.append_to_html("<html><body><p>Hi</p></BODY></html>");
+        assert!(html.starts_with("<html><body><p>Hi</p><br><br>-- <br>"));
+        assert!(html.ends_with("Acme &amp; Co</div></BODY></html>"));
+    }
+
+    #[test]
+    fn test_extract_addresses() {
+        assert_eq!(
+            extract_addresses(r#""Doe, Jane" <jane@example.com>, bob@example.com"#),
+            vec!["jane@example.com", "bob@example.com"]
+        );
+        assert_eq!(extract_addresses("Alice <alice@example.com>"), vec!["alice@example.com"]);
+        assert!(extract_addresses(" ").is_empty());
+    }
+
+    #[test]
+    fn test_reply_subject() {
+        assert_eq!(reply_subject("Lunch?"), "Re: Lunch?");
+        assert_eq!(reply_subject("RE: Lunch?"), "RE: Lunch?");
+        assert_eq!(reply_subject("Réunion"), "Re: Réunion");
+        assert_eq!(reply_subject("ééé"), "Re: ééé");
+        assert_eq!(reply_subject("🎉 Launch"), "Re: 🎉 Launch");
+    }
+}
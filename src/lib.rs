@@ -7,6 +7,7 @@ pub mod config;
 pub mod error;
 pub mod gmail;
 pub mod mcp;
+pub mod redact;
 
 pub use config::Config;
 pub use error::{GmailMcpError, Result};
@@ -9,17 +9,36 @@ use clap::{Parser, Subcommand};
 
 use gmail_mcp_server_rust::config::Config;
 use gmail_mcp_server_rust::error::Result;
+use gmail_mcp_server_rust::gmail::aliases::RecipientAliases;
+use gmail_mcp_server_rust::gmail::api::GmailApi;
 use gmail_mcp_server_rust::gmail::auth::Authenticator;
 use gmail_mcp_server_rust::gmail::client::GmailClient;
-use gmail_mcp_server_rust::mcp::server::McpServer;
+use gmail_mcp_server_rust::gmail::last_seen::LastSeen;
+use gmail_mcp_server_rust::gmail::mock::MockGmailClient;
+use gmail_mcp_server_rust::gmail::saved_queries::SavedQueries;
+use gmail_mcp_server_rust::mcp::server::{McpServer, StdioExit};
+
+/// Version reported by `--version`: crate version, git commit and build time
+const LONG_VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (commit ",
+    env!("GMAIL_MCP_GIT_SHA"),
+    ", built ",
+    env!("GMAIL_MCP_BUILD_DATE"),
+    ")"
+);
 
 /// Gmail MCP Server
 #[derive(Parser)]
 #[command(name = "gmail-mcp-server")]
-#[command(author, version, about = "Gmail MCP Server - A Model Context Protocol server for Gmail")]
+#[command(author, version = LONG_VERSION, about = "Gmail MCP Server - A Model Context Protocol server for Gmail")]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Serve an in-memory sample mailbox instead of Gmail; no credentials or network needed
+    #[arg(long)]
+    test_mode: bool,
 }
 
 #[derive(Subcommand)]
@@ -58,16 +77,18 @@ async fn main() -> Result<()> {
         }
         None => {
             // Run MCP server
-            run_server(config).await?;
+            run_server(config, cli.test_mode).await?;
         }
     }
 
     Ok(())
 }
 
-async fn run_server(config: Config) -> Result<()> {
+async fn run_server(config: Config, test_mode: bool) -> Result<()> {
+    tracing::info!("gmail-mcp-server {}", LONG_VERSION);
+
     // Check for OAuth keys
-    if !config.oauth_keys_exist() {
+    if !test_mode && !config.oauth_keys_exist() {
         eprintln!("Error: OAuth keys file not found.");
         eprintln!(
             "Please place gcp-oauth.keys.json in current directory or {}",
@@ -76,21 +97,67 @@ async fn run_server(config: Config) -> Result<()> {
         std::process::exit(1);
     }
 
-    // Initialize authenticator
-    let authenticator = Authenticator::new(config).await?;
-
-    // Check if we have credentials
-    if !authenticator.is_authenticated().await {
-        eprintln!("Error: Not authenticated. Please run 'gmail-mcp-server auth' first.");
-        std::process::exit(1);
+    let max_message_bytes = config.max_message_bytes;
+    let max_result_chars = config.max_result_chars;
+    let redact_logs = config.redact_logs;
+    let rate_limit = config.rate_limit;
+    let attachment_retries = config.attachment_retries;
+    let default_max_results = config.default_max_results;
+    let user_id = config.user_id.clone();
+    let saved_queries = SavedQueries::load(&config.saved_queries_path)?;
+    let last_seen = LastSeen::load(&config.last_seen_path)?;
+    let signature = config.signature.clone();
+    let from_name = config.from_name.clone();
+    let confirm_threshold = config.confirm_threshold;
+    let tool_access = config.tool_access.clone();
+    if tool_access.is_read_only() {
+        tracing::info!("Read-only mode: tools that change the mailbox are disabled");
+    }
+    let aliases = RecipientAliases::load(&config.aliases_path)?;
+    if !aliases.is_empty() {
+        tracing::info!("Loaded {} recipient aliases from {}", aliases.len(), config.aliases_path.display());
     }
 
-    // Create Gmail client
-    let gmail_client = Arc::new(GmailClient::new(Arc::new(authenticator)));
+    let gmail_client: Arc<dyn GmailApi> = if test_mode {
+        tracing::warn!("Test mode: serving an in-memory sample mailbox, nothing reaches Gmail");
+        Arc::new(MockGmailClient::sample())
+    } else {
+        // Initialize authenticator
+        let authenticator = Authenticator::new(config).await?;
+
+        // Check if we have credentials
+        if !authenticator.is_authenticated().await {
+            eprintln!("Error: Not authenticated. Please run 'gmail-mcp-server auth' first.");
+            std::process::exit(1);
+        }
+
+        // Create Gmail client
+        Arc::new(
+            GmailClient::new(Arc::new(authenticator))
+                .with_rate_limit(rate_limit)
+                .with_attachment_retries(attachment_retries)
+                .with_user_id(user_id),
+        )
+    };
 
     // Create and run MCP server
-    let mut server = McpServer::new(gmail_client);
-    server.run_stdio().await?;
+    let server = McpServer::new(gmail_client)
+        .with_max_message_bytes(max_message_bytes)
+        .with_max_result_chars(max_result_chars)
+        .with_log_redaction(redact_logs)
+        .with_aliases(aliases)
+        .with_signature(signature)
+        .with_from_name(from_name)
+        .with_default_max_results(default_max_results)
+        .with_saved_queries(saved_queries)
+        .with_last_seen(last_seen)
+        .with_confirm_threshold(confirm_threshold)
+        .with_tool_access(tool_access);
+    if server.run_stdio().await? == StdioExit::Signal {
+        // The stdin reader thread can still be blocked in a read, which would
+        // keep the runtime from exiting
+        std::process::exit(0);
+    }
 
     Ok(())
 }
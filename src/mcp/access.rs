@@ -0,0 +1,173 @@
+//! Operator control over which tools are exposed
+//!
+//! `GMAIL_ENABLED_TOOLS` names the only tools offered and
+//! `GMAIL_DISABLED_TOOLS` hides tools, e.g. everything destructive.
+//! `GMAIL_READONLY` hides every tool that changes the mailbox or its
+//! settings. Hidden tools are left out of `tools/list` and calls to them are
+//! rejected, whatever scopes the stored token was granted.
+
+use crate::config::flag_enabled;
+
+/// Tools that send mail or change messages, labels or filters
+pub const WRITE_TOOLS: &[&str] = &[
+    "send_email",
+    "draft_email",
+    "reply_email",
+    "send_email_batch",
+    "modify_email",
+    "delete_email",
+    "set_importance",
+    "move_email",
+    "batch_modify_emails",
+    "batch_delete_emails",
+    "empty_trash",
+    "empty_spam",
+    "create_label",
+    "update_label",
+    "delete_label",
+    "get_or_create_label",
+    "import_labels",
+    "create_filter",
+    "delete_filter",
+    "import_filters",
+    "create_filter_from_template",
+    "apply_filter_to_existing",
+    "import_email",
+    "insert_email",
+    "unsubscribe",
+];
+
+/// Which tools the server exposes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolAccess {
+    /// When set, only these tools are exposed
+    enabled: Option<Vec<String>>,
+
+    /// Tools never exposed, even if listed in `enabled`
+    disabled: Vec<String>,
+
+    /// Hide every tool in [`WRITE_TOOLS`]
+    read_only: bool,
+}
+
+impl ToolAccess {
+    /// Expose `enabled` (or every tool when `None`) minus `disabled`
+    pub fn new(enabled: Option<Vec<String>>, disabled: Vec<String>) -> Self {
+        Self {
+            enabled,
+            disabled,
+            read_only: false,
+        }
+    }
+
+    /// Also hide every tool in [`WRITE_TOOLS`] when `read_only`
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Whether write tools are hidden
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Read `GMAIL_ENABLED_TOOLS`, `GMAIL_DISABLED_TOOLS` and `GMAIL_READONLY`
+    ///
+    /// An unset or blank `GMAIL_ENABLED_TOOLS` enables every tool.
+    pub fn from_env() -> Self {
+        let list = |var: &str| std::env::var(var).map(|v| parse_tool_list(&v)).unwrap_or_default();
+        let enabled = list("GMAIL_ENABLED_TOOLS");
+        Self::new((!enabled.is_empty()).then_some(enabled), list("GMAIL_DISABLED_TOOLS"))
+            .with_read_only(flag_enabled(std::env::var("GMAIL_READONLY").ok().as_deref()))
+    }
+
+    /// Why `tool` may not be listed or called, or `None` if it may
+    pub fn denial(&self, tool: &str) -> Option<String> {
+        if self.read_only && WRITE_TOOLS.contains(&tool) {
+            return Some(format!(
+                "Tool '{}' modifies the mailbox and this server is read-only (GMAIL_READONLY)",
+                tool
+            ));
+        }
+        if self.disabled.iter().any(|t| t == tool) {
+            return Some(format!(
+                "Tool '{}' is disabled on this server (GMAIL_DISABLED_TOOLS)",
+                tool
+            ));
+        }
+        match self.enabled {
+            Some(ref enabled) if !enabled.iter().any(|t| t == tool) => Some(format!(
+                "Tool '{}' is not enabled on this server (GMAIL_ENABLED_TOOLS)",
+                tool
+            )),
+            _ => None,
+        }
+    }
+
+    /// Whether `tool` is exposed
+    pub fn allows(&self, tool: &str) -> bool {
+        self.denial(tool).is_none()
+    }
+
+    /// Configured names that aren't in `known`, most likely typos
+    pub fn unknown_names<'a>(&'a self, known: &[&str]) -> Vec<&'a str> {
+        self.enabled
+            .iter()
+            .flatten()
+            .chain(&self.disabled)
+            .map(String::as_str)
+            .filter(|name| !known.contains(name))
+            .collect()
+    }
+}
+
+/// Split a comma- or whitespace-separated list of tool names
+pub fn parse_tool_list(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tool_list() {
+        assert_eq!(parse_tool_list(" read_email, search_emails\nlist_filters ,"), vec![
+            "read_email",
+            "search_emails",
+            "list_filters"
+        ]);
+        assert!(parse_tool_list(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_enabled_and_disabled() {
+        let everything = ToolAccess::default();
+        assert!(everything.allows("delete_email"));
+
+        let access = ToolAccess::new(
+            Some(vec!["read_email".to_string(), "delete_email".to_string()]),
+            vec!["delete_email".to_string()],
+        );
+        assert!(access.allows("read_email"));
+        assert!(access.denial("delete_email").unwrap().contains("GMAIL_DISABLED_TOOLS"));
+        assert!(access.denial("send_email").unwrap().contains("GMAIL_ENABLED_TOOLS"));
+
+        assert!(access.unknown_names(&["read_email", "delete_email"]).is_empty());
+        let typo = ToolAccess::new(None, vec!["delet_email".to_string()]);
+        assert_eq!(typo.unknown_names(&["delete_email"]), vec!["delet_email"]);
+    }
+
+    #[test]
+    fn test_read_only() {
+        let access = ToolAccess::default().with_read_only(true);
+        assert!(access.denial("batch_delete_emails").unwrap().contains("GMAIL_READONLY"));
+        assert!(access.denial("create_filter").is_some());
+        assert!(access.allows("search_emails"));
+        assert!(access.allows("download_all_attachments"));
+    }
+}
@@ -1,7 +1,9 @@
 //! MCP (Model Context Protocol) module
 //!
-//! Implements the MCP server protocol for tool invocation.
+//! Implements the MCP server protocol for tool invocation and prompt templates.
 
+pub mod access;
+pub mod prompts;
 pub mod server;
 pub mod tools;
 pub mod types;
@@ -0,0 +1,183 @@
+//! MCP prompt templates
+//!
+//! Defines reusable Gmail prompts exposed through `prompts/list` and `prompts/get`.
+
+use std::collections::HashMap;
+
+use crate::error::{GmailMcpError, McpError, Result};
+use crate::mcp::types::{GetPromptResult, Prompt, PromptArgument, PromptMessage, ToolResultContent};
+
+/// Default number of unread messages to triage
+const DEFAULT_TRIAGE_COUNT: &str = "20";
+
+/// List all available prompts
+pub fn list_prompts() -> Vec<Prompt> {
+    vec![
+        prompt_def(
+            "summarize_thread",
+            "Summarize an email conversation",
+            vec![arg("messageId", "ID of any message in the conversation", true)],
+        ),
+        prompt_def(
+            "draft_polite_decline",
+            "Draft a polite reply declining a request",
+            vec![
+                arg("messageId", "ID of the email to decline", true),
+                arg("reason", "Optional reason to mention in the reply", false),
+            ],
+        ),
+        prompt_def(
+            "triage_unread_inbox",
+            "Triage unread inbox messages and suggest actions",
+            vec![arg("maxResults", "Maximum number of unread messages to review (default: 20)", false)],
+        ),
+    ]
+}
+
+/// Render a prompt by name with the given arguments
+pub fn get_prompt(name: &str, arguments: &HashMap<String, String>) -> Result<GetPromptResult> {
+    let (description, text) = match name {
+        "summarize_thread" => {
+            let message_id = required(arguments, "messageId")?;
+            (
+                "Summarize an email conversation",
+                format!(
+                    "Use the read_email tool to read the email with ID {message_id}. \
+                     If it is part of a longer conversation, use search_emails with its subject \
+                     to find the related messages and read those too.\n\n\
+                     Then summarize the conversation: who is involved, what was discussed, \
+                     any decisions made, and any open questions or action items."
+                ),
+            )
+        }
+        "draft_polite_decline" => {
+            let message_id = required(arguments, "messageId")?;
+            let reason = arguments
+                .get("reason")
+                .filter(|r| !r.trim().is_empty())
+                .map(|r| format!(" Mention this reason: {}.", r))
+                .unwrap_or_default();
+            (
+                "Draft a polite reply declining a request",
+                format!(
+                    "Use the read_email tool to read the email with ID {message_id}. \
+                     Write a short, polite reply that declines the request while keeping \
+                     a friendly tone.{reason}\n\n\
+                     Save the reply with the draft_email tool, addressed to the original sender, \
+                     using the original thread ID as threadId and a subject prefixed with \"Re:\". \
+                     Do not send it."
+                ),
+            )
+        }
+        "triage_unread_inbox" => {
+            let max_results = arguments
+                .get("maxResults")
+                .map(String::as_str)
+                .unwrap_or(DEFAULT_TRIAGE_COUNT);
+            (
+                "Triage unread inbox messages and suggest actions",
+                format!(
+                    "Use the search_emails tool with the query \"is:unread in:inbox\" and \
+                     maxResults {max_results}. Read any messages whose subject and sender are \
+                     not enough to judge them.\n\n\
+                     Group the messages into: needs a reply, needs action, FYI, and can be archived. \
+                     For each message give a one-line summary and a suggested next step. \
+                     Do not modify or delete anything without asking first."
+                ),
+            )
+        }
+        _ => {
+            return Err(GmailMcpError::Mcp(McpError::UnknownPrompt {
+                name: name.to_string(),
+            }))
+        }
+    };
+
+    Ok(GetPromptResult {
+        description: Some(description.to_string()),
+        messages: vec![PromptMessage {
+            role: "user".to_string(),
+            content: ToolResultContent::Text { text },
+        }],
+    })
+}
+
+fn prompt_def(name: &str, description: &str, arguments: Vec<PromptArgument>) -> Prompt {
+    Prompt {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        arguments,
+    }
+}
+
+fn arg(name: &str, description: &str, required: bool) -> PromptArgument {
+    PromptArgument {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        required,
+    }
+}
+
+fn required<'a>(arguments: &'a HashMap<String, String>, name: &str) -> Result<&'a str> {
+    arguments
+        .get(name)
+        .map(String::as_str)
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| {
+            GmailMcpError::Mcp(McpError::InvalidArguments {
+                message: format!("{} is required", name),
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt_text(result: &GetPromptResult) -> &str {
+        match &result.messages[0].content {
+            ToolResultContent::Text { text } => text,
+            _ => panic!("Expected text content"),
+        }
+    }
+
+    #[test]
+    fn test_every_listed_prompt_renders() {
+        let mut args = HashMap::new();
+        args.insert("messageId".to_string(), "abc123".to_string());
+
+        for prompt in list_prompts() {
+            assert!(get_prompt(&prompt.name, &args).is_ok(), "{} failed", prompt.name);
+        }
+    }
+
+    #[test]
+    fn test_prompt_fills_arguments() {
+        let mut args = HashMap::new();
+        args.insert("messageId".to_string(), "abc123".to_string());
+        args.insert("reason".to_string(), "I'm travelling that week".to_string());
+
+        let result = get_prompt("draft_polite_decline", &args).unwrap();
+        let text = prompt_text(&result);
+        assert!(text.contains("abc123"));
+        assert!(text.contains("I'm travelling that week"));
+    }
+
+    #[test]
+    fn test_prompt_missing_required_argument() {
+        let result = get_prompt("summarize_thread", &HashMap::new());
+        assert!(matches!(
+            result,
+            Err(GmailMcpError::Mcp(McpError::InvalidArguments { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_unknown_prompt() {
+        let result = get_prompt("nope", &HashMap::new());
+        assert!(matches!(
+            result,
+            Err(GmailMcpError::Mcp(McpError::UnknownPrompt { .. }))
+        ));
+    }
+}
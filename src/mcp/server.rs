@@ -2,82 +2,331 @@
 //!
 //! Implements the Model Context Protocol server for stdio transport.
 
+use std::collections::HashMap;
 use std::io::{BufRead, Write};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use serde::Serialize;
 use serde_json::Value;
+use tokio_util::sync::CancellationToken;
 
+use crate::config::DEFAULT_MAX_MESSAGE_BYTES;
 use crate::error::Result;
-use crate::gmail::client::GmailClient;
-use crate::mcp::tools::ToolHandler;
+use crate::gmail::aliases::RecipientAliases;
+use crate::gmail::api::GmailApi;
+use crate::gmail::last_seen::LastSeen;
+use crate::gmail::saved_queries::SavedQueries;
+use crate::mcp::access::ToolAccess;
+use crate::gmail::utils::Signature;
+use crate::mcp::prompts;
+use crate::mcp::tools::{ProgressReporter, ToolContext, ToolHandler};
 use crate::mcp::types::*;
+use crate::redact;
 
 /// MCP Server info
-const SERVER_NAME: &str = "gmail";
-const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub(crate) const SERVER_NAME: &str = "gmail";
+pub(crate) const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// URI prefix for messages exposed as resources
+const MESSAGE_URI_PREFIX: &str = "gmail://message/";
+
+/// Number of recent inbox messages listed as resources
+const RECENT_MESSAGE_RESOURCES: u32 = 20;
+
+/// How long in-flight requests may run after a shutdown signal
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long cancelled requests get to write their final response
+const CANCEL_GRACE: Duration = Duration::from_secs(2);
 
 /// MCP Server for Gmail
 pub struct McpServer {
-    /// Gmail client (kept for potential future use)
-    #[allow(dead_code)]
-    gmail_client: Arc<GmailClient>,
+    /// Gmail client (used directly for resources)
+    gmail_client: Arc<dyn GmailApi>,
 
     /// Tool handler
     tool_handler: ToolHandler,
 
     /// Whether initialized
-    initialized: bool,
+    initialized: AtomicBool,
+
+    /// Cancellation tokens for in-flight tool calls, keyed by request ID
+    in_flight: Mutex<HashMap<RequestId, CancellationToken>>,
+
+    /// Lines longer than this are rejected without being buffered
+    max_message_bytes: usize,
+
+    /// Whether error text is redacted before it is logged
+    redact_logs: bool,
 }
 
 impl McpServer {
     /// Create a new MCP server
-    pub fn new(gmail_client: Arc<GmailClient>) -> Self {
+    pub fn new(gmail_client: Arc<dyn GmailApi>) -> Self {
         let tool_handler = ToolHandler::new(gmail_client.clone());
 
         Self {
             gmail_client,
             tool_handler,
-            initialized: false,
+            initialized: AtomicBool::new(false),
+            in_flight: Mutex::new(HashMap::new()),
+            max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+            redact_logs: true,
         }
     }
 
+    /// Set the largest message accepted on stdin
+    pub fn with_max_message_bytes(mut self, max_message_bytes: usize) -> Self {
+        self.max_message_bytes = max_message_bytes;
+        self
+    }
+
+    /// Set the longest tool result text returned, in characters (0 for no limit)
+    pub fn with_max_result_chars(mut self, max_result_chars: usize) -> Self {
+        self.tool_handler = self.tool_handler.with_max_result_chars(max_result_chars);
+        self
+    }
+
+    /// Set whether addresses and content are redacted from logged errors
+    pub fn with_log_redaction(mut self, redact_logs: bool) -> Self {
+        self.redact_logs = redact_logs;
+        self.tool_handler = self.tool_handler.with_log_redaction(redact_logs);
+        self
+    }
+
+    /// Set the recipient groups that `send_email` and `draft_email` expand
+    pub fn with_aliases(mut self, aliases: RecipientAliases) -> Self {
+        self.tool_handler = self.tool_handler.with_aliases(aliases);
+        self
+    }
+
+    /// Set the signature appended to outgoing mail
+    pub fn with_signature(mut self, signature: Option<Signature>) -> Self {
+        self.tool_handler = self.tool_handler.with_signature(signature);
+        self
+    }
+
+    /// Set the display name shown with the sender address
+    pub fn with_from_name(mut self, from_name: Option<String>) -> Self {
+        self.tool_handler = self.tool_handler.with_from_name(from_name);
+        self
+    }
+
+    /// Set how many results `search_emails` returns when `maxResults` is omitted
+    pub fn with_default_max_results(mut self, default_max_results: u32) -> Self {
+        self.tool_handler = self.tool_handler.with_default_max_results(default_max_results);
+        self
+    }
+
+    /// Set the store behind `save_query` and `run_saved_query`
+    pub fn with_saved_queries(mut self, saved_queries: SavedQueries) -> Self {
+        self.tool_handler = self.tool_handler.with_saved_queries(saved_queries);
+        self
+    }
+
+    /// Set the marker `get_new_messages` reads from and advances
+    pub fn with_last_seen(mut self, last_seen: LastSeen) -> Self {
+        self.tool_handler = self.tool_handler.with_last_seen(last_seen);
+        self
+    }
+
+    /// Set how many messages a bulk delete or relabel may touch before it needs `confirm: true`
+    pub fn with_confirm_threshold(mut self, confirm_threshold: usize) -> Self {
+        self.tool_handler = self.tool_handler.with_confirm_threshold(confirm_threshold);
+        self
+    }
+
+    /// Set which tools are listed and may be called
+    pub fn with_tool_access(mut self, tool_access: ToolAccess) -> Self {
+        self.tool_handler = self.tool_handler.with_tool_access(tool_access);
+        self
+    }
+
     /// Run the server on stdio
-    pub async fn run_stdio(&mut self) -> Result<()> {
-        let stdin = std::io::stdin();
-        let mut stdout = std::io::stdout();
+    ///
+    /// Stdin is read on a blocking thread and every message is handled on its
+    /// own task, so a slow tool call doesn't hold up `ping` or other requests.
+    /// Responses can therefore go out in any order; JSON-RPC matches them by id.
+    /// All writes take the stdout lock, so lines never interleave.
+    ///
+    /// On SIGINT/SIGTERM no further requests are accepted. In-flight calls get
+    /// `SHUTDOWN_TIMEOUT` to finish; any still running are then cancelled, as
+    /// with `notifications/cancelled`, so batches stop between messages. The
+    /// stdin reader may still be blocked afterwards, so on `StdioExit::Signal`
+    /// the caller should exit the process rather than wait for the runtime to
+    /// wind down.
+    pub async fn run_stdio(self) -> Result<StdioExit> {
+        let server = Arc::new(self);
+        let max_message_bytes = server.max_message_bytes;
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(64);
 
-        let reader = stdin.lock();
+        let reader = tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+            let stdin = std::io::stdin();
+            let mut reader = stdin.lock();
+            let mut buf = Vec::new();
 
-        for line in reader.lines() {
-            let line = line?;
-            if line.trim().is_empty() {
-                continue;
-            }
+            loop {
+                // A malformed or oversized message is answered with an error and
+                // skipped; only a failure of stdin itself ends the session
+                match read_bounded_line(&mut reader, &mut buf, max_message_bytes)? {
+                    LineRead::Eof => return Ok(()),
+                    LineRead::TooLong => {
+                        tracing::warn!("Dropping message larger than {} bytes", max_message_bytes);
+                        write_message(&JsonRpcResponse::error(
+                            RequestId::Number(0),
+                            JsonRpcError::invalid_request(format!(
+                                "Message exceeds the {} byte limit",
+                                max_message_bytes
+                            )),
+                        ));
+                        continue;
+                    }
+                    LineRead::Line => {}
+                }
 
-            match self.handle_message(&line).await {
-                Ok(Some(response)) => {
-                    let response_str = serde_json::to_string(&response)?;
-                    writeln!(stdout, "{}", response_str)?;
-                    stdout.flush()?;
+                match String::from_utf8(std::mem::take(&mut buf)) {
+                    Ok(line) if line.trim().is_empty() => {}
+                    Ok(line) => {
+                        if tx.blocking_send(line).is_err() {
+                            return Ok(());
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Dropping message that is not valid UTF-8: {}", e);
+                        write_message(&JsonRpcResponse::error(
+                            RequestId::Number(0),
+                            JsonRpcError::parse_error(format!("Message is not valid UTF-8: {}", e)),
+                        ));
+                    }
                 }
-                Ok(None) => {
-                    // Notification, no response needed
+            }
+        });
+
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+
+        let mut tasks = tokio::task::JoinSet::new();
+        loop {
+            let line = tokio::select! {
+                line = rx.recv() => match line {
+                    Some(line) => line,
+                    None => break,
+                },
+                _ = &mut shutdown => {
+                    while tasks.try_join_next().is_some() {}
+                    tracing::info!("Shutdown requested; finishing {} in-flight requests", tasks.len());
+                    drop(rx);
+                    server.shut_down(&mut tasks).await;
+                    return Ok(StdioExit::Signal);
                 }
-                Err(e) => {
-                    eprintln!("Error handling message: {}", e);
+            };
+
+            let server = server.clone();
+            tasks.spawn(async move {
+                match server.handle_line(&line).await {
+                    Ok(Some(response)) => write_message(&response),
+                    Ok(None) => {
+                        // Notification, no response needed
+                    }
+                    Err(e) => {
+                        tracing::error!(
+                            "Error handling message: {}",
+                            redact::for_log(&e.to_string(), server.redact_logs)
+                        );
+                    }
                 }
+            });
+
+            // Reap finished handlers so the set doesn't grow over a long session
+            while tasks.try_join_next().is_some() {}
+        }
+
+        // Input is closed; let in-flight requests write their responses
+        while tasks.join_next().await.is_some() {}
+
+        reader.await.map_err(std::io::Error::other)??;
+        Ok(StdioExit::InputClosed)
+    }
+
+    /// Wait for in-flight requests after a shutdown signal, cancelling stragglers
+    async fn shut_down(&self, tasks: &mut tokio::task::JoinSet<()>) {
+        if !drain(tasks, SHUTDOWN_TIMEOUT).await {
+            tracing::warn!(
+                "{} requests still running after {}s; cancelling them",
+                tasks.len(),
+                SHUTDOWN_TIMEOUT.as_secs()
+            );
+            for token in self.in_flight.lock().unwrap().values() {
+                token.cancel();
+            }
+
+            if !drain(tasks, CANCEL_GRACE).await {
+                tasks.abort_all();
+                while tasks.join_next().await.is_some() {}
+            }
+        }
+
+        if let Err(e) = std::io::stdout().lock().flush() {
+            tracing::warn!("Failed to flush stdout: {}", e);
+        }
+    }
+
+    /// Handle one line of input, which may be a single message or a batch array
+    ///
+    /// A batch is answered with an array holding the responses to its requests;
+    /// notifications contribute nothing, and a batch of only notifications gets
+    /// no reply at all.
+    async fn handle_line(&self, line: &str) -> Result<Option<Value>> {
+        if !line.trim_start().starts_with('[') {
+            return match self.handle_message(line).await? {
+                Some(response) => Ok(Some(serde_json::to_value(response)?)),
+                None => Ok(None),
+            };
+        }
+
+        let messages: Vec<Value> = match serde_json::from_str(line) {
+            Ok(messages) => messages,
+            Err(e) => {
+                return Ok(Some(serde_json::to_value(JsonRpcResponse::error(
+                    RequestId::Number(0),
+                    JsonRpcError::parse_error(e.to_string()),
+                ))?));
+            }
+        };
+
+        if messages.is_empty() {
+            return Ok(Some(serde_json::to_value(JsonRpcResponse::error(
+                RequestId::Number(0),
+                JsonRpcError::invalid_request("Empty batch"),
+            ))?));
+        }
+
+        let mut responses = Vec::new();
+        for message in messages {
+            if let Some(response) = self.handle_message(&message.to_string()).await? {
+                responses.push(response);
             }
         }
 
-        Ok(())
+        if responses.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(serde_json::to_value(responses)?))
+        }
     }
 
     /// Handle an incoming JSON-RPC message
-    async fn handle_message(&mut self, message: &str) -> Result<Option<JsonRpcResponse>> {
-        // Try to parse as request
+    async fn handle_message(&self, message: &str) -> Result<Option<JsonRpcResponse>> {
+        // Try to parse as request, then as notification (no id)
         let request: JsonRpcRequest = match serde_json::from_str(message) {
             Ok(req) => req,
             Err(e) => {
+                if let Ok(notification) = serde_json::from_str::<JsonRpcNotification>(message) {
+                    self.handle_notification(&notification);
+                    return Ok(None);
+                }
                 return Ok(Some(JsonRpcResponse::error(
                     RequestId::Number(0),
                     JsonRpcError::parse_error(e.to_string()),
@@ -91,10 +340,6 @@ impl McpServer {
                 let result = self.handle_initialize(&request).await?;
                 Ok(Some(JsonRpcResponse::success(request.id, result)))
             }
-            methods::INITIALIZED => {
-                self.initialized = true;
-                Ok(None) // Notification, no response
-            }
             methods::PING => {
                 Ok(Some(JsonRpcResponse::success(
                     request.id,
@@ -109,6 +354,21 @@ impl McpServer {
                 let result = self.handle_call_tool(&request).await;
                 Ok(Some(JsonRpcResponse::success(request.id, result)))
             }
+            methods::LIST_RESOURCES => match self.handle_list_resources().await {
+                Ok(result) => Ok(Some(JsonRpcResponse::success(request.id, result))),
+                Err(e) => Ok(Some(JsonRpcResponse::error(
+                    request.id,
+                    JsonRpcError::internal_error(e.to_string()),
+                ))),
+            },
+            methods::READ_RESOURCE => Ok(Some(self.handle_read_resource(&request).await)),
+            methods::LIST_PROMPTS => {
+                let result = serde_json::to_value(ListPromptsResult {
+                    prompts: prompts::list_prompts(),
+                })?;
+                Ok(Some(JsonRpcResponse::success(request.id, result)))
+            }
+            methods::GET_PROMPT => Ok(Some(self.handle_get_prompt(&request))),
             _ => Ok(Some(JsonRpcResponse::error(
                 request.id,
                 JsonRpcError::method_not_found(&request.method),
@@ -116,19 +376,69 @@ impl McpServer {
         }
     }
 
+    /// Handle an incoming JSON-RPC notification
+    fn handle_notification(&self, notification: &JsonRpcNotification) {
+        match notification.method.as_str() {
+            methods::INITIALIZED => {
+                self.initialized.store(true, Ordering::SeqCst);
+            }
+            methods::CANCELLED => {
+                let params: Option<CancelledParams> = notification
+                    .params
+                    .as_ref()
+                    .and_then(|p| serde_json::from_value(p.clone()).ok());
+
+                if let Some(params) = params {
+                    let in_flight = self.in_flight.lock().unwrap();
+                    if let Some(token) = in_flight.get(&params.request_id) {
+                        tracing::info!(
+                            "Cancelling request {:?}: {}",
+                            params.request_id,
+                            params.reason.as_deref().unwrap_or("no reason given")
+                        );
+                        token.cancel();
+                    }
+                }
+            }
+            _ => {
+                tracing::debug!("Ignoring notification: {}", notification.method);
+            }
+        }
+    }
+
     /// Handle initialize request
-    async fn handle_initialize(&self, _request: &JsonRpcRequest) -> Result<Value> {
+    async fn handle_initialize(&self, request: &JsonRpcRequest) -> Result<Value> {
+        let params: Option<InitializeParams> = request
+            .params
+            .as_ref()
+            .and_then(|p| serde_json::from_value(p.clone()).ok());
+
+        if let Some(ref params) = params {
+            tracing::info!(
+                "Client {} {} requested protocol {}",
+                params.client_info.name,
+                params.client_info.version,
+                params.protocol_version
+            );
+        }
+
+        let protocol_version =
+            negotiate_protocol_version(params.as_ref().map(|p| p.protocol_version.as_str()));
+        if params.as_ref().map(|p| p.protocol_version.as_str()) != Some(protocol_version) {
+            tracing::warn!(
+                "Client protocol version {:?} is not supported; answering with {}",
+                params.as_ref().map(|p| &p.protocol_version),
+                protocol_version
+            );
+        }
+
         let result = InitializeResult {
-            protocol_version: MCP_VERSION.to_string(),
+            protocol_version: protocol_version.to_string(),
             server_info: ServerInfo {
                 name: SERVER_NAME.to_string(),
                 version: SERVER_VERSION.to_string(),
             },
-            capabilities: ServerCapabilities {
-                tools: Some(ToolsCapability {}),
-                resources: None,
-                prompts: None,
-            },
+            capabilities: server_capabilities(),
         };
 
         Ok(serde_json::to_value(result)?)
@@ -162,20 +472,452 @@ impl McpServer {
             }
         };
 
-        let result = self.tool_handler.call_tool(&params.name, params.arguments).await;
+        let ctx = ToolContext {
+            progress: params
+                .meta
+                .as_ref()
+                .and_then(|m| m.progress_token.clone())
+                .map(|token| ProgressReporter::new(token, write_notification)),
+            ..Default::default()
+        };
+        self.in_flight
+            .lock()
+            .unwrap()
+            .insert(request.id.clone(), ctx.cancel.clone());
+
+        let result = self
+            .tool_handler
+            .call_tool(&params.name, params.arguments, &ctx)
+            .await;
+
+        self.in_flight.lock().unwrap().remove(&request.id);
+
         serde_json::to_value(result).unwrap_or_else(|e| {
             serde_json::to_value(CallToolResult::error(e.to_string())).unwrap()
         })
     }
+
+    /// Handle list resources request
+    ///
+    /// Exposes the most recent inbox messages as `gmail://message/{id}` resources.
+    async fn handle_list_resources(&self) -> Result<Value> {
+        let messages = self
+            .gmail_client
+            .search_messages("in:inbox", Some(RECENT_MESSAGE_RESOURCES))
+            .await?;
+
+        let resources = messages
+            .into_iter()
+            .map(|m| Resource {
+                uri: format!("{}{}", MESSAGE_URI_PREFIX, m.id),
+                name: if m.subject.is_empty() {
+                    "(no subject)".to_string()
+                } else {
+                    m.subject
+                },
+                description: Some(format!(
+                    "From {} on {}",
+                    m.from,
+                    m.received.as_deref().unwrap_or(&m.date)
+                )),
+                mime_type: Some("text/plain".to_string()),
+            })
+            .collect();
+
+        Ok(serde_json::to_value(ListResourcesResult { resources })?)
+    }
+
+    /// Handle read resource request
+    async fn handle_read_resource(&self, request: &JsonRpcRequest) -> JsonRpcResponse {
+        let params: ReadResourceParams = match request
+            .params
+            .as_ref()
+            .map(|p| serde_json::from_value(p.clone()))
+        {
+            Some(Ok(params)) => params,
+            Some(Err(e)) => {
+                return JsonRpcResponse::error(
+                    request.id.clone(),
+                    JsonRpcError::invalid_params(format!("Invalid resource parameters: {}", e)),
+                );
+            }
+            None => {
+                return JsonRpcResponse::error(
+                    request.id.clone(),
+                    JsonRpcError::invalid_params("Missing resource parameters"),
+                );
+            }
+        };
+
+        let message_id = match parse_message_uri(&params.uri) {
+            Some(id) => id,
+            None => {
+                return JsonRpcResponse::error(
+                    request.id.clone(),
+                    JsonRpcError::invalid_params(format!("Unknown resource URI: {}", params.uri)),
+                );
+            }
+        };
+
+        let message = match self.gmail_client.read_message(message_id).await {
+            Ok(m) => m,
+            Err(e) => {
+                return JsonRpcResponse::error(
+                    request.id.clone(),
+                    JsonRpcError::internal_error(e.to_string()),
+                );
+            }
+        };
+
+        let text = format!(
+            "Subject: {}\nFrom: {}\nTo: {}\nDate: {}\nReceived: {}\n\n{}",
+            message.subject,
+            message.from,
+            message.to,
+            message.date,
+            message.received.as_deref().unwrap_or("unknown"),
+            message.body
+        );
+
+        let result = ReadResourceResult {
+            contents: vec![ResourceContent {
+                uri: params.uri.clone(),
+                text: Some(text),
+                blob: None,
+                mime_type: Some("text/plain".to_string()),
+            }],
+        };
+
+        match serde_json::to_value(result) {
+            Ok(value) => JsonRpcResponse::success(request.id.clone(), value),
+            Err(e) => JsonRpcResponse::error(
+                request.id.clone(),
+                JsonRpcError::internal_error(e.to_string()),
+            ),
+        }
+    }
+
+    /// Handle get prompt request
+    fn handle_get_prompt(&self, request: &JsonRpcRequest) -> JsonRpcResponse {
+        let params: GetPromptParams = match request
+            .params
+            .as_ref()
+            .map(|p| serde_json::from_value(p.clone()))
+        {
+            Some(Ok(params)) => params,
+            Some(Err(e)) => {
+                return JsonRpcResponse::error(
+                    request.id.clone(),
+                    JsonRpcError::invalid_params(format!("Invalid prompt parameters: {}", e)),
+                );
+            }
+            None => {
+                return JsonRpcResponse::error(
+                    request.id.clone(),
+                    JsonRpcError::invalid_params("Missing prompt parameters"),
+                );
+            }
+        };
+
+        match prompts::get_prompt(&params.name, &params.arguments)
+            .and_then(|result| Ok(serde_json::to_value(result)?))
+        {
+            Ok(value) => JsonRpcResponse::success(request.id.clone(), value),
+            Err(e) => JsonRpcResponse::error(
+                request.id.clone(),
+                JsonRpcError::invalid_params(e.to_string()),
+            ),
+        }
+    }
+}
+
+/// Wait for every task in `tasks`, giving up after `timeout`; true if all finished
+async fn drain(tasks: &mut tokio::task::JoinSet<()>, timeout: Duration) -> bool {
+    tokio::time::timeout(timeout, async {
+        while tasks.join_next().await.is_some() {}
+    })
+    .await
+    .is_ok()
+}
+
+/// Resolve when the process is asked to stop: Ctrl-C, or SIGTERM on Unix
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    result = tokio::signal::ctrl_c() => {
+                        if let Err(e) = result {
+                            tracing::warn!("Could not install Ctrl-C handler: {}", e);
+                            terminate.recv().await;
+                        }
+                    }
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(e) => tracing::warn!("Could not install SIGTERM handler: {}", e),
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        // Without a handler the server simply runs until stdin closes
+        tracing::warn!("Could not install Ctrl-C handler: {}", e);
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Write one message to stdout as a single line
+///
+/// The stdout lock is held for the whole line, so concurrent handlers never
+/// interleave their output.
+fn write_message<T: Serialize>(message: &T) {
+    let line = match serde_json::to_string(message) {
+        Ok(line) => line,
+        Err(e) => {
+            tracing::warn!("Failed to serialize message: {}", e);
+            return;
+        }
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    if let Err(e) = writeln!(stdout, "{}", line).and_then(|_| stdout.flush()) {
+        tracing::warn!("Failed to write message: {}", e);
+    }
+}
+
+/// Write a notification to stdout
+///
+/// Used for notifications emitted while a request is still being handled,
+/// such as batch progress.
+fn write_notification(notification: JsonRpcNotification) {
+    write_message(&notification);
+}
+
+/// Why `McpServer::run_stdio` returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StdioExit {
+    /// stdin reached end of input and every request was answered
+    InputClosed,
+    /// A shutdown signal arrived; the stdin reader may still be blocked
+    Signal,
+}
+
+/// Outcome of reading one line from the transport
+#[derive(Debug, PartialEq, Eq)]
+enum LineRead {
+    /// A complete line is in the buffer (without its newline)
+    Line,
+    /// The line exceeded the limit and was discarded
+    TooLong,
+    /// End of input
+    Eof,
+}
+
+/// Read one newline-terminated line into `buf`, storing at most `max_len` bytes
+///
+/// An oversized line is still consumed up to its newline so the next read
+/// starts on a fresh message, but it's never held in memory.
+fn read_bounded_line<R: BufRead>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    max_len: usize,
+) -> std::io::Result<LineRead> {
+    buf.clear();
+    let mut too_long = false;
+
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        if available.is_empty() {
+            return Ok(match (too_long, buf.is_empty()) {
+                (true, _) => LineRead::TooLong,
+                (false, true) => LineRead::Eof,
+                (false, false) => LineRead::Line,
+            });
+        }
+
+        let newline = available.iter().position(|&b| b == b'\n');
+        let chunk = &available[..newline.unwrap_or(available.len())];
+
+        if !too_long {
+            if buf.len() + chunk.len() > max_len {
+                too_long = true;
+                buf.clear();
+            } else {
+                buf.extend_from_slice(chunk);
+            }
+        }
+
+        let consumed = newline.map_or(available.len(), |i| i + 1);
+        reader.consume(consumed);
+
+        if newline.is_some() {
+            return Ok(if too_long { LineRead::TooLong } else { LineRead::Line });
+        }
+    }
+}
+
+/// Pick the protocol version to answer `initialize` with
+///
+/// The client's version is echoed back when supported; otherwise we offer the
+/// latest we support, as MCP requires, and let the client decide whether to continue.
+fn negotiate_protocol_version(requested: Option<&str>) -> &'static str {
+    requested
+        .and_then(|v| SUPPORTED_PROTOCOL_VERSIONS.iter().find(|s| **s == v))
+        .or(SUPPORTED_PROTOCOL_VERSIONS.last())
+        .copied()
+        .unwrap_or(MCP_VERSION)
+}
+
+/// Capabilities advertised during `initialize`
+pub(crate) fn server_capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        tools: Some(ToolsCapability {}),
+        resources: Some(ResourcesCapability::default()),
+        prompts: Some(PromptsCapability::default()),
+    }
+}
+
+/// Extract the message ID from a `gmail://message/{id}` URI
+fn parse_message_uri(uri: &str) -> Option<&str> {
+    uri.strip_prefix(MESSAGE_URI_PREFIX)
+        .filter(|id| !id.is_empty() && !id.contains('/'))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gmail::mock::MockGmailClient;
+
+    /// Server backed by the in-memory sample mailbox
+    async fn test_server() -> McpServer {
+        McpServer::new(Arc::new(MockGmailClient::sample()))
+    }
+
+    #[tokio::test]
+    async fn test_handle_line_batch() {
+        let server = test_server().await;
+        let line = r#"[
+            {"jsonrpc": "2.0", "id": 1, "method": "ping"},
+            {"jsonrpc": "2.0", "method": "notifications/initialized"},
+            {"jsonrpc": "2.0", "id": 2, "method": "ping"}
+        ]"#
+        .replace('\n', "");
+
+        let response = server.handle_line(&line).await.unwrap().unwrap();
+        let responses = response.as_array().unwrap();
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[1]["id"], 2);
+        assert!(server.initialized.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_handle_line_single_and_empty_batch() {
+        let server = test_server().await;
+
+        let single = server
+            .handle_line(r#"{"jsonrpc": "2.0", "id": 7, "method": "ping"}"#)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(single["id"], 7);
+
+        let empty = server.handle_line("[]").await.unwrap().unwrap();
+        assert_eq!(empty["error"]["code"], -32600);
+
+        let notifications_only = server
+            .handle_line(r#"[{"jsonrpc": "2.0", "method": "notifications/initialized"}]"#)
+            .await
+            .unwrap();
+        assert!(notifications_only.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_and_resources_against_mock() {
+        let server = test_server().await;
+
+        let call = server
+            .handle_line(
+                r#"{"jsonrpc": "2.0", "id": 1, "method": "tools/call",
+                    "params": {"name": "search_emails", "arguments": {"query": "from:bob"}}}"#,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let text = call["result"]["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("Invoice #1042"), "{}", text);
+        assert!(!text.contains("Project kickoff"), "{}", text);
+
+        let resources = server
+            .handle_line(r#"{"jsonrpc": "2.0", "id": 2, "method": "resources/list"}"#)
+            .await
+            .unwrap()
+            .unwrap();
+        let uri = resources["result"]["resources"][0]["uri"].as_str().unwrap().to_string();
+
+        let read = server
+            .handle_line(&format!(
+                r#"{{"jsonrpc": "2.0", "id": 3, "method": "resources/read", "params": {{"uri": "{}"}}}}"#,
+                uri
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(read["result"]["contents"][0]["text"].as_str().unwrap().contains("Weekly digest"));
+    }
 
     #[test]
     fn test_server_info() {
         assert_eq!(SERVER_NAME, "gmail");
     }
+
+    #[test]
+    fn test_read_bounded_line() {
+        let input = b"short\nthis line is far too long\n\xff\xfe\nlast";
+        let mut reader = std::io::BufReader::with_capacity(4, &input[..]);
+        let mut buf = Vec::new();
+
+        assert_eq!(read_bounded_line(&mut reader, &mut buf, 10).unwrap(), LineRead::Line);
+        assert_eq!(buf, b"short");
+
+        assert_eq!(read_bounded_line(&mut reader, &mut buf, 10).unwrap(), LineRead::TooLong);
+        assert!(buf.is_empty());
+
+        // Invalid UTF-8 is passed through for the caller to reject
+        assert_eq!(read_bounded_line(&mut reader, &mut buf, 10).unwrap(), LineRead::Line);
+        assert!(std::str::from_utf8(&buf).is_err());
+
+        assert_eq!(read_bounded_line(&mut reader, &mut buf, 10).unwrap(), LineRead::Line);
+        assert_eq!(buf, b"last");
+
+        assert_eq!(read_bounded_line(&mut reader, &mut buf, 10).unwrap(), LineRead::Eof);
+    }
+
+    #[test]
+    fn test_negotiate_protocol_version() {
+        assert_eq!(negotiate_protocol_version(Some("2025-03-26")), "2025-03-26");
+        assert_eq!(negotiate_protocol_version(Some(MCP_VERSION)), MCP_VERSION);
+        assert_eq!(negotiate_protocol_version(Some("1999-01-01")), "2025-03-26");
+        assert_eq!(negotiate_protocol_version(Some("2099-01-01")), "2025-03-26");
+        assert_eq!(negotiate_protocol_version(None), "2025-03-26");
+    }
+
+    #[test]
+    fn test_parse_message_uri() {
+        assert_eq!(parse_message_uri("gmail://message/18c2f0a1"), Some("18c2f0a1"));
+        assert_eq!(parse_message_uri("gmail://message/"), None);
+        assert_eq!(parse_message_uri("gmail://thread/18c2f0a1"), None);
+        assert_eq!(parse_message_uri("gmail://message/a/b"), None);
+    }
 }
 
@@ -2,97 +2,555 @@
 //!
 //! Defines all available tools and their implementations.
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use serde::Deserialize;
 use serde_json::{json, Value};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 
-use crate::gmail::client::GmailClient;
-use crate::gmail::filters::FilterTemplates;
-use crate::gmail::types::{FilterAction, FilterCriteria, SizeComparison, UpdateLabelRequest};
-use crate::gmail::utils::{decode_base64url, format_size, EmailParams, MimeType};
-use crate::mcp::types::{CallToolResult, Tool};
+use crate::config::gmail::scopes;
+use crate::config::{DEFAULT_CONFIRM_THRESHOLD, DEFAULT_MAX_RESULT_CHARS};
+use crate::error::{GmailApiError, GmailMcpError};
+use crate::gmail::aliases::RecipientAliases;
+use crate::gmail::api::GmailApi;
+use crate::gmail::last_seen::{LastSeen, Marker};
+use crate::gmail::saved_queries::SavedQueries;
+use crate::gmail::client::{
+    BatchControl, BatchOperationResult, ImportOptions, ProgressCallback, ReadMessageResult, ReplyContext, SearchMessageResult,
+    DEFAULT_SEARCH_RESULTS, DEFAULT_SEND_INTERVAL, MAX_BATCH_DELETE_IDS, MAX_SEARCH_RESULTS, MAX_SEND_INTERVAL,
+};
+use crate::gmail::filters::{criteria_to_query, same_filter, FilterActionFlags, FilterTemplates, TextField};
+use crate::gmail::quota::USER_UNITS_PER_SECOND;
+use crate::gmail::types::{
+    EmailAttachment, Filter, FilterAction, FilterCriteria, FilterList, Header, Label, LabelList, SizeComparison,
+    UpdateLabelRequest,
+};
+use crate::gmail::utils::{
+    attachment_matches, attachments_to_drop, format_from, format_size, sanitize_filename, truncate_chars, unique_file_path, validate_email,
+    EmailParams, MimeType, QueryBuilder, Signature, UnsubscribeInfo,
+};
+use crate::mcp::access::ToolAccess;
+use crate::mcp::server::{server_capabilities, SERVER_NAME, SERVER_VERSION};
+use crate::mcp::types::{
+    methods, CallToolResult, JsonRpcNotification, ProgressParams, Tool, ToolResultContent,
+    JSONRPC_VERSION, MCP_VERSION,
+};
+use crate::redact;
+
+/// Per-request context passed to tool handlers
+#[derive(Debug, Clone, Default)]
+pub struct ToolContext {
+    /// Cancelled when the client sends `notifications/cancelled` for this request
+    pub cancel: CancellationToken,
+
+    /// Set when the client asked for progress notifications
+    pub progress: Option<ProgressReporter>,
+}
+
+impl ToolContext {
+    /// Batch controls wired to this request's cancellation and progress
+    fn batch_control(&self) -> BatchControl {
+        BatchControl {
+            cancel: self.cancel.clone(),
+            progress: self.progress.clone().map(|reporter| {
+                Arc::new(move |processed: usize, total: usize| {
+                    reporter.report(processed as u64, Some(total as u64))
+                }) as ProgressCallback
+            }),
+        }
+    }
+}
+
+/// Sends `notifications/progress` tagged with the client's progress token
+#[derive(Clone)]
+pub struct ProgressReporter {
+    token: Value,
+    send: Arc<dyn Fn(JsonRpcNotification) + Send + Sync>,
+}
+
+impl ProgressReporter {
+    /// Create a reporter that hands each notification to `send`
+    pub fn new(token: Value, send: impl Fn(JsonRpcNotification) + Send + Sync + 'static) -> Self {
+        Self {
+            token,
+            send: Arc::new(send),
+        }
+    }
+
+    /// Report progress
+    pub fn report(&self, progress: u64, total: Option<u64>) {
+        let params = ProgressParams {
+            progress_token: self.token.clone(),
+            progress,
+            total,
+        };
+
+        (self.send)(JsonRpcNotification {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            method: methods::PROGRESS.to_string(),
+            params: serde_json::to_value(params).ok(),
+        });
+    }
+}
+
+impl std::fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressReporter")
+            .field("token", &self.token)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Arguments shared by `send_email` and `draft_email`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SendEmailArgs {
+    to: Vec<String>,
+    subject: String,
+    body: String,
+    html_body: Option<String>,
+    mime_type: Option<String>,
+    cc: Option<Vec<String>>,
+    bcc: Option<Vec<String>>,
+    reply_to: Option<String>,
+    thread_id: Option<String>,
+    in_reply_to: Option<String>,
+    reply_to_message_id: Option<String>,
+    attachments: Option<Vec<AttachmentInput>>,
+    headers: Option<std::collections::BTreeMap<String, String>>,
+    idempotency_key: Option<String>,
+    label_ids: Option<Vec<String>>,
+    label_names: Option<Vec<String>>,
+    append_signature: Option<bool>,
+    from_name: Option<String>,
+}
+
+/// An `attachments` entry: a local path or URL, or the file's content inline
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AttachmentInput {
+    Location(String),
+    #[serde(rename_all = "camelCase")]
+    Inline {
+        filename: String,
+        mime_type: Option<String>,
+        data_base64: String,
+    },
+}
+
+/// Headers returned by `get_message_raw_header` when none are named
+const DELIVERABILITY_HEADERS: &[&str] = &[
+    "Authentication-Results",
+    "ARC-Authentication-Results",
+    "Received-SPF",
+    "DKIM-Signature",
+    "Received",
+    "Return-Path",
+    "From",
+];
+
+/// Most messages `batch_read_emails` reads in one call
+const MAX_BATCH_READ: usize = 100;
+
+/// Most new messages `get_new_messages` lists with their headers
+const MAX_NEW_MESSAGES: usize = 100;
+
+/// Most matching messages `preview_filter` counts before reporting "more than"
+const PREVIEW_COUNT_LIMIT: usize = 1000;
+
+/// Messages `batch_read_emails` fetches at the same time
+const BATCH_READ_CONCURRENCY: usize = 8;
+
+/// How long a send's idempotency key is remembered
+const IDEMPOTENCY_KEY_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// State of an idempotency key
+enum IdempotencyClaim<'a> {
+    /// First use: the caller should perform the operation and complete the reservation
+    New(IdempotencyReservation<'a>),
+    /// An earlier call with this key is still running
+    InProgress,
+    /// An earlier call with this key succeeded; this is its result
+    Done(CallToolResult),
+}
+
+/// Recently used idempotency keys and the results they produced
+///
+/// Only successful results are kept, so a retry after a failed send tries again.
+#[derive(Default)]
+struct IdempotencyCache {
+    entries: Mutex<HashMap<String, (Instant, Option<CallToolResult>)>>,
+}
+
+impl IdempotencyCache {
+    /// Reserve `key`, or report what an earlier call with it did
+    fn claim(&self, key: &str) -> IdempotencyClaim<'_> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (used_at, _)| used_at.elapsed() < IDEMPOTENCY_KEY_TTL);
+
+        match entries.get(key) {
+            Some((_, Some(result))) => IdempotencyClaim::Done(result.clone()),
+            Some((_, None)) => IdempotencyClaim::InProgress,
+            None => {
+                entries.insert(key.to_string(), (Instant::now(), None));
+                IdempotencyClaim::New(IdempotencyReservation {
+                    cache: self,
+                    key: key.to_string(),
+                    completed: false,
+                })
+            }
+        }
+    }
+}
+
+/// A claimed idempotency key whose call is still running
+///
+/// Dropping it without `complete`, as happens when the call is cancelled,
+/// releases the key so a retry isn't refused as in progress.
+struct IdempotencyReservation<'a> {
+    cache: &'a IdempotencyCache,
+    key: String,
+    completed: bool,
+}
+
+impl IdempotencyReservation<'_> {
+    /// Record the outcome of the call that claimed the key
+    fn complete(mut self, result: &CallToolResult) {
+        self.completed = true;
+        let mut entries = self.cache.entries.lock().unwrap();
+        if result.is_error {
+            entries.remove(&self.key);
+        } else {
+            entries.insert(self.key.clone(), (Instant::now(), Some(result.clone())));
+        }
+    }
+}
+
+impl Drop for IdempotencyReservation<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.cache.entries.lock().unwrap().remove(&self.key);
+        }
+    }
+}
 
 /// Tool handler
 pub struct ToolHandler {
-    gmail_client: Arc<GmailClient>,
+    gmail_client: Arc<dyn GmailApi>,
+
+    /// Results of recent sends, keyed by the client's idempotency key
+    idempotency: IdempotencyCache,
+
+    /// Whether error text is redacted before it is logged
+    redact_logs: bool,
+
+    /// Longest result text returned to the client; 0 means no limit
+    max_result_chars: usize,
+
+    /// Recipient groups expanded in `to`/`cc`/`bcc`
+    aliases: RecipientAliases,
+
+    /// Signature appended to outgoing mail
+    signature: Option<Signature>,
+
+    /// Display name put in front of the sender address
+    from_name: Option<String>,
+
+    /// Search results returned when `maxResults` is omitted
+    default_max_results: u32,
+
+    /// Named queries for `save_query` and `run_saved_query`
+    saved_queries: SavedQueries,
+
+    /// How far `get_new_messages` has read the inbox
+    last_seen: LastSeen,
+
+    /// Which tools the operator has enabled or disabled
+    tool_access: ToolAccess,
+
+    /// Messages a bulk delete or relabel may touch without `confirm: true`
+    confirm_threshold: usize,
 }
 
 impl ToolHandler {
-    /// Create a new tool handler
-    pub fn new(gmail_client: Arc<GmailClient>) -> Self {
-        Self { gmail_client }
+    /// Create a new tool handler over any Gmail backend, e.g. `GmailClient` or `MockGmailClient`
+    pub fn new(gmail_client: Arc<dyn GmailApi>) -> Self {
+        Self {
+            gmail_client,
+            idempotency: IdempotencyCache::default(),
+            redact_logs: true,
+            max_result_chars: DEFAULT_MAX_RESULT_CHARS,
+            aliases: RecipientAliases::default(),
+            signature: None,
+            from_name: None,
+            default_max_results: DEFAULT_SEARCH_RESULTS,
+            saved_queries: SavedQueries::default(),
+            last_seen: LastSeen::default(),
+            tool_access: ToolAccess::default(),
+            confirm_threshold: DEFAULT_CONFIRM_THRESHOLD,
+        }
     }
 
-    /// List all available tools
+    /// Set how many messages a bulk delete or relabel may touch before it needs `confirm: true`
+    pub fn with_confirm_threshold(mut self, confirm_threshold: usize) -> Self {
+        self.confirm_threshold = confirm_threshold;
+        self
+    }
+
+    /// Set which tools are listed and may be called
+    pub fn with_tool_access(mut self, tool_access: ToolAccess) -> Self {
+        let tools = all_tools();
+        let known: Vec<&str> = tools.iter().map(|tool| tool.name.as_str()).collect();
+        for name in tool_access.unknown_names(&known) {
+            tracing::warn!("Ignoring unknown tool '{}' in GMAIL_ENABLED_TOOLS/GMAIL_DISABLED_TOOLS", name);
+        }
+        self.tool_access = tool_access;
+        self
+    }
+
+    /// Set the store behind `save_query` and `run_saved_query`
+    pub fn with_saved_queries(mut self, saved_queries: SavedQueries) -> Self {
+        self.saved_queries = saved_queries;
+        self
+    }
+
+    /// Set the marker `get_new_messages` reads from and advances
+    pub fn with_last_seen(mut self, last_seen: LastSeen) -> Self {
+        self.last_seen = last_seen;
+        self
+    }
+
+    /// Set whether addresses and content are redacted from logged errors
+    pub fn with_log_redaction(mut self, redact_logs: bool) -> Self {
+        self.redact_logs = redact_logs;
+        self
+    }
+
+    /// Set the longest result text returned to the client; 0 disables the cap
+    pub fn with_max_result_chars(mut self, max_result_chars: usize) -> Self {
+        self.max_result_chars = max_result_chars;
+        self
+    }
+
+    /// Set the recipient groups that `send_email` and `draft_email` expand
+    pub fn with_aliases(mut self, aliases: RecipientAliases) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Set how many results `search_emails` returns when `maxResults` is omitted
+    pub fn with_default_max_results(mut self, default_max_results: u32) -> Self {
+        self.default_max_results = default_max_results.clamp(1, MAX_SEARCH_RESULTS);
+        self
+    }
+
+    /// Set the signature appended to outgoing mail
+    pub fn with_signature(mut self, signature: Option<Signature>) -> Self {
+        self.signature = signature;
+        self
+    }
+
+    /// Set the display name shown with the sender address
+    pub fn with_from_name(mut self, from_name: Option<String>) -> Self {
+        self.from_name = from_name;
+        self
+    }
+
+    /// List the tools the operator hasn't disabled
     pub fn list_tools(&self) -> Vec<Tool> {
-        vec![
-            tool_def("send_email", "Sends a new email", send_email_schema()),
-            tool_def("draft_email", "Create a new email draft", send_email_schema()),
-            tool_def("read_email", "Retrieves the content of a specific email", read_email_schema()),
-            tool_def("search_emails", "Searches for emails using Gmail search syntax", search_emails_schema()),
-            tool_def("modify_email", "Modifies email labels (move to different folders)", modify_email_schema()),
-            tool_def("delete_email", "Permanently deletes an email", delete_email_schema()),
-            tool_def("list_email_labels", "Retrieves all available Gmail labels", json!({"type": "object", "properties": {}})),
-            tool_def("batch_modify_emails", "Modifies labels for multiple emails in batches", batch_modify_emails_schema()),
-            tool_def("batch_delete_emails", "Permanently deletes multiple emails in batches", batch_delete_emails_schema()),
-            tool_def("create_label", "Creates a new Gmail label", create_label_schema()),
-            tool_def("update_label", "Updates an existing Gmail label", update_label_schema()),
-            tool_def("delete_label", "Deletes a Gmail label", delete_label_schema()),
-            tool_def("get_or_create_label", "Gets an existing label by name or creates it if it doesn't exist", get_or_create_label_schema()),
-            tool_def("create_filter", "Creates a new Gmail filter with custom criteria and actions", create_filter_schema()),
-            tool_def("list_filters", "Retrieves all Gmail filters", json!({"type": "object", "properties": {}})),
-            tool_def("get_filter", "Gets details of a specific Gmail filter", get_filter_schema()),
-            tool_def("delete_filter", "Deletes a Gmail filter", delete_filter_schema()),
-            tool_def("create_filter_from_template", "Creates a filter using a pre-defined template for common scenarios", create_filter_from_template_schema()),
-            tool_def("download_attachment", "Downloads an email attachment to a specified location", download_attachment_schema()),
-        ]
+        all_tools()
+            .into_iter()
+            .filter(|tool| self.tool_access.allows(&tool.name))
+            .collect()
     }
 
     /// Call a tool by name
-    pub async fn call_tool(&self, name: &str, args: Value) -> CallToolResult {
+    ///
+    /// Each call runs in a `tool_call` span and logs its duration and outcome.
+    /// Only argument names are logged: values can hold addresses and message content.
+    pub async fn call_tool(&self, name: &str, args: Value, ctx: &ToolContext) -> CallToolResult {
+        let arg_keys: Vec<String> = args
+            .as_object()
+            .map(|map| map.keys().cloned().collect())
+            .unwrap_or_default();
+        let span = tracing::info_span!("tool_call", tool = name);
+        let started = Instant::now();
+
+        let result = async {
+            tracing::debug!(args = ?arg_keys, "Tool call started");
+            // Capped here so no single tool can flood the client
+            self.dispatch_tool(name, args, ctx).await.truncated(self.max_result_chars)
+        }
+        .instrument(span.clone())
+        .await;
+
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        span.in_scope(|| {
+            if result.is_error {
+                tracing::info!(elapsed_ms, outcome = "error", "Tool call failed");
+                let detail: Vec<String> = result
+                    .content
+                    .iter()
+                    .filter_map(|c| match c {
+                        ToolResultContent::Text { text } => {
+                            Some(redact::for_log(text, self.redact_logs))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                tracing::debug!(error = ?detail, "Tool call error detail");
+            } else {
+                tracing::info!(elapsed_ms, outcome = "ok", "Tool call completed");
+            }
+        });
+
+        result
+    }
+
+    async fn dispatch_tool(&self, name: &str, args: Value, ctx: &ToolContext) -> CallToolResult {
+        if let Some(reason) = self.tool_access.denial(name) {
+            return CallToolResult::error(reason);
+        }
+        if let Some(scope) = required_scope(name) {
+            if let Err(e) = self.gmail_client.check_scope(scope).await {
+                return CallToolResult::error(e.to_string());
+            }
+        }
+
         match name {
             "send_email" => self.handle_send_email(args, false).await,
+            "reply_email" => self.handle_reply_email(args).await,
+            "batch_read_emails" => self.handle_batch_read_emails(args).await,
             "draft_email" => self.handle_send_email(args, true).await,
+            "send_email_batch" => self.handle_send_email_batch(args, ctx).await,
             "read_email" => self.handle_read_email(args).await,
+            "get_thread_text" => self.handle_get_thread_text(args).await,
+            "get_message_raw_header" => self.handle_get_message_raw_header(args).await,
             "search_emails" => self.handle_search_emails(args).await,
+            "save_query" => self.handle_save_query(args).await,
+            "list_saved_queries" => self.handle_list_saved_queries().await,
+            "run_saved_query" => self.handle_run_saved_query(args).await,
+            "get_new_messages" => self.handle_get_new_messages().await,
+            "verify_recipient" => self.handle_verify_recipient(args).await,
             "modify_email" => self.handle_modify_email(args).await,
             "delete_email" => self.handle_delete_email(args).await,
-            "list_email_labels" => self.handle_list_labels().await,
-            "batch_modify_emails" => self.handle_batch_modify(args).await,
-            "batch_delete_emails" => self.handle_batch_delete(args).await,
+            "get_importance" => self.handle_get_importance(args).await,
+            "set_importance" => self.handle_set_importance(args).await,
+            "move_email" => self.handle_move_email(args).await,
+            "list_email_labels" => self.handle_list_labels(args).await,
+            "batch_modify_emails" => self.handle_batch_modify(args, ctx).await,
+            "batch_delete_emails" => self.handle_batch_delete(args, ctx).await,
+            "empty_trash" => self.handle_empty_trash(args, ctx).await,
+            "empty_spam" => self.handle_empty_spam(args, ctx).await,
             "create_label" => self.handle_create_label(args).await,
             "update_label" => self.handle_update_label(args).await,
             "delete_label" => self.handle_delete_label(args).await,
+            "get_label" => self.handle_get_label(args).await,
             "get_or_create_label" => self.handle_get_or_create_label(args).await,
+            "export_labels" => self.handle_export_labels(args).await,
+            "import_labels" => self.handle_import_labels(args).await,
             "create_filter" => self.handle_create_filter(args).await,
             "list_filters" => self.handle_list_filters().await,
             "get_filter" => self.handle_get_filter(args).await,
             "delete_filter" => self.handle_delete_filter(args).await,
+            "export_filters" => self.handle_export_filters(args).await,
+            "import_filters" => self.handle_import_filters(args).await,
             "create_filter_from_template" => self.handle_create_filter_template(args).await,
+            "preview_filter" => self.handle_preview_filter(args).await,
+            "apply_filter_to_existing" => self.handle_apply_filter_to_existing(args, ctx).await,
             "download_attachment" => self.handle_download_attachment(args).await,
+            "download_all_attachments" => self.handle_download_all_attachments(args).await,
+            "import_email" => self.handle_import_email(args).await,
+            "insert_email" => self.handle_insert_email(args).await,
+            "get_unsubscribe_info" => self.handle_get_unsubscribe_info(args).await,
+            "unsubscribe" => self.handle_unsubscribe(args).await,
+            "server_info" => self.handle_server_info(),
+            "get_usage" => self.handle_get_usage(),
             _ => CallToolResult::error(format!("Unknown tool: {}", name)),
         }
     }
 
     // ==================== Tool Handlers ====================
 
+    fn handle_server_info(&self) -> CallToolResult {
+        CallToolResult::text(format!(
+            "Server: {}\nVersion: {}\nMCP protocol version: {}\nCapabilities: {}",
+            SERVER_NAME,
+            SERVER_VERSION,
+            MCP_VERSION,
+            server_capabilities().enabled().join(", ")
+        ))
+    }
+
+    fn handle_get_usage(&self) -> CallToolResult {
+        let usage = self.gmail_client.quota_usage();
+        let elapsed = usage.elapsed.as_secs_f64().max(1.0);
+
+        let mut output = format!(
+            "Estimated Gmail quota usage this session ({}s):\nTotal: {} units over {} requests (avg {:.1} units/s; Gmail allows {} units/s per user)\n",
+            usage.elapsed.as_secs(),
+            usage.total_units,
+            usage.total_calls,
+            usage.total_units as f64 / elapsed,
+            USER_UNITS_PER_SECOND
+        );
+        for (method, used) in &usage.methods {
+            output.push_str(&format!(
+                "\n{}: {} calls x {} = {} units",
+                method.name(),
+                used.calls,
+                method.cost(),
+                used.units
+            ));
+        }
+
+        CallToolResult::text(output)
+    }
+
     async fn handle_send_email(&self, args: Value, draft: bool) -> CallToolResult {
-        use crate::gmail::utils::load_attachment;
+        let args: SendEmailArgs = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
 
+        // A retried call with the same key gets the original result instead of a second send
+        let Some(key) = args.idempotency_key.clone() else {
+            return self.send_or_draft(args, draft, None).await;
+        };
+        let key = format!("{}:{}", if draft { "draft" } else { "send" }, key);
+
+        let reservation = match self.idempotency.claim(&key) {
+            IdempotencyClaim::Done(result) => return result,
+            IdempotencyClaim::InProgress => {
+                return CallToolResult::error(
+                    "A request with this idempotencyKey is still in progress; retry once it completes",
+                )
+            }
+            IdempotencyClaim::New(reservation) => reservation,
+        };
+
+        let result = self.send_or_draft(args, draft, None).await;
+        reservation.complete(&result);
+        result
+    }
+
+    async fn handle_reply_email(&self, args: Value) -> CallToolResult {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct Args {
-            to: Vec<String>,
-            subject: String,
+            message_id: String,
             body: String,
             html_body: Option<String>,
             mime_type: Option<String>,
             cc: Option<Vec<String>>,
-            bcc: Option<Vec<String>>,
-            thread_id: Option<String>,
-            in_reply_to: Option<String>,
-            attachments: Option<Vec<String>>,
+            attachments: Option<Vec<AttachmentInput>>,
+            append_signature: Option<bool>,
+            from_name: Option<String>,
         }
 
         let args: Args = match serde_json::from_value(args) {
@@ -100,23 +558,132 @@ impl ToolHandler {
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
 
+        let reply = match self.gmail_client.reply_context(&args.message_id).await {
+            Ok(reply) => reply,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+        if reply.reply_recipients.is_empty() {
+            return CallToolResult::error(format!(
+                "Message {} has no From or Reply-To address to reply to",
+                args.message_id
+            ));
+        }
+
+        let send_args = SendEmailArgs {
+            to: reply.reply_recipients.clone(),
+            subject: reply.subject.clone(),
+            body: args.body,
+            html_body: args.html_body,
+            mime_type: args.mime_type,
+            cc: args.cc,
+            bcc: None,
+            reply_to: None,
+            thread_id: None,
+            in_reply_to: None,
+            reply_to_message_id: Some(args.message_id),
+            attachments: args.attachments,
+            headers: None,
+            idempotency_key: None,
+            label_ids: None,
+            label_names: None,
+            append_signature: args.append_signature,
+            from_name: args.from_name,
+        };
+        self.send_or_draft(send_args, false, Some(reply)).await
+    }
+
+    /// `From` header for outgoing mail: `name`, or else the configured display
+    /// name, with the profile's address; `None` when no name is set
+    async fn sender_header(&self, name: Option<&str>) -> Result<Option<String>, GmailMcpError> {
+        let name = name
+            .or(self.from_name.as_deref())
+            .map(str::trim)
+            .filter(|n| !n.is_empty());
+        match name {
+            Some(name) => Ok(Some(format_from(name, self.gmail_client.sender_email().await?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Send or draft a message; `reply` is the already-fetched context of the
+    /// message being replied to, looked up from `replyToMessageId` when absent
+    async fn send_or_draft(
+        &self,
+        args: SendEmailArgs,
+        draft: bool,
+        reply: Option<ReplyContext>,
+    ) -> CallToolResult {
+        use crate::gmail::utils::{
+            attachment_source, encoded_attachment_size, inline_attachment, load_attachment,
+            AttachmentSource,
+        };
+
+        // Labels go on the sent copy via a follow-up modify; drafts have no sent copy
+        let labels: Vec<String> = args
+            .label_ids
+            .iter()
+            .chain(args.label_names.iter())
+            .flatten()
+            .cloned()
+            .collect();
+        if draft && !labels.is_empty() {
+            return CallToolResult::error("labelIds/labelNames are only supported by send_email");
+        }
+
+        // Group names like "marketing" expand to the addresses in aliases.toml
+        let resolve = |recipients: Option<Vec<String>>| {
+            recipients.map(|r| self.aliases.resolve(r)).transpose()
+        };
+        let (to, cc, bcc) = match (resolve(Some(args.to)), resolve(args.cc), resolve(args.bcc)) {
+            (Ok(to), Ok(cc), Ok(bcc)) => (to.unwrap_or_default(), cc, bcc),
+            (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+                return CallToolResult::error(e.to_string())
+            }
+        };
+
         let mime_type = match args.mime_type.as_deref() {
             Some("text/html") => Some(MimeType::TextHtml),
             Some("multipart/alternative") => Some(MimeType::MultipartAlternative),
             _ => None,
         };
 
-        // Load attachments from file paths
+        // Load attachments from file paths, URLs or inline base64 content
         let attachments = match args.attachments {
-            Some(paths) if !paths.is_empty() => {
+            Some(entries) if !entries.is_empty() => {
                 let mut loaded = Vec::new();
-                for path in paths {
-                    match load_attachment(&path) {
+                for entry in entries {
+                    let (name, attachment) = match entry {
+                        AttachmentInput::Inline {
+                            filename,
+                            mime_type,
+                            data_base64,
+                        } => {
+                            let attachment =
+                                inline_attachment(&filename, mime_type.as_deref(), &data_base64);
+                            (filename, attachment)
+                        }
+                        AttachmentInput::Location(location) => {
+                            let attachment = match attachment_source(&location) {
+                                AttachmentSource::File => load_attachment(&location),
+                                AttachmentSource::Url => {
+                                    self.gmail_client.download_url_attachment(location.trim()).await
+                                }
+                                AttachmentSource::UnsupportedUrl => {
+                                    return CallToolResult::error(format!(
+                                        "Failed to load attachment '{}': only http(s) URLs and local paths are supported",
+                                        location
+                                    ))
+                                }
+                            };
+                            (location, attachment)
+                        }
+                    };
+                    match attachment {
                         Ok(attachment) => loaded.push(attachment),
                         Err(e) => {
                             return CallToolResult::error(format!(
                                 "Failed to load attachment '{}': {}",
-                                path, e
+                                name, e
                             ))
                         }
                     }
@@ -126,30 +693,208 @@ impl ToolHandler {
             _ => None,
         };
 
+        // Replying to a Gmail message: thread it properly using the original's headers.
+        // A bare threadId replies to the thread's latest message, so the draft or
+        // email carries In-Reply-To/References and threads for every recipient.
+        // If that lookup fails, the threadId alone still files it in the thread.
+        let mut threading_note = None;
+        let reply = match (&reply, &args.reply_to_message_id, &args.thread_id, &args.in_reply_to) {
+            (Some(_), ..) => reply,
+            (None, Some(id), ..) => match self.gmail_client.reply_context(id).await {
+                Ok(ctx) => Some(ctx),
+                Err(e) => return CallToolResult::error(e.to_string()),
+            },
+            (None, None, Some(thread_id), None) => match self.gmail_client.thread_reply_context(thread_id).await {
+                Ok(ctx) => Some(ctx),
+                Err(e) => {
+                    threading_note = Some(format!(
+                        "\nNote: no message to reply to was found in thread {} ({}), so In-Reply-To/References were not set; \
+                         recipients outside Gmail may not see it threaded",
+                        thread_id, e
+                    ));
+                    None
+                }
+            },
+            _ => None,
+        };
+        let (thread_id, in_reply_to, references) = match reply {
+            Some(ctx) => (
+                args.thread_id.or(ctx.thread_id),
+                Some(ctx.in_reply_to),
+                Some(ctx.references),
+            ),
+            None => (args.thread_id, args.in_reply_to, None),
+        };
+
+        let from = match self.sender_header(args.from_name.as_deref()).await {
+            Ok(from) => from,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
         let params = EmailParams {
-            to: args.to,
+            to,
             subject: args.subject,
             body: args.body,
             html_body: args.html_body,
             mime_type,
-            cc: args.cc,
-            bcc: args.bcc,
-            thread_id: args.thread_id,
-            in_reply_to: args.in_reply_to,
+            cc,
+            bcc,
+            reply_to: args.reply_to,
+            thread_id,
+            in_reply_to,
+            references,
             attachments,
+            headers: args.headers.map(|headers| {
+                headers
+                    .into_iter()
+                    .map(|(name, value)| Header { name, value })
+                    .collect()
+            }),
+            signature: self.signature.clone().filter(|_| args.append_signature.unwrap_or(true)),
+            from,
+            ..Default::default()
+        };
+
+        // Kept for the size report in case Gmail rejects the message as too large
+        let attachment_sizes: Vec<(String, usize)> = params
+            .attachments
+            .iter()
+            .flatten()
+            .map(|a| (a.filename.clone(), encoded_attachment_size(a.data.len())))
+            .collect();
+        let send_error = |e: GmailMcpError| match e {
+            GmailMcpError::Gmail(GmailApiError::MessageTooLarge { size, limit }) => {
+                CallToolResult::error(message_too_large_report(size, limit, &attachment_sizes))
+            }
+            e => CallToolResult::error(e.to_string()),
         };
 
+        let threading_note = threading_note.unwrap_or_default();
         if draft {
             match self.gmail_client.create_draft(params).await {
-                Ok(d) => CallToolResult::text(format!("Email draft created successfully with ID: {}", d.id)),
-                Err(e) => CallToolResult::error(e.to_string()),
+                Ok(d) => CallToolResult::text(format!(
+                    "Email draft created successfully with ID: {}{}",
+                    d.id, threading_note
+                )),
+                Err(e) => send_error(e),
             }
         } else {
-            match self.gmail_client.send_email(params).await {
-                Ok(m) => CallToolResult::text(format!("Email sent successfully with ID: {}", m.id)),
-                Err(e) => CallToolResult::error(e.to_string()),
+            let sent = match self.gmail_client.send_email(params).await {
+                Ok(sent) => sent,
+                Err(e) => return send_error(e),
+            };
+            let mut text = format!(
+                "Email sent successfully with ID: {}\nMessage-ID: {}{}",
+                sent.message.id, sent.message_id_header, threading_note
+            );
+
+            // The email is already out, so a labeling failure is reported, not returned as an error
+            if !labels.is_empty() {
+                let labeled = match self.gmail_client.resolve_label_ids(Some(labels.clone())).await {
+                    Ok(ids) => {
+                        self.gmail_client
+                            .modify_message(&sent.message.id, ids, None)
+                            .await
+                    }
+                    Err(e) => Err(e),
+                };
+                match labeled {
+                    Ok(_) => text.push_str(&format!("\nLabels applied: {}", labels.join(", "))),
+                    Err(e) => text.push_str(&format!(
+                        "\nWarning: the email was sent, but applying labels failed: {}",
+                        e
+                    )),
+                }
+            }
+
+            CallToolResult::text(text)
+        }
+    }
+
+    async fn handle_send_email_batch(&self, args: Value, ctx: &ToolContext) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Item {
+            to: Vec<String>,
+            subject: String,
+            body: String,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            messages: Vec<Item>,
+            interval_ms: Option<u64>,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        if args.messages.is_empty() {
+            return CallToolResult::error("messages must contain at least one item");
+        }
+
+        let from = match self.sender_header(None).await {
+            Ok(from) => from,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
+        let total = args.messages.len();
+        let messages = args
+            .messages
+            .into_iter()
+            .map(|item| EmailParams {
+                to: item.to,
+                subject: item.subject,
+                body: item.body,
+                signature: self.signature.clone(),
+                from: from.clone(),
+                ..Default::default()
+            })
+            .collect();
+
+        // Clamped so a huge interval can't leave the call hanging for hours
+        let interval = args
+            .interval_ms
+            .map(|ms| std::time::Duration::from_millis(ms).min(MAX_SEND_INTERVAL))
+            .unwrap_or(DEFAULT_SEND_INTERVAL);
+
+        let result = self
+            .gmail_client
+            .send_email_batch(messages, interval, &ctx.batch_control())
+            .await;
+
+        let mut text = if result.cancelled {
+            format!(
+                "Batch send cancelled.\nProcessed before cancellation: {} of {} messages\n",
+                result.success_count + result.failure_count,
+                total
+            )
+        } else {
+            "Batch send complete.\n".to_string()
+        };
+        text.push_str(&format!("Sent: {} messages\n", result.success_count));
+
+        if !result.succeeded.is_empty() {
+            text.push_str("\nSent message IDs:\n");
+            for id in &result.succeeded {
+                text.push_str(&format!("- {}\n", id));
+            }
+        }
+
+        if result.failure_count > 0 {
+            text.push_str(&format!(
+                "\nFailed to send: {} messages\n",
+                result.failure_count
+            ));
+            for (item, err) in &result.failures {
+                text.push_str(&format!("- {} ({})\n", item, err));
             }
         }
+
+        CallToolResult::text(text)
     }
 
     async fn handle_read_email(&self, args: Value) -> CallToolResult {
@@ -157,36 +902,84 @@ impl ToolHandler {
         #[serde(rename_all = "camelCase")]
         struct Args {
             message_id: String,
+            #[serde(default)]
+            include_headers: bool,
+            #[serde(default)]
+            attachments_only: bool,
+            plaintext: Option<bool>,
         }
 
         let args: Args = match serde_json::from_value(args) {
             Ok(a) => a,
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
+        let plaintext = args.plaintext.unwrap_or(true);
+
+        if args.attachments_only {
+            return match self.gmail_client.list_attachments(&args.message_id).await {
+                Ok(attachments) => CallToolResult::text(format_attachment_list(&attachments)),
+                Err(e) => CallToolResult::error(e.to_string()),
+            };
+        }
 
         match self.gmail_client.read_message(&args.message_id).await {
             Ok(result) => {
                 let mut text = format!(
-                    "Thread ID: {}\nSubject: {}\nFrom: {}\nTo: {}\nDate: {}\n\n",
+                    "Thread ID: {}\nSubject: {}\nFrom: {}\nTo: {}\nDate: {}\n",
                     result.thread_id, result.subject, result.from, result.to, result.date
                 );
 
-                if result.is_html_only {
-                    text.push_str("[Note: This email is HTML-formatted. Plain text version not available.]\n\n");
+                if let Some(ref received) = result.received {
+                    text.push_str(&format!("Received: {}\n", received));
+                }
+                if let Some(size) = result.size_estimate {
+                    text.push_str(&format!("Size: {}\n", format_size(size)));
                 }
+                if !result.label_ids.is_empty() {
+                    text.push_str(&format!("Labels: {}\n", result.label_ids.join(", ")));
+                }
+                text.push('\n');
 
-                text.push_str(&result.body);
+                if result.content_unavailable {
+                    text.push_str("[Note: Gmail returned no content for this message; headers and body are unavailable, showing the snippet only.]\n\n");
+                }
 
-                if !result.attachments.is_empty() {
-                    text.push_str(&format!("\n\nAttachments ({}):\n", result.attachments.len()));
-                    for a in &result.attachments {
+                // HTML-only messages are converted to text unless the caller asked for the markup
+                let raw_html = result.html_body.as_deref().filter(|_| result.is_html_only && !plaintext);
+                if result.is_html_only {
+                    text.push_str(if raw_html.is_some() {
+                        "[Note: This email is HTML-only; showing the raw HTML.]\n\n"
+                    } else {
+                        "[Note: This email is HTML-only; showing text converted from HTML. Pass plaintext: false for the raw HTML.]\n\n"
+                    });
+                }
+
+                if !result.delivery_status.is_empty() {
+                    text.push_str("Delivery status report:\n");
+                    for status in &result.delivery_status {
                         text.push_str(&format!(
-                            "- {} ({}, {}, ID: {})\n",
-                            a.filename,
-                            a.mime_type,
-                            format_size(a.size),
-                            a.id
+                            "- {}: {} ({})",
+                            status.recipient, status.action, status.status
                         ));
+                        if let Some(ref diagnostic) = status.diagnostic_code {
+                            text.push_str(&format!(" - {}", diagnostic));
+                        }
+                        text.push('\n');
+                    }
+                    text.push('\n');
+                }
+
+                text.push_str(raw_html.unwrap_or(&result.body));
+
+                if !result.attachments.is_empty() {
+                    text.push_str("\n\n");
+                    text.push_str(&format_attachment_list(&result.attachments));
+                }
+
+                if args.include_headers {
+                    text.push_str(&format!("\n\nHeaders ({}):\n", result.headers.len()));
+                    for h in &result.headers {
+                        text.push_str(&format!("{}: {}\n", h.name, h.value));
                     }
                 }
 
@@ -196,12 +989,13 @@ impl ToolHandler {
         }
     }
 
-    async fn handle_search_emails(&self, args: Value) -> CallToolResult {
+    async fn handle_get_thread_text(&self, args: Value) -> CallToolResult {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct Args {
-            query: String,
-            max_results: Option<u32>,
+            thread_id: String,
+            #[serde(default)]
+            include_drafts: bool,
         }
 
         let args: Args = match serde_json::from_value(args) {
@@ -209,60 +1003,118 @@ impl ToolHandler {
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
 
-        match self.gmail_client.search_messages(&args.query, args.max_results).await {
-            Ok(results) => {
-                let text = results
-                    .iter()
-                    .map(|r| {
-                        format!(
-                            "ID: {}\nSubject: {}\nFrom: {}\nDate: {}\n",
-                            r.id, r.subject, r.from, r.date
-                        )
-                    })
-                    .collect::<Vec<_>>()
-                    .join("\n");
-
-                CallToolResult::text(text)
-            }
-            Err(e) => CallToolResult::error(e.to_string()),
+        let messages = match self.gmail_client.read_thread(&args.thread_id).await {
+            Ok(messages) => messages,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+        let messages: Vec<ReadMessageResult> = messages
+            .into_iter()
+            .filter(|m| args.include_drafts || !m.label_ids.iter().any(|l| l == "DRAFT"))
+            .collect();
+        if messages.is_empty() {
+            return CallToolResult::error(format!("Thread {} has no messages", args.thread_id));
         }
+
+        CallToolResult::text(thread_transcript(&messages))
     }
 
-    async fn handle_modify_email(&self, args: Value) -> CallToolResult {
+    async fn handle_batch_read_emails(&self, args: Value) -> CallToolResult {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct Args {
-            message_id: String,
-            label_ids: Option<Vec<String>>,
-            add_label_ids: Option<Vec<String>>,
-            remove_label_ids: Option<Vec<String>>,
+            message_ids: Vec<String>,
+            body_max_chars: Option<usize>,
+            #[serde(default)]
+            snippet_only: bool,
         }
 
         let args: Args = match serde_json::from_value(args) {
             Ok(a) => a,
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
+        if args.message_ids.is_empty() {
+            return CallToolResult::error("messageIds must not be empty");
+        }
+        if args.message_ids.len() > MAX_BATCH_READ {
+            return CallToolResult::error(format!(
+                "At most {} messages can be read at once, got {}",
+                MAX_BATCH_READ,
+                args.message_ids.len()
+            ));
+        }
 
-        let add = args.add_label_ids.or(args.label_ids);
+        let permits = Arc::new(Semaphore::new(BATCH_READ_CONCURRENCY));
+        let mut tasks = JoinSet::new();
+        for (index, id) in args.message_ids.iter().cloned().enumerate() {
+            let client = self.gmail_client.clone();
+            let permits = permits.clone();
+            let (snippet_only, body_max_chars) = (args.snippet_only, args.body_max_chars);
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                let entry = if snippet_only {
+                    // Metadata only: no body is downloaded at all
+                    client.get_message_summary(&id).await.map(|message| {
+                        json!({
+                            "id": id,
+                            "threadId": message.thread_id,
+                            "subject": message.subject,
+                            "from": message.from,
+                            "to": message.to,
+                            "date": message.date,
+                            "labelIds": message.label_ids,
+                            "snippet": message.snippet,
+                        })
+                    })
+                } else {
+                    client.read_message(&id).await.map(|message| {
+                        let mut entry = json!({
+                            "id": id,
+                            "threadId": message.thread_id,
+                            "subject": message.subject,
+                            "from": message.from,
+                            "to": message.to,
+                            "date": message.date,
+                            "labelIds": message.label_ids,
+                            "body": message.body,
+                            "attachments": message.attachments.iter().map(|a| &a.filename).collect::<Vec<_>>(),
+                        });
+                        if message.content_unavailable {
+                            entry["contentUnavailable"] = json!(true);
+                        }
+                        if let Some(max_chars) = body_max_chars {
+                            let (body, truncated) = truncate_chars(&message.body, max_chars);
+                            if truncated {
+                                entry["body"] = json!(body);
+                                entry["bodyTruncated"] = json!(true);
+                            }
+                        }
+                        entry
+                    })
+                };
+                (index, entry.unwrap_or_else(|e| json!({"id": id, "error": e.to_string()})))
+            });
+        }
 
-        match self
-            .gmail_client
-            .modify_message(&args.message_id, add, args.remove_label_ids)
-            .await
-        {
-            Ok(_) => CallToolResult::text(format!(
-                "Email {} labels updated successfully",
-                args.message_id
-            )),
+        let mut results = vec![Value::Null; args.message_ids.len()];
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, entry)) => results[index] = entry,
+                Err(e) => return CallToolResult::error(format!("Read task failed: {}", e)),
+            }
+        }
+
+        match serde_json::to_string_pretty(&results) {
+            Ok(text) => CallToolResult::text(text),
             Err(e) => CallToolResult::error(e.to_string()),
         }
     }
 
-    async fn handle_delete_email(&self, args: Value) -> CallToolResult {
+    async fn handle_get_message_raw_header(&self, args: Value) -> CallToolResult {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct Args {
             message_id: String,
+            headers: Option<Vec<String>>,
         }
 
         let args: Args = match serde_json::from_value(args) {
@@ -270,47 +1122,150 @@ impl ToolHandler {
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
 
-        match self.gmail_client.delete_message(&args.message_id).await {
-            Ok(_) => CallToolResult::text(format!(
-                "Email {} deleted successfully",
-                args.message_id
+        let names = match args.headers {
+            Some(names) if !names.is_empty() => names,
+            _ => DELIVERABILITY_HEADERS.iter().map(|h| h.to_string()).collect(),
+        };
+
+        match self.gmail_client.get_message_headers(&args.message_id, &names).await {
+            Ok(headers) if headers.is_empty() => CallToolResult::text(format!(
+                "Message {} has none of the requested headers: {}",
+                args.message_id,
+                names.join(", ")
             )),
+            Ok(headers) => {
+                let mut text = format!("Headers of message {} ({}):\n", args.message_id, headers.len());
+                for h in &headers {
+                    text.push_str(&format!("{}: {}\n", h.name, h.value));
+                }
+                CallToolResult::text(text)
+            }
             Err(e) => CallToolResult::error(e.to_string()),
         }
     }
 
-    async fn handle_list_labels(&self) -> CallToolResult {
-        match self.gmail_client.list_labels().await {
-            Ok(result) => {
-                let mut text = format!(
-                    "Found {} labels ({} system, {} user):\n\n",
-                    result.count.total, result.count.system, result.count.user
-                );
+    async fn handle_search_emails(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            query: String,
+            max_results: Option<u32>,
+            #[serde(default)]
+            snippet_only: bool,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        let requested = args.max_results.unwrap_or(self.default_max_results);
+        let max_results = requested.min(MAX_SEARCH_RESULTS);
+        let clamp_note = if requested > max_results {
+            format!(
+                "Note: maxResults {} exceeds the limit of {}; returning at most {} results.\n\n",
+                requested, MAX_SEARCH_RESULTS, MAX_SEARCH_RESULTS
+            )
+        } else {
+            String::new()
+        };
 
-                text.push_str("System Labels:\n");
-                for label in &result.system {
-                    text.push_str(&format!("ID: {}\nName: {}\n\n", label.id, label.name));
+        if args.snippet_only {
+            return match self
+                .gmail_client
+                .search_message_snippets(&args.query, Some(max_results))
+                .await
+            {
+                Ok(results) if results.is_empty() => CallToolResult::text(format!(
+                    "{}No messages matched query: {}\nResult size estimate: 0",
+                    clamp_note, args.query
+                )),
+                Ok(results) => {
+                    let text = results
+                        .iter()
+                        .map(|r| {
+                            let mut entry = format!("ID: {}\nThread ID: {}\n", r.id, r.thread_id);
+                            if let Some(ref received) = r.received {
+                                entry.push_str(&format!("Received: {}\n", received));
+                            }
+                            entry.push_str(&format!("Snippet: {}\n", r.snippet));
+                            entry
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    CallToolResult::text(clamp_note + &text)
                 }
+                Err(e) => CallToolResult::error(e.to_string()),
+            };
+        }
 
-                text.push_str("\nUser Labels:\n");
-                for label in &result.user {
-                    text.push_str(&format!("ID: {}\nName: {}\n\n", label.id, label.name));
+        match self.gmail_client.search_messages(&args.query, Some(max_results)).await {
+            Ok(results) => {
+                if results.is_empty() {
+                    return CallToolResult::text(format!(
+                        "{}No messages matched query: {}\nResult size estimate: 0",
+                        clamp_note, args.query
+                    ));
                 }
 
-                CallToolResult::text(text)
+                let text = results
+                    .iter()
+                    .map(format_search_result)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                CallToolResult::text(clamp_note + &text)
             }
             Err(e) => CallToolResult::error(e.to_string()),
         }
     }
 
-    async fn handle_batch_modify(&self, args: Value) -> CallToolResult {
+    async fn handle_save_query(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        struct Args {
+            name: String,
+            query: String,
+            #[serde(default)]
+            overwrite: bool,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        match self.saved_queries.save(&args.name, &args.query, args.overwrite).await {
+            Ok(replaced) => CallToolResult::text(format!(
+                "Query '{}' {}: {}",
+                args.name.trim(),
+                if replaced { "updated" } else { "saved" },
+                args.query.trim()
+            )),
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_list_saved_queries(&self) -> CallToolResult {
+        let queries = self.saved_queries.list().await;
+        if queries.is_empty() {
+            return CallToolResult::text("No saved queries. Use save_query to add one.");
+        }
+
+        let mut text = format!("Saved queries ({}):\n", queries.len());
+        for (name, query) in &queries {
+            text.push_str(&format!("- {}: {}\n", name, query));
+        }
+        CallToolResult::text(text)
+    }
+
+    async fn handle_run_saved_query(&self, args: Value) -> CallToolResult {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct Args {
-            message_ids: Vec<String>,
-            add_label_ids: Option<Vec<String>>,
-            remove_label_ids: Option<Vec<String>>,
-            batch_size: Option<usize>,
+            name: String,
+            max_results: Option<u32>,
+            #[serde(default)]
+            snippet_only: bool,
         }
 
         let args: Args = match serde_json::from_value(args) {
@@ -318,44 +1273,99 @@ impl ToolHandler {
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
 
-        match self
+        let Some(query) = self.saved_queries.get(&args.name).await else {
+            return CallToolResult::error(format!(
+                "No saved query named '{}'. Use list_saved_queries to see the saved names",
+                args.name
+            ));
+        };
+
+        self.handle_search_emails(json!({
+            "query": query,
+            "maxResults": args.max_results,
+            "snippetOnly": args.snippet_only,
+        }))
+        .await
+    }
+
+    async fn handle_get_new_messages(&self) -> CallToolResult {
+        let mut last_seen = self.last_seen.lock().await;
+        let checked_at = time::OffsetDateTime::now_utc().unix_timestamp();
+
+        let Some(previous) = last_seen.marker().cloned() else {
+            let history_id = match self.gmail_client.get_profile().await {
+                Ok(profile) => profile.history_id.unwrap_or_default(),
+                Err(e) => return CallToolResult::error(e.to_string()),
+            };
+            if let Err(e) = last_seen.advance(Marker { history_id, checked_at }).await {
+                return CallToolResult::error(format!("Failed to save the last-seen marker: {}", e));
+            }
+            return CallToolResult::text(
+                "No earlier check to compare against; started watching the inbox from now. \
+                 Call get_new_messages again to list mail that arrives from here on.",
+            );
+        };
+
+        // History is the precise source; once Gmail has expired it, fall back
+        // to the inbox messages received since the last check's time
+        let (message_ids, history_id) = match self
             .gmail_client
-            .batch_modify_messages(
-                &args.message_ids,
-                args.add_label_ids,
-                args.remove_label_ids,
-                args.batch_size.unwrap_or(50),
-            )
+            .list_history(&previous.history_id, "INBOX")
             .await
         {
-            Ok(result) => {
-                let mut text = format!(
-                    "Batch label modification complete.\nSuccessfully processed: {} messages\n",
-                    result.success_count
-                );
-
-                if result.failure_count > 0 {
-                    text.push_str(&format!(
-                        "Failed to process: {} messages\n\nFailed message IDs:\n",
-                        result.failure_count
-                    ));
-                    for (id, err) in &result.failures {
-                        text.push_str(&format!("- {}... ({})\n", &id[..16.min(id.len())], err));
-                    }
+            Ok(Some(changes)) => (changes.message_ids, changes.history_id),
+            Ok(None) => {
+                let history_id = match self.gmail_client.get_profile().await {
+                    Ok(profile) => profile.history_id.unwrap_or_default(),
+                    Err(e) => return CallToolResult::error(e.to_string()),
+                };
+                let query = format!("in:inbox after:{}", previous.checked_at);
+                match self.gmail_client.list_all_message_ids(&query).await {
+                    // Search returns newest first; report oldest first like history
+                    Ok(ids) => (ids.into_iter().rev().collect(), history_id),
+                    Err(e) => return CallToolResult::error(e.to_string()),
                 }
+            }
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
 
-                CallToolResult::text(text)
+        let mut entries = Vec::new();
+        for id in message_ids.iter().take(MAX_NEW_MESSAGES) {
+            match self.gmail_client.get_message_summary(id).await {
+                Ok(m) => entries.push(format!(
+                    "ID: {}\nThread ID: {}\nSubject: {}\nFrom: {}\nDate: {}\n",
+                    m.id, m.thread_id, m.subject, m.from, m.date
+                )),
+                // Deleted again since it arrived
+                Err(GmailMcpError::Gmail(GmailApiError::MessageNotFound { .. })) => {}
+                Err(e) => return CallToolResult::error(e.to_string()),
             }
-            Err(e) => CallToolResult::error(e.to_string()),
         }
+
+        // Only advanced once the messages are in hand, so a failed call is retried from the same point
+        if let Err(e) = last_seen.advance(Marker { history_id, checked_at }).await {
+            return CallToolResult::error(format!("Failed to save the last-seen marker: {}", e));
+        }
+
+        if entries.is_empty() {
+            return CallToolResult::text("No new messages since the last check.");
+        }
+        let mut text = format!("{} new messages since the last check:\n\n", entries.len());
+        text.push_str(&entries.join("\n"));
+        if message_ids.len() > MAX_NEW_MESSAGES {
+            text.push_str(&format!(
+                "\n... and {} more: {}\n",
+                message_ids.len() - MAX_NEW_MESSAGES,
+                message_ids[MAX_NEW_MESSAGES..].join(", ")
+            ));
+        }
+        CallToolResult::text(text)
     }
 
-    async fn handle_batch_delete(&self, args: Value) -> CallToolResult {
+    async fn handle_verify_recipient(&self, args: Value) -> CallToolResult {
         #[derive(Deserialize)]
-        #[serde(rename_all = "camelCase")]
         struct Args {
-            message_ids: Vec<String>,
-            batch_size: Option<usize>,
+            emails: Vec<String>,
         }
 
         let args: Args = match serde_json::from_value(args) {
@@ -363,40 +1373,49 @@ impl ToolHandler {
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
 
-        match self
-            .gmail_client
-            .batch_delete_messages(&args.message_ids, args.batch_size.unwrap_or(50))
-            .await
-        {
-            Ok(result) => {
-                let mut text = format!(
-                    "Batch delete operation complete.\nSuccessfully deleted: {} messages\n",
-                    result.success_count
-                );
+        if args.emails.is_empty() {
+            return CallToolResult::error("emails must contain at least one address");
+        }
 
-                if result.failure_count > 0 {
-                    text.push_str(&format!(
-                        "Failed to delete: {} messages\n\nFailed message IDs:\n",
-                        result.failure_count
-                    ));
-                    for (id, err) in &result.failures {
-                        text.push_str(&format!("- {}... ({})\n", &id[..16.min(id.len())], err));
-                    }
-                }
+        let mut lines = Vec::new();
+        for email in &args.emails {
+            let email = email.trim();
+            if !validate_email(email) {
+                lines.push(format!("{}: not a valid email address", email));
+                continue;
+            }
 
-                CallToolResult::text(text)
+            let query = QueryBuilder::new()
+                .any_of([QueryBuilder::new().from(email), QueryBuilder::new().to(email)])
+                .build();
+            match self.gmail_client.search_messages(&query, Some(1)).await {
+                Ok(results) => match results.first() {
+                    Some(latest) => lines.push(format!(
+                        "{}: prior correspondence found (most recent: {}, \"{}\")",
+                        email,
+                        latest.received.as_deref().unwrap_or(&latest.date),
+                        latest.subject
+                    )),
+                    None => lines.push(format!(
+                        "{}: no prior correspondence found; double-check the address before sending",
+                        email
+                    )),
+                },
+                Err(e) => return CallToolResult::error(e.to_string()),
             }
-            Err(e) => CallToolResult::error(e.to_string()),
         }
+
+        CallToolResult::text(lines.join("\n"))
     }
 
-    async fn handle_create_label(&self, args: Value) -> CallToolResult {
+    async fn handle_modify_email(&self, args: Value) -> CallToolResult {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct Args {
-            name: String,
-            message_list_visibility: Option<String>,
-            label_list_visibility: Option<String>,
+            message_id: String,
+            label_ids: Option<Vec<String>>,
+            add_label_ids: Option<Vec<String>>,
+            remove_label_ids: Option<Vec<String>>,
         }
 
         let args: Args = match serde_json::from_value(args) {
@@ -404,34 +1423,41 @@ impl ToolHandler {
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
 
+        let add = match self
+            .gmail_client
+            .resolve_label_ids(args.add_label_ids.or(args.label_ids))
+            .await
+        {
+            Ok(ids) => ids,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+        let remove = match self.gmail_client.resolve_label_ids(args.remove_label_ids).await {
+            Ok(ids) => ids,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
         match self
             .gmail_client
-            .create_label(
-                &args.name,
-                args.message_list_visibility.as_deref(),
-                args.label_list_visibility.as_deref(),
-            )
+            .modify_message(&args.message_id, add, remove)
             .await
         {
-            Ok(label) => CallToolResult::text(format!(
-                "Label created successfully:\nID: {}\nName: {}\nType: {}",
-                label.id,
-                label.name,
-                label.label_type.unwrap_or_default()
+            Ok(_) => CallToolResult::text(format!(
+                "Email {} labels updated successfully",
+                args.message_id
             )),
             Err(e) => CallToolResult::error(e.to_string()),
         }
     }
 
-    async fn handle_update_label(&self, args: Value) -> CallToolResult {
+    async fn handle_move_email(&self, args: Value) -> CallToolResult {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct Args {
+            message_id: String,
             #[serde(alias = "labelId")]
-            id: String,
-            name: Option<String>,
-            message_list_visibility: Option<String>,
-            label_list_visibility: Option<String>,
+            label: String,
+            #[serde(default)]
+            keep_in_inbox: bool,
         }
 
         let args: Args = match serde_json::from_value(args) {
@@ -439,28 +1465,38 @@ impl ToolHandler {
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
 
-        let updates = UpdateLabelRequest {
-            name: args.name,
-            message_list_visibility: args.message_list_visibility,
-            label_list_visibility: args.label_list_visibility,
+        let add = match self
+            .gmail_client
+            .resolve_label_ids(Some(vec![args.label.clone()]))
+            .await
+        {
+            Ok(ids) => ids,
+            Err(e) => return CallToolResult::error(e.to_string()),
         };
+        let remove = (!args.keep_in_inbox).then(|| vec!["INBOX".to_string()]);
 
-        match self.gmail_client.update_label(&args.id, updates).await {
-            Ok(label) => CallToolResult::text(format!(
-                "Label updated successfully:\nID: {}\nName: {}\nType: {}",
-                label.id,
-                label.name,
-                label.label_type.unwrap_or_default()
+        match self
+            .gmail_client
+            .modify_message(&args.message_id, add, remove)
+            .await
+        {
+            Ok(_) if args.keep_in_inbox => CallToolResult::text(format!(
+                "Email {} labeled {} (kept in inbox)",
+                args.message_id, args.label
+            )),
+            Ok(_) => CallToolResult::text(format!(
+                "Email {} moved to {}",
+                args.message_id, args.label
             )),
             Err(e) => CallToolResult::error(e.to_string()),
         }
     }
 
-    async fn handle_delete_label(&self, args: Value) -> CallToolResult {
+    async fn handle_get_importance(&self, args: Value) -> CallToolResult {
         #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
         struct Args {
-            #[serde(alias = "labelId")]
-            id: String,
+            message_id: String,
         }
 
         let args: Args = match serde_json::from_value(args) {
@@ -468,19 +1504,25 @@ impl ToolHandler {
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
 
-        match self.gmail_client.delete_label(&args.id).await {
-            Ok(_) => CallToolResult::text(format!("Label {} deleted successfully", args.id)),
+        match self.gmail_client.get_label_ids(&args.message_id).await {
+            Ok(label_ids) => {
+                let important = label_ids.iter().any(|l| l == "IMPORTANT");
+                CallToolResult::text(format!(
+                    "Email {} is {}marked important",
+                    args.message_id,
+                    if important { "" } else { "not " }
+                ))
+            }
             Err(e) => CallToolResult::error(e.to_string()),
         }
     }
 
-    async fn handle_get_or_create_label(&self, args: Value) -> CallToolResult {
+    async fn handle_set_importance(&self, args: Value) -> CallToolResult {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct Args {
-            name: String,
-            message_list_visibility: Option<String>,
-            label_list_visibility: Option<String>,
+            message_id: String,
+            important: bool,
         }
 
         let args: Args = match serde_json::from_value(args) {
@@ -488,53 +1530,55 @@ impl ToolHandler {
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
 
-        match self
-            .gmail_client
-            .get_or_create_label(
-                &args.name,
-                args.message_list_visibility.as_deref(),
-                args.label_list_visibility.as_deref(),
-            )
-            .await
-        {
-            Ok(label) => CallToolResult::text(format!(
-                "Label:\nID: {}\nName: {}\nType: {}",
-                label.id,
-                label.name,
-                label.label_type.unwrap_or_default()
+        let label = Some(vec!["IMPORTANT".to_string()]);
+        let (add, remove) = if args.important { (label, None) } else { (None, label) };
+        match self.gmail_client.modify_message(&args.message_id, add, remove).await {
+            Ok(_) => CallToolResult::text(format!(
+                "Email {} marked {}",
+                args.message_id,
+                if args.important { "important" } else { "not important" }
             )),
             Err(e) => CallToolResult::error(e.to_string()),
         }
     }
 
-    async fn handle_create_filter(&self, args: Value) -> CallToolResult {
+    async fn handle_delete_email(&self, args: Value) -> CallToolResult {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct Args {
-            criteria: CriteriaArgs,
-            action: ActionArgs,
+            message_id: String,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        match self.gmail_client.delete_message(&args.message_id).await {
+            Ok(_) => CallToolResult::text(format!(
+                "Email {} deleted successfully",
+                args.message_id
+            )),
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_list_labels(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize, Default, PartialEq)]
+        #[serde(rename_all = "lowercase")]
+        enum LabelType {
+            System,
+            User,
+            #[default]
+            All,
         }
 
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
-        struct CriteriaArgs {
-            from: Option<String>,
-            to: Option<String>,
-            subject: Option<String>,
-            query: Option<String>,
-            negated_query: Option<String>,
-            has_attachment: Option<bool>,
-            exclude_chats: Option<bool>,
-            size: Option<i64>,
-            size_comparison: Option<String>,
-        }
-
-        #[derive(Deserialize)]
-        #[serde(rename_all = "camelCase")]
-        struct ActionArgs {
-            add_label_ids: Option<Vec<String>>,
-            remove_label_ids: Option<Vec<String>>,
-            forward: Option<String>,
+        struct Args {
+            #[serde(default, rename = "type")]
+            label_type: LabelType,
+            include_counts: Option<bool>,
         }
 
         let args: Args = match serde_json::from_value(args) {
@@ -542,73 +1586,39 @@ impl ToolHandler {
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
 
-        let criteria = FilterCriteria {
-            from: args.criteria.from,
-            to: args.criteria.to,
-            subject: args.criteria.subject,
-            query: args.criteria.query,
-            negated_query: args.criteria.negated_query,
-            has_attachment: args.criteria.has_attachment,
-            exclude_chats: args.criteria.exclude_chats,
-            size: args.criteria.size,
-            size_comparison: args.criteria.size_comparison.map(|s| match s.as_str() {
-                "smaller" => SizeComparison::Smaller,
-                "larger" => SizeComparison::Larger,
-                _ => SizeComparison::Unspecified,
-            }),
-        };
-
-        let action = FilterAction {
-            add_label_ids: args.action.add_label_ids,
-            remove_label_ids: args.action.remove_label_ids,
-            forward: args.action.forward,
+        let result = if args.include_counts.unwrap_or(false) {
+            self.gmail_client.list_labels_with_counts().await
+        } else {
+            self.gmail_client.list_labels().await
         };
 
-        match self.gmail_client.create_filter(criteria, action).await {
-            Ok(filter) => CallToolResult::text(format!(
-                "Filter created successfully:\nID: {}",
-                filter.id.unwrap_or_default()
-            )),
-            Err(e) => CallToolResult::error(e.to_string()),
-        }
-    }
-
-    async fn handle_list_filters(&self) -> CallToolResult {
-        match self.gmail_client.list_filters().await {
+        match result {
             Ok(result) => {
-                if result.filters.is_empty() {
-                    return CallToolResult::text("No filters found.");
-                }
-
-                let mut text = format!("Found {} filters:\n\n", result.count);
-
-                for filter in &result.filters {
-                    text.push_str(&format!("ID: {}\n", filter.id.as_deref().unwrap_or("")));
-
-                    // Format criteria
-                    let criteria_parts: Vec<String> = [
-                        filter.criteria.from.as_ref().map(|v| format!("from: {}", v)),
-                        filter.criteria.to.as_ref().map(|v| format!("to: {}", v)),
-                        filter.criteria.subject.as_ref().map(|v| format!("subject: {}", v)),
-                        filter.criteria.query.as_ref().map(|v| format!("query: {}", v)),
-                    ]
-                    .into_iter()
-                    .flatten()
-                    .collect();
+                let mut text = match args.label_type {
+                    LabelType::All => format!(
+                        "Found {} labels ({} system, {} user):\n\n",
+                        result.count.total, result.count.system, result.count.user
+                    ),
+                    LabelType::System => format!("Found {} system labels:\n\n", result.count.system),
+                    LabelType::User => format!("Found {} user labels:\n\n", result.count.user),
+                };
 
-                    text.push_str(&format!("Criteria: {}\n", criteria_parts.join(", ")));
+                if args.label_type != LabelType::User {
+                    text.push_str("System Labels:\n");
+                    for label in &result.system {
+                        text.push_str(&format_label_line(label));
+                    }
+                }
 
-                    // Format actions
-                    let action_parts: Vec<String> = [
-                        filter.action.add_label_ids.as_ref().map(|v| format!("addLabelIds: {}", v.join(", "))),
-                        filter.action.remove_label_ids.as_ref().map(|v| format!("removeLabelIds: {}", v.join(", "))),
-                        filter.action.forward.as_ref().map(|v| format!("forward: {}", v)),
-                    ]
-                    .into_iter()
-                    .flatten()
-                    .collect();
+                if args.label_type == LabelType::All {
+                    text.push('\n');
+                }
 
-                    text.push_str(&format!("Actions: {}\n\n", action_parts.join(", ")));
+                if args.label_type != LabelType::System {
+                    text.push_str("User Labels:\n");
+                    for label in &result.user {
+                        text.push_str(&format_label_line(label));
+                    }
                 }
 
                 CallToolResult::text(text)
@@ -617,11 +1627,14 @@ impl ToolHandler {
         }
     }
 
-    async fn handle_get_filter(&self, args: Value) -> CallToolResult {
+    async fn handle_batch_modify(&self, args: Value, ctx: &ToolContext) -> CallToolResult {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct Args {
-            filter_id: String,
+            message_ids: Vec<String>,
+            add_label_ids: Option<Vec<String>>,
+            remove_label_ids: Option<Vec<String>>,
+            batch_size: Option<usize>,
         }
 
         let args: Args = match serde_json::from_value(args) {
@@ -629,20 +1642,43 @@ impl ToolHandler {
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
 
-        match self.gmail_client.get_filter(&args.filter_id).await {
-            Ok(filter) => {
-                let mut text = format!("Filter details:\nID: {}\n", filter.id.as_deref().unwrap_or(""));
-
-                let criteria_parts: Vec<String> = [
-                    filter.criteria.from.as_ref().map(|v| format!("from: {}", v)),
-                    filter.criteria.to.as_ref().map(|v| format!("to: {}", v)),
-                    filter.criteria.subject.as_ref().map(|v| format!("subject: {}", v)),
-                ]
-                .into_iter()
-                .flatten()
-                .collect();
+        let control = ctx.batch_control();
+
+        match self
+            .gmail_client
+            .batch_modify_messages(
+                &args.message_ids,
+                args.add_label_ids,
+                args.remove_label_ids,
+                args.batch_size.unwrap_or(50),
+                &control,
+            )
+            .await
+        {
+            Ok(result) => {
+                let mut text = if result.cancelled {
+                    format!(
+                        "Batch label modification cancelled.\nProcessed before cancellation: {} of {} messages\n",
+                        result.success_count + result.failure_count,
+                        args.message_ids.len()
+                    )
+                } else {
+                    "Batch label modification complete.\n".to_string()
+                };
+                text.push_str(&format!(
+                    "Successfully processed: {} messages\n",
+                    result.success_count
+                ));
 
-                text.push_str(&format!("Criteria: {}\n", criteria_parts.join(", ")));
+                if result.failure_count > 0 {
+                    text.push_str(&format!(
+                        "Failed to process: {} messages\n\nFailed message IDs:\n",
+                        result.failure_count
+                    ));
+                    for (id, err) in &result.failures {
+                        text.push_str(&format!("- {}... ({})\n", &id[..16.min(id.len())], err));
+                    }
+                }
 
                 CallToolResult::text(text)
             }
@@ -650,11 +1686,16 @@ impl ToolHandler {
         }
     }
 
-    async fn handle_delete_filter(&self, args: Value) -> CallToolResult {
+    async fn handle_batch_delete(&self, args: Value, ctx: &ToolContext) -> CallToolResult {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct Args {
-            filter_id: String,
+            message_ids: Vec<String>,
+            batch_size: Option<usize>,
+            #[serde(default)]
+            permanent: bool,
+            #[serde(default)]
+            confirm: bool,
         }
 
         let args: Args = match serde_json::from_value(args) {
@@ -662,187 +1703,1621 @@ impl ToolHandler {
             Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
         };
 
-        match self.gmail_client.delete_filter(&args.filter_id).await {
-            Ok(_) => CallToolResult::text(format!("Filter {} deleted successfully", args.filter_id)),
-            Err(e) => CallToolResult::error(e.to_string()),
-        }
-    }
-
-    async fn handle_create_filter_template(&self, args: Value) -> CallToolResult {
-        #[derive(Deserialize, Default)]
-        #[serde(rename_all = "camelCase")]
-        struct TemplateParams {
-            sender_email: Option<String>,
-            subject_text: Option<String>,
-            search_text: Option<String>,
-            list_identifier: Option<String>,
-            size_in_bytes: Option<i64>,
-            label_ids: Option<Vec<String>>,
-            archive: Option<bool>,
-            mark_as_read: Option<bool>,
-            mark_important: Option<bool>,
-        }
-
-        // Accept both nested `parameters` object and flat parameters for better UX
-        #[derive(Deserialize)]
-        #[serde(rename_all = "camelCase")]
-        struct Args {
-            #[serde(alias = "templateName")]
-            template: String,
-            // Nested parameters object (preferred)
-            #[serde(default)]
-            parameters: Option<TemplateParams>,
-            // Flat parameters (for convenience)
-            sender_email: Option<String>,
-            subject_text: Option<String>,
-            search_text: Option<String>,
-            list_identifier: Option<String>,
-            size_in_bytes: Option<i64>,
-            label_ids: Option<Vec<String>>,
-            #[serde(alias = "labelId")]
-            label_id: Option<String>,
-            archive: Option<bool>,
-            mark_as_read: Option<bool>,
-            mark_important: Option<bool>,
+        let verb = if args.permanent { "permanently delete" } else { "move to trash" };
+        if let Err(e) = self.require_bulk_confirm(args.message_ids.len(), args.confirm, verb) {
+            return e;
         }
 
-        let args: Args = match serde_json::from_value(args) {
-            Ok(a) => a,
-            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
-        };
+        let control = ctx.batch_control();
 
-        // Merge flat params with nested params (flat params take precedence)
-        let nested = args.parameters.unwrap_or_default();
-        let params = TemplateParams {
-            sender_email: args.sender_email.or(nested.sender_email),
-            subject_text: args.subject_text.or(nested.subject_text),
-            search_text: args.search_text.or(nested.search_text),
-            list_identifier: args.list_identifier.or(nested.list_identifier),
-            size_in_bytes: args.size_in_bytes.or(nested.size_in_bytes),
-            // Handle both labelIds array and single labelId
-            label_ids: args.label_ids.or(nested.label_ids).or_else(|| {
-                args.label_id.map(|id| vec![id])
-            }),
-            archive: args.archive.or(nested.archive),
-            mark_as_read: args.mark_as_read.or(nested.mark_as_read),
-            mark_important: args.mark_important.or(nested.mark_important),
+        let outcome = if args.permanent {
+            if let Err(e) = self.gmail_client.check_scope(scopes::FULL_ACCESS).await {
+                return CallToolResult::error(format!(
+                    "{}. Or omit permanent to move the messages to trash instead",
+                    e
+                ));
+            }
+            self.gmail_client
+                .batch_delete_messages_permanently(
+                    &args.message_ids,
+                    args.batch_size.unwrap_or(MAX_BATCH_DELETE_IDS),
+                    &control,
+                )
+                .await
+        } else {
+            self.gmail_client
+                .batch_delete_messages(&args.message_ids, args.batch_size.unwrap_or(50), &control)
+                .await
         };
+        let action = if args.permanent { "permanently deleted" } else { "moved to trash" };
 
-        let (criteria, action) = match args.template.as_str() {
-            "fromSender" => {
-                let email = match params.sender_email {
-                    Some(e) => e,
-                    None => return CallToolResult::error("senderEmail is required for fromSender template"),
-                };
-                FilterTemplates::from_sender(&email, params.label_ids, params.archive.unwrap_or(false))
-            }
-            "withSubject" => {
-                let subject = match params.subject_text {
-                    Some(s) => s,
-                    None => return CallToolResult::error("subjectText is required for withSubject template"),
-                };
-                FilterTemplates::with_subject(&subject, params.label_ids, params.mark_as_read.unwrap_or(false))
-            }
-            "withAttachments" => {
-                FilterTemplates::with_attachments(params.label_ids)
-            }
-            "largeEmails" => {
-                let size = match params.size_in_bytes {
-                    Some(s) => s,
-                    None => return CallToolResult::error("sizeInBytes is required for largeEmails template"),
-                };
-                FilterTemplates::large_emails(size, params.label_ids)
-            }
-            "containingText" => {
-                let text = match params.search_text {
-                    Some(t) => t,
-                    None => return CallToolResult::error("searchText is required for containingText template"),
-                };
-                FilterTemplates::containing_text(&text, params.label_ids, params.mark_important.unwrap_or(false))
-            }
-            "mailingList" => {
-                let list = match params.list_identifier {
-                    Some(l) => l,
-                    None => return CallToolResult::error("listIdentifier is required for mailingList template"),
+        match outcome {
+            Ok(result) => {
+                let mut text = if result.cancelled {
+                    format!(
+                        "Batch delete operation cancelled.\nProcessed before cancellation: {} of {} messages\n",
+                        result.success_count + result.failure_count,
+                        args.message_ids.len()
+                    )
+                } else {
+                    "Batch delete operation complete.\n".to_string()
                 };
-                FilterTemplates::mailing_list(&list, params.label_ids, params.archive.unwrap_or(true))
-            }
-            _ => return CallToolResult::error(format!("Unknown template: {}", args.template)),
-        };
+                text.push_str(&format!(
+                    "Successfully {}: {} messages\n",
+                    action, result.success_count
+                ));
 
-        match self.gmail_client.create_filter(criteria, action).await {
-            Ok(filter) => CallToolResult::text(format!(
-                "Filter created from template '{}':\nID: {}",
-                args.template,
-                filter.id.unwrap_or_default()
-            )),
+                if result.failure_count > 0 {
+                    text.push_str(&format!(
+                        "Failed to delete: {} messages\n\nFailed message IDs:\n",
+                        result.failure_count
+                    ));
+                    for (id, err) in &result.failures {
+                        text.push_str(&format!("- {}... ({})\n", &id[..16.min(id.len())], err));
+                    }
+                }
+
+                CallToolResult::text(text)
+            }
             Err(e) => CallToolResult::error(e.to_string()),
         }
     }
 
-    async fn handle_download_attachment(&self, args: Value) -> CallToolResult {
-        #[derive(Deserialize)]
-        #[serde(rename_all = "camelCase")]
-        struct Args {
-            message_id: String,
-            attachment_id: String,
-            filename: Option<String>,
-            save_path: Option<String>,
+    /// Check that a bulk operation over more than `confirm_threshold` messages carries `confirm: true`
+    fn require_bulk_confirm(&self, count: usize, confirm: bool, verb: &str) -> std::result::Result<(), CallToolResult> {
+        if confirm || count <= self.confirm_threshold {
+            return Ok(());
         }
+        Err(CallToolResult::error(format!(
+            "This would {} {} messages, more than the confirmation threshold of {}. Set confirm: true to proceed.",
+            verb, count, self.confirm_threshold
+        )))
+    }
 
-        let args: Args = match serde_json::from_value(args) {
-            Ok(a) => a,
-            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
-        };
+    async fn handle_empty_trash(&self, args: Value, ctx: &ToolContext) -> CallToolResult {
+        if let Err(e) = require_confirm(args, "empty_trash permanently deletes every message in Trash") {
+            return e;
+        }
 
-        // Get attachment data
-        let attachment = match self
-            .gmail_client
-            .get_attachment(&args.message_id, &args.attachment_id)
-            .await
-        {
-            Ok(a) => a,
-            Err(e) => return CallToolResult::error(e.to_string()),
-        };
+        // Listed and deleted a batch at a time so a huge Trash is never held in memory
+        let query = QueryBuilder::new().in_folder("trash").build();
+        let control = ctx.batch_control();
+        let mut deleted = 0;
+        let mut failed: Option<(usize, String)> = None;
+        let mut cancelled = false;
 
-        // Decode the data
-        let data = match decode_base64url(&attachment.data) {
-            Ok(d) => d,
-            Err(e) => return CallToolResult::error(format!("Failed to decode attachment: {}", e)),
-        };
+        loop {
+            let ids = match self.gmail_client.list_message_ids(&query, MAX_BATCH_DELETE_IDS).await {
+                Ok(ids) => ids,
+                Err(e) if deleted == 0 => return CallToolResult::error(e.to_string()),
+                Err(e) => {
+                    failed = Some((0, e.to_string()));
+                    break;
+                }
+            };
+            if ids.is_empty() {
+                break;
+            }
 
-        // Determine filename
-        let filename = args.filename.unwrap_or_else(|| format!("attachment-{}", args.attachment_id));
+            // Progress counts across batches; the total is only known once Trash is empty
+            let done_before = deleted;
+            let batch_control = BatchControl {
+                cancel: control.cancel.clone(),
+                progress: control.progress.clone().map(|progress| {
+                    Arc::new(move |processed: usize, total: usize| {
+                        progress(done_before + processed, done_before + total)
+                    }) as ProgressCallback
+                }),
+            };
 
-        // Determine save path
-        let save_dir = args.save_path.unwrap_or_else(|| ".".to_string());
-        let full_path = std::path::Path::new(&save_dir).join(&filename);
+            let result = match self
+                .gmail_client
+                .batch_delete_messages_permanently(&ids, MAX_BATCH_DELETE_IDS, &batch_control)
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => return CallToolResult::error(e.to_string()),
+            };
+            deleted += result.success_count;
 
-        // Ensure directory exists
-        if let Some(parent) = full_path.parent() {
-            if !parent.exists() {
-                if let Err(e) = std::fs::create_dir_all(parent) {
-                    return CallToolResult::error(format!("Failed to create directory: {}", e));
-                }
+            // Stop rather than list the same undeletable messages again
+            if let Some((_, reason)) = result.failures.first() {
+                failed = Some((result.failure_count, reason.clone()));
+                break;
+            }
+            if result.cancelled {
+                cancelled = true;
+                break;
             }
         }
 
-        // Write file
-        if let Err(e) = std::fs::write(&full_path, &data) {
-            return CallToolResult::error(format!("Failed to write file: {}", e));
+        if deleted == 0 && failed.is_none() && !cancelled {
+            return CallToolResult::text("Trash is already empty.");
         }
 
+        let mut text = if cancelled {
+            "Emptying trash cancelled.\n".to_string()
+        } else if failed.is_some() && deleted == 0 {
+            "Could not empty trash.\n".to_string()
+        } else if failed.is_some() {
+            "Trash partly emptied.\n".to_string()
+        } else {
+            "Trash emptied.\n".to_string()
+        };
+        text.push_str(&format!("Permanently deleted: {} messages\n", deleted));
+        match &failed {
+            Some((0, reason)) => text.push_str(&format!("Stopped listing Trash: {}\n", reason)),
+            Some((count, reason)) => text.push_str(&format!("Failed: {} messages ({})\n", count, reason)),
+            None => {}
+        }
+        if deleted == 0 && failed.is_some() {
+            CallToolResult::error(text)
+        } else {
+            CallToolResult::text(text)
+        }
+    }
+
+    async fn handle_empty_spam(&self, args: Value, ctx: &ToolContext) -> CallToolResult {
+        if let Err(e) = require_confirm(args, "empty_spam moves every message in Spam to Trash") {
+            return e;
+        }
+
+        let ids = match self.gmail_client.list_all_message_ids(&QueryBuilder::new().in_folder("spam").build()).await {
+            Ok(ids) => ids,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
+        if ids.is_empty() {
+            return CallToolResult::text("Spam is already empty.");
+        }
+
+        let control = ctx.batch_control();
+
+        match self.gmail_client.batch_delete_messages(&ids, 50, &control).await {
+            Ok(result) => {
+                let mut text = if result.cancelled {
+                    format!(
+                        "Emptying spam cancelled.\nProcessed before cancellation: {} of {} messages\n",
+                        result.success_count + result.failure_count,
+                        ids.len()
+                    )
+                } else {
+                    "Spam emptied.\n".to_string()
+                };
+                text.push_str(&format!("Moved to trash: {} messages\n", result.success_count));
+                if result.failure_count > 0 {
+                    text.push_str(&format!("Failed: {} messages\n", result.failure_count));
+                }
+                CallToolResult::text(text)
+            }
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_create_label(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            name: String,
+            message_list_visibility: Option<String>,
+            label_list_visibility: Option<String>,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        match self
+            .gmail_client
+            .create_label(
+                &args.name,
+                args.message_list_visibility.as_deref(),
+                args.label_list_visibility.as_deref(),
+            )
+            .await
+        {
+            Ok(label) => CallToolResult::text(format!(
+                "Label created successfully:\nID: {}\nName: {}\nType: {}",
+                label.id,
+                label.name,
+                label.label_type.unwrap_or_default()
+            )),
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_update_label(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            #[serde(alias = "labelId")]
+            id: String,
+            name: Option<String>,
+            message_list_visibility: Option<String>,
+            label_list_visibility: Option<String>,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        let updates = UpdateLabelRequest {
+            name: args.name,
+            message_list_visibility: args.message_list_visibility,
+            label_list_visibility: args.label_list_visibility,
+            color: None,
+        };
+
+        match self.gmail_client.update_label(&args.id, updates).await {
+            Ok(label) => CallToolResult::text(format!(
+                "Label updated successfully:\nID: {}\nName: {}\nType: {}",
+                label.id,
+                label.name,
+                label.label_type.unwrap_or_default()
+            )),
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_delete_label(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        struct Args {
+            #[serde(alias = "labelId")]
+            id: String,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        match self.gmail_client.delete_label(&args.id).await {
+            Ok(_) => CallToolResult::text(format!("Label {} deleted successfully", args.id)),
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_get_label(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        struct Args {
+            #[serde(alias = "labelId", alias = "name")]
+            id: String,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        match self.gmail_client.get_label(&args.id).await {
+            Ok(label) => {
+                let mut text = format!("ID: {}\nName: {}\n", label.id, label.name);
+                if let Some(ref label_type) = label.label_type {
+                    text.push_str(&format!("Type: {}\n", label_type));
+                }
+                if let Some(ref visibility) = label.message_list_visibility {
+                    text.push_str(&format!("Message list visibility: {}\n", visibility));
+                }
+                if let Some(ref visibility) = label.label_list_visibility {
+                    text.push_str(&format!("Label list visibility: {}\n", visibility));
+                }
+                if let Some(ref color) = label.color {
+                    text.push_str(&format!(
+                        "Color: text {}, background {}\n",
+                        color.text_color.as_deref().unwrap_or("default"),
+                        color.background_color.as_deref().unwrap_or("default")
+                    ));
+                }
+                text.push_str(&format!(
+                    "Messages: {}\nUnread: {}\n",
+                    label.messages_total.unwrap_or(0),
+                    label.messages_unread.unwrap_or(0)
+                ));
+
+                CallToolResult::text(text)
+            }
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_get_or_create_label(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            name: String,
+            message_list_visibility: Option<String>,
+            label_list_visibility: Option<String>,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        match self
+            .gmail_client
+            .get_or_create_label(
+                &args.name,
+                args.message_list_visibility.as_deref(),
+                args.label_list_visibility.as_deref(),
+            )
+            .await
+        {
+            Ok(label) => CallToolResult::text(format!(
+                "Label:\nID: {}\nName: {}\nType: {}",
+                label.id,
+                label.name,
+                label.label_type.unwrap_or_default()
+            )),
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_export_labels(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        struct Args {
+            path: String,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        // System labels exist in every account and can't be created
+        let export = match self.gmail_client.list_labels().await {
+            Ok(result) => LabelList { labels: result.user },
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
+        let contents = match serde_json::to_string_pretty(&export) {
+            Ok(c) => c,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+        if let Err(e) = std::fs::write(&args.path, contents) {
+            return CallToolResult::error(format!("Failed to write {}: {}", args.path, e));
+        }
+
+        CallToolResult::text(format!("Exported {} labels to {}", export.labels.len(), args.path))
+    }
+
+    async fn handle_import_labels(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        struct Args {
+            path: String,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        let imported: LabelList = match std::fs::read_to_string(&args.path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| serde_json::from_str(&contents).map_err(|e| e.to_string()))
+        {
+            Ok(list) => list,
+            Err(e) => return CallToolResult::error(format!("Failed to read {}: {}", args.path, e)),
+        };
+
+        let mut imported_count = 0;
+        let mut failed = Vec::new();
+        for saved in imported.labels {
+            if saved.label_type.as_deref() == Some("system") {
+                continue;
+            }
+
+            let outcome = async {
+                let label = self
+                    .gmail_client
+                    .get_or_create_label(
+                        &saved.name,
+                        saved.message_list_visibility.as_deref(),
+                        saved.label_list_visibility.as_deref(),
+                    )
+                    .await?;
+
+                // An existing label keeps its settings unless the file has some to apply
+                let unchanged = saved.color.is_none()
+                    && saved.message_list_visibility == label.message_list_visibility
+                    && saved.label_list_visibility == label.label_list_visibility;
+                if !unchanged {
+                    let updates = UpdateLabelRequest {
+                        name: Some(label.name.clone()),
+                        message_list_visibility: saved.message_list_visibility.or(label.message_list_visibility),
+                        label_list_visibility: saved.label_list_visibility.or(label.label_list_visibility),
+                        color: saved.color.or(label.color),
+                    };
+                    self.gmail_client.update_label(&label.id, updates).await?;
+                }
+                Ok::<_, GmailMcpError>(())
+            }
+            .await;
+
+            match outcome {
+                Ok(()) => imported_count += 1,
+                Err(e) => failed.push(format!("{}: {}", saved.name, e)),
+            }
+        }
+
+        let mut text = format!("Imported {} labels from {}\n", imported_count, args.path);
+        if !failed.is_empty() {
+            text.push_str(&format!("\nFailed ({}):\n", failed.len()));
+            for failure in &failed {
+                text.push_str(&format!("- {}\n", failure));
+            }
+        }
+        CallToolResult::text(text)
+    }
+
+    async fn handle_create_filter(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            criteria: CriteriaArgs,
+            action: ActionArgs,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct CriteriaArgs {
+            from: Option<String>,
+            to: Option<String>,
+            subject: Option<String>,
+            query: Option<String>,
+            negated_query: Option<String>,
+            has_attachment: Option<bool>,
+            exclude_chats: Option<bool>,
+            size: Option<i64>,
+            size_comparison: Option<String>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ActionArgs {
+            add_label_ids: Option<Vec<String>>,
+            remove_label_ids: Option<Vec<String>>,
+            forward: Option<String>,
+            #[serde(default)]
+            mark_important: bool,
+            #[serde(default)]
+            never_spam: bool,
+            #[serde(default)]
+            mark_read: bool,
+            #[serde(default)]
+            archive: bool,
+            #[serde(default)]
+            star: bool,
+            #[serde(default)]
+            delete: bool,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        let criteria = FilterCriteria {
+            from: args.criteria.from,
+            to: args.criteria.to,
+            subject: args.criteria.subject,
+            query: args.criteria.query,
+            negated_query: args.criteria.negated_query,
+            has_attachment: args.criteria.has_attachment,
+            exclude_chats: args.criteria.exclude_chats,
+            size: args.criteria.size,
+            size_comparison: args.criteria.size_comparison.map(|s| match s.as_str() {
+                "smaller" => SizeComparison::Smaller,
+                "larger" => SizeComparison::Larger,
+                _ => SizeComparison::Unspecified,
+            }),
+        };
+
+        let flags = FilterActionFlags {
+            mark_important: args.action.mark_important,
+            never_spam: args.action.never_spam,
+            mark_read: args.action.mark_read,
+            archive: args.action.archive,
+            star: args.action.star,
+            delete: args.action.delete,
+        };
+
+        let action = flags.apply(FilterAction {
+            add_label_ids: args.action.add_label_ids,
+            remove_label_ids: args.action.remove_label_ids,
+            forward: args.action.forward,
+        });
+
+        let action = match self.resolve_filter_labels(action).await {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
+        match self.gmail_client.create_filter(criteria, action).await {
+            Ok(filter) => CallToolResult::text(format!(
+                "Filter created successfully:\n{}",
+                format_filter(&filter)
+            )),
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_list_filters(&self) -> CallToolResult {
+        match self.gmail_client.list_filters().await {
+            Ok(result) => {
+                if result.filters.is_empty() {
+                    return CallToolResult::text("No filters found.");
+                }
+
+                let mut text = format!("Found {} filters:\n\n", result.count);
+
+                for filter in &result.filters {
+                    text.push_str(&format_filter(filter));
+                    text.push('\n');
+                }
+
+                CallToolResult::text(text)
+            }
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_get_filter(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            filter_id: String,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        match self.gmail_client.get_filter(&args.filter_id).await {
+            Ok(filter) => CallToolResult::text(format!("Filter details:\n{}", format_filter(&filter))),
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_delete_filter(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            filter_id: String,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        match self.gmail_client.delete_filter(&args.filter_id).await {
+            Ok(_) => CallToolResult::text(format!("Filter {} deleted successfully", args.filter_id)),
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_export_filters(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        struct Args {
+            path: String,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        let filters = match self.gmail_client.list_filters().await {
+            Ok(result) => result.filters,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+        let labels = match self.gmail_client.list_labels().await {
+            Ok(result) => result.all,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
+        // Label IDs differ between accounts, so the file refers to labels by name
+        let names: HashMap<&str, &str> = labels.iter().map(|l| (l.id.as_str(), l.name.as_str())).collect();
+        let to_names = |ids: Option<Vec<String>>| {
+            ids.map(|ids| {
+                ids.into_iter()
+                    .map(|id| names.get(id.as_str()).map_or(id, |name| name.to_string()))
+                    .collect()
+            })
+        };
+        let export = FilterList {
+            filter: filters
+                .into_iter()
+                .map(|f| Filter {
+                    id: None,
+                    criteria: f.criteria,
+                    action: FilterAction {
+                        add_label_ids: to_names(f.action.add_label_ids),
+                        remove_label_ids: to_names(f.action.remove_label_ids),
+                        forward: f.action.forward,
+                    },
+                })
+                .collect(),
+        };
+
+        let contents = match serde_json::to_string_pretty(&export) {
+            Ok(c) => c,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+        if let Err(e) = std::fs::write(&args.path, contents) {
+            return CallToolResult::error(format!("Failed to write {}: {}", args.path, e));
+        }
+
+        CallToolResult::text(format!("Exported {} filters to {}", export.filter.len(), args.path))
+    }
+
+    async fn handle_import_filters(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        struct Args {
+            path: String,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        let imported: FilterList = match std::fs::read_to_string(&args.path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| serde_json::from_str(&contents).map_err(|e| e.to_string()))
+        {
+            Ok(list) => list,
+            Err(e) => return CallToolResult::error(format!("Failed to read {}: {}", args.path, e)),
+        };
+        let mut existing = match self.gmail_client.list_filters().await {
+            Ok(result) => result.filters,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
+        let mut created = 0;
+        let mut skipped = 0;
+        let mut failed = Vec::new();
+        for (index, filter) in imported.filter.into_iter().enumerate() {
+            let action = match self.resolve_filter_labels(filter.action).await {
+                Ok(action) => action,
+                Err(e) => {
+                    failed.push(format!("#{}: {}", index + 1, e));
+                    continue;
+                }
+            };
+            let candidate = Filter {
+                id: None,
+                criteria: filter.criteria,
+                action,
+            };
+            if existing.iter().any(|f| same_filter(f, &candidate)) {
+                skipped += 1;
+                continue;
+            }
+
+            match self
+                .gmail_client
+                .create_filter(candidate.criteria.clone(), candidate.action.clone())
+                .await
+            {
+                Ok(_) => {
+                    created += 1;
+                    existing.push(candidate);
+                }
+                Err(e) => failed.push(format!("#{}: {}", index + 1, e)),
+            }
+        }
+
+        let mut text = format!(
+            "Imported filters from {}:\nCreated: {}\nSkipped (already exist): {}\n",
+            args.path, created, skipped
+        );
+        if !failed.is_empty() {
+            text.push_str(&format!("\nFailed ({}):\n", failed.len()));
+            for failure in &failed {
+                text.push_str(&format!("- {}\n", failure));
+            }
+        }
+        CallToolResult::text(text)
+    }
+
+    async fn handle_apply_filter_to_existing(&self, args: Value, ctx: &ToolContext) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            filter_id: Option<String>,
+            criteria: Option<FilterCriteria>,
+            add_label_ids: Option<Vec<String>>,
+            remove_label_ids: Option<Vec<String>>,
+            batch_size: Option<usize>,
+            #[serde(default)]
+            confirm: bool,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        let (criteria, add, remove, forward) = match (args.filter_id, args.criteria) {
+            (Some(_), Some(_)) => return CallToolResult::error("Provide either filterId or criteria, not both"),
+            (None, None) => return CallToolResult::error("Either filterId or criteria is required"),
+            (Some(_), None) if args.add_label_ids.is_some() || args.remove_label_ids.is_some() => {
+                return CallToolResult::error(
+                    "addLabelIds and removeLabelIds go with criteria; a filterId applies the filter's own action",
+                )
+            }
+            (Some(filter_id), None) => match self.gmail_client.get_filter(&filter_id).await {
+                Ok(filter) => (
+                    filter.criteria,
+                    filter.action.add_label_ids,
+                    filter.action.remove_label_ids,
+                    filter.action.forward,
+                ),
+                Err(e) => return CallToolResult::error(e.to_string()),
+            },
+            (None, Some(criteria)) => {
+                let add = match self.gmail_client.resolve_label_ids(args.add_label_ids).await {
+                    Ok(ids) => ids,
+                    Err(e) => return CallToolResult::error(e.to_string()),
+                };
+                let remove = match self.gmail_client.resolve_label_ids(args.remove_label_ids).await {
+                    Ok(ids) => ids,
+                    Err(e) => return CallToolResult::error(e.to_string()),
+                };
+                (criteria, add, remove, None)
+            }
+        };
+
+        // Trashing is a delete, so it goes through `batch_delete_messages` and its wording
+        let trash = add.as_ref().is_some_and(|ids| ids.iter().any(|id| id == "TRASH"));
+        let add = add
+            .map(|ids| ids.into_iter().filter(|id| id != "TRASH").collect::<Vec<_>>())
+            .filter(|ids| !ids.is_empty());
+        let remove = remove.filter(|ids| !ids.is_empty());
+        if add.is_none() && remove.is_none() && !trash {
+            return CallToolResult::error("The filter adds or removes no labels, so there is nothing to apply");
+        }
+
+        let query = criteria_to_query(&criteria);
+        if query.is_empty() {
+            return CallToolResult::error("Filter criteria are empty and would match every message");
+        }
+
+        // Without confirm, count only far enough to tell whether the threshold is crossed
+        let listed = if args.confirm {
+            self.gmail_client.list_all_message_ids(&query).await
+        } else {
+            self.gmail_client
+                .list_message_ids(&query, self.confirm_threshold.saturating_add(1))
+                .await
+        };
+        let ids = match listed {
+            Ok(ids) => ids,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+        if ids.is_empty() {
+            return CallToolResult::text(format!("Query: {}\nNo existing messages match.", query));
+        }
+        let verb = if trash { "move to trash" } else { "relabel" };
+        if !args.confirm && ids.len() > self.confirm_threshold {
+            return CallToolResult::error(format!(
+                "This would {} more than {} messages, the confirmation threshold. Set confirm: true to proceed.",
+                verb, self.confirm_threshold
+            ));
+        }
+
+        let control = ctx.batch_control();
+        let batch_size = args.batch_size.unwrap_or(50);
+        let mut text = format!("Query: {}\nMatching messages: {}\n", query, ids.len());
+        let mut cancelled = false;
+
+        if add.is_some() || remove.is_some() {
+            match self
+                .gmail_client
+                .batch_modify_messages(&ids, add, remove, batch_size, &control)
+                .await
+            {
+                Ok(result) => {
+                    cancelled = result.cancelled;
+                    push_batch_outcome(&mut text, &result, ids.len(), "relabeled", "relabel");
+                }
+                Err(e) => return CallToolResult::error(e.to_string()),
+            }
+        }
+        if trash && !cancelled {
+            match self.gmail_client.batch_delete_messages(&ids, batch_size, &control).await {
+                Ok(result) => push_batch_outcome(&mut text, &result, ids.len(), "moved to trash", "move to trash"),
+                Err(e) => return CallToolResult::error(e.to_string()),
+            }
+        }
+        if let Some(forward) = forward {
+            text.push_str(&format!("\nNote: existing messages were not forwarded to {}\n", forward));
+        }
+
+        CallToolResult::text(text)
+    }
+
+    async fn handle_create_filter_template(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize, Default)]
+        #[serde(rename_all = "camelCase")]
+        struct TemplateParams {
+            sender_email: Option<String>,
+            subject_text: Option<String>,
+            search_text: Option<String>,
+            field: Option<String>,
+            list_identifier: Option<String>,
+            size_in_bytes: Option<i64>,
+            label_ids: Option<Vec<String>>,
+            archive: Option<bool>,
+            mark_as_read: Option<bool>,
+            mark_important: Option<bool>,
+        }
+
+        // Accept both nested `parameters` object and flat parameters for better UX
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            #[serde(alias = "templateName")]
+            template: String,
+            // Nested parameters object (preferred)
+            #[serde(default)]
+            parameters: Option<TemplateParams>,
+            // Flat parameters (for convenience)
+            sender_email: Option<String>,
+            subject_text: Option<String>,
+            search_text: Option<String>,
+            field: Option<String>,
+            list_identifier: Option<String>,
+            size_in_bytes: Option<i64>,
+            label_ids: Option<Vec<String>>,
+            #[serde(alias = "labelId")]
+            label_id: Option<String>,
+            archive: Option<bool>,
+            mark_as_read: Option<bool>,
+            mark_important: Option<bool>,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        // Merge flat params with nested params (flat params take precedence)
+        let nested = args.parameters.unwrap_or_default();
+        let params = TemplateParams {
+            sender_email: args.sender_email.or(nested.sender_email),
+            subject_text: args.subject_text.or(nested.subject_text),
+            search_text: args.search_text.or(nested.search_text),
+            field: args.field.or(nested.field),
+            list_identifier: args.list_identifier.or(nested.list_identifier),
+            size_in_bytes: args.size_in_bytes.or(nested.size_in_bytes),
+            // Handle both labelIds array and single labelId
+            label_ids: args.label_ids.or(nested.label_ids).or_else(|| {
+                args.label_id.map(|id| vec![id])
+            }),
+            archive: args.archive.or(nested.archive),
+            mark_as_read: args.mark_as_read.or(nested.mark_as_read),
+            mark_important: args.mark_important.or(nested.mark_important),
+        };
+
+        let (criteria, action) = match args.template.as_str() {
+            "fromSender" => {
+                let email = match params.sender_email {
+                    Some(e) => e,
+                    None => return CallToolResult::error("senderEmail is required for fromSender template"),
+                };
+                FilterTemplates::from_sender(&email, params.label_ids, params.archive.unwrap_or(false))
+            }
+            "withSubject" => {
+                let subject = match params.subject_text {
+                    Some(s) => s,
+                    None => return CallToolResult::error("subjectText is required for withSubject template"),
+                };
+                FilterTemplates::with_subject(&subject, params.label_ids, params.mark_as_read.unwrap_or(false))
+            }
+            "withAttachments" => {
+                FilterTemplates::with_attachments(params.label_ids)
+            }
+            "largeEmails" => {
+                let size = match params.size_in_bytes {
+                    Some(s) => s,
+                    None => return CallToolResult::error("sizeInBytes is required for largeEmails template"),
+                };
+                FilterTemplates::large_emails(size, params.label_ids)
+            }
+            "containingText" => {
+                let text = match params.search_text {
+                    Some(t) => t,
+                    None => return CallToolResult::error("searchText is required for containingText template"),
+                };
+                let field = match params.field.as_deref().map(str::parse::<TextField>).transpose() {
+                    Ok(f) => f.unwrap_or_default(),
+                    Err(e) => return CallToolResult::error(e.to_string()),
+                };
+                FilterTemplates::containing_text_in(&text, field, params.label_ids, params.mark_important.unwrap_or(false))
+            }
+            "mailingList" => {
+                let list = match params.list_identifier {
+                    Some(l) => l,
+                    None => return CallToolResult::error("listIdentifier is required for mailingList template"),
+                };
+                FilterTemplates::mailing_list(&list, params.label_ids, params.archive.unwrap_or(true))
+            }
+            "deleteFromSender" => {
+                let email = match params.sender_email {
+                    Some(e) => e,
+                    None => return CallToolResult::error("senderEmail is required for deleteFromSender template"),
+                };
+                FilterTemplates::delete_from_sender(&email)
+            }
+            _ => return CallToolResult::error(format!("Unknown template: {}", args.template)),
+        };
+
+        let action = match self.resolve_filter_labels(action).await {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
+        match self.gmail_client.create_filter(criteria, action).await {
+            Ok(filter) => CallToolResult::text(format!(
+                "Filter created from template '{}':\n{}",
+                args.template,
+                format_filter(&filter)
+            )),
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_preview_filter(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            criteria: FilterCriteria,
+            max_results: Option<u32>,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        let query = criteria_to_query(&args.criteria);
+        if query.is_empty() {
+            return CallToolResult::error("Filter criteria are empty; set at least one of from, to, subject, query, negatedQuery, hasAttachment or size");
+        }
+
+        // Counting stops after a few pages so broad criteria stay cheap
+        let total = match self.gmail_client.list_message_ids(&query, PREVIEW_COUNT_LIMIT + 1).await {
+            Ok(ids) => ids.len(),
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+        let count = if total > PREVIEW_COUNT_LIMIT {
+            format!("more than {} (counting stopped there)", PREVIEW_COUNT_LIMIT)
+        } else {
+            total.to_string()
+        };
+        let mut text = format!("Query: {}\nMatching messages: {}\n", query, count);
+        if total == 0 {
+            return CallToolResult::text(text);
+        }
+
+        let max_results = args.max_results.unwrap_or(self.default_max_results).min(MAX_SEARCH_RESULTS);
+        match self.gmail_client.search_messages(&query, Some(max_results)).await {
+            Ok(results) => {
+                text.push_str(&format!("\nMost recent {}:\n\n", results.len()));
+                text.push_str(&results.iter().map(format_search_result).collect::<Vec<_>>().join("\n"));
+                CallToolResult::text(text)
+            }
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    /// Resolve label names in a filter action to IDs
+    ///
+    /// Labels to add are created if missing, so a filter can introduce a new label;
+    /// labels to remove must already exist.
+    async fn resolve_filter_labels(&self, action: FilterAction) -> crate::error::Result<FilterAction> {
+        Ok(FilterAction {
+            add_label_ids: self
+                .gmail_client
+                .resolve_or_create_label_ids(action.add_label_ids)
+                .await?,
+            remove_label_ids: self
+                .gmail_client
+                .resolve_label_ids(action.remove_label_ids)
+                .await?,
+            forward: action.forward,
+        })
+    }
+
+    async fn handle_download_attachment(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            message_id: String,
+            attachment_id: String,
+            filename: Option<String>,
+            save_path: Option<String>,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        // Determine filename, keeping it inside the save directory
+        let filename = args.filename.unwrap_or_else(|| format!("attachment-{}", args.attachment_id));
+        let filename = match sanitize_filename(&filename) {
+            Ok(f) => f,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
+        // Determine save path
+        let save_dir = args.save_path.unwrap_or_else(|| ".".to_string());
+        let full_path = std::path::Path::new(&save_dir).join(&filename);
+
+        // Ensure directory exists
+        if let Some(parent) = full_path.parent() {
+            if !parent.exists() {
+                if let Err(e) = std::fs::create_dir_all(parent) {
+                    return CallToolResult::error(format!("Failed to create directory: {}", e));
+                }
+            }
+        }
+
+        // Decode the download straight into the file
+        let size = match self
+            .gmail_client
+            .save_attachment(&args.message_id, &args.attachment_id, &full_path)
+            .await
+        {
+            Ok(size) => size,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
         CallToolResult::text(format!(
             "Attachment downloaded successfully:\nFile: {}\nSize: {} bytes\nSaved to: {}",
             filename,
-            data.len(),
+            size,
             full_path.display()
         ))
     }
+
+    async fn handle_download_all_attachments(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            message_id: String,
+            save_path: Option<String>,
+            #[serde(default)]
+            mime_types: Vec<String>,
+            #[serde(default)]
+            extensions: Vec<String>,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        let all_attachments = match self.gmail_client.list_attachments(&args.message_id).await {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
+        if all_attachments.is_empty() {
+            return CallToolResult::text("This email has no attachments.");
+        }
+
+        let attachments: Vec<EmailAttachment> = all_attachments
+            .iter()
+            .filter(|a| attachment_matches(a, &args.mime_types, &args.extensions))
+            .cloned()
+            .collect();
+        if attachments.is_empty() {
+            return CallToolResult::text(format!(
+                "None of the email's {} attachments match the requested types.",
+                all_attachments.len()
+            ));
+        }
+
+        let save_dir = std::path::PathBuf::from(args.save_path.unwrap_or_else(|| ".".to_string()));
+        if let Err(e) = std::fs::create_dir_all(&save_dir) {
+            return CallToolResult::error(format!("Failed to create directory: {}", e));
+        }
+
+        let mut saved = Vec::new();
+        let mut failed = Vec::new();
+
+        for attachment in &attachments {
+            let filename = match sanitize_filename(&attachment.filename) {
+                Ok(f) => f,
+                Err(e) => {
+                    failed.push((attachment.filename.clone(), e.to_string()));
+                    continue;
+                }
+            };
+
+            let full_path = unique_file_path(&save_dir, &filename);
+            match self
+                .gmail_client
+                .save_attachment(&args.message_id, &attachment.id, &full_path)
+                .await
+            {
+                Ok(size) => saved.push((full_path, size)),
+                Err(e) => failed.push((attachment.filename.clone(), e.to_string())),
+            }
+        }
+
+        let skipped = all_attachments.len() - attachments.len();
+        let mut text = if skipped > 0 {
+            format!("Skipped {} attachments that don't match the requested types.\n", skipped)
+        } else {
+            String::new()
+        };
+        text.push_str(&format!(
+            "Downloaded {} of {} attachments to {}:\n",
+            saved.len(),
+            attachments.len(),
+            save_dir.display()
+        ));
+        for (path, size) in &saved {
+            text.push_str(&format!("- {} ({})\n", path.display(), format_size(*size as i64)));
+        }
+        if !failed.is_empty() {
+            text.push_str(&format!("\nFailed ({}):\n", failed.len()));
+            for (filename, err) in &failed {
+                text.push_str(&format!("- {} ({})\n", filename, err));
+            }
+        }
+
+        if saved.is_empty() {
+            CallToolResult::error(text)
+        } else {
+            CallToolResult::text(text)
+        }
+    }
+
+    async fn handle_import_email(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            raw_path: Option<String>,
+            raw: Option<String>,
+            #[serde(default)]
+            label_ids: Vec<String>,
+            #[serde(default)]
+            never_mark_spam: bool,
+            internal_date_source: Option<String>,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        let raw = match load_raw_source(args.raw_path.as_deref(), args.raw.as_deref()) {
+            Ok(r) => r,
+            Err(e) => return CallToolResult::error(e),
+        };
+
+        let options = ImportOptions {
+            label_ids: args.label_ids,
+            never_mark_spam: args.never_mark_spam,
+            internal_date_source: args.internal_date_source,
+        };
+
+        match self.gmail_client.import_message(&raw, &options).await {
+            Ok(message) => CallToolResult::text(format!(
+                "Message imported successfully.\nID: {}\nThread ID: {}",
+                message.id,
+                message.thread_id.unwrap_or_default()
+            )),
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_insert_email(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            raw_path: Option<String>,
+            raw: Option<String>,
+            #[serde(default)]
+            label_ids: Vec<String>,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        let raw = match load_raw_source(args.raw_path.as_deref(), args.raw.as_deref()) {
+            Ok(r) => r,
+            Err(e) => return CallToolResult::error(e),
+        };
+
+        match self.gmail_client.insert_message(&raw, &args.label_ids).await {
+            Ok(message) => CallToolResult::text(format!(
+                "Message inserted successfully.\nID: {}\nThread ID: {}",
+                message.id,
+                message.thread_id.unwrap_or_default()
+            )),
+            Err(e) => CallToolResult::error(e.to_string()),
+        }
+    }
+
+    async fn handle_get_unsubscribe_info(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            message_id: String,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        let info = match self.gmail_client.get_unsubscribe_info(&args.message_id).await {
+            Ok(i) => i,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
+        if info.mailto.is_empty() && info.http.is_empty() {
+            return CallToolResult::text("This message has no List-Unsubscribe header.");
+        }
+
+        CallToolResult::text(format_unsubscribe_targets(&info))
+    }
+
+    async fn handle_unsubscribe(&self, args: Value) -> CallToolResult {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Args {
+            message_id: String,
+        }
+
+        let args: Args = match serde_json::from_value(args) {
+            Ok(a) => a,
+            Err(e) => return CallToolResult::error(format!("Invalid arguments: {}", e)),
+        };
+
+        let info = match self.gmail_client.get_unsubscribe_info(&args.message_id).await {
+            Ok(i) => i,
+            Err(e) => return CallToolResult::error(e.to_string()),
+        };
+
+        if info.mailto.is_empty() && info.http.is_empty() {
+            return CallToolResult::error("This message has no List-Unsubscribe header.");
+        }
+
+        let text = format_unsubscribe_targets(&info);
+        match info.one_click_url() {
+            Some(url) => match self.gmail_client.unsubscribe_one_click(url).await {
+                Ok(()) => CallToolResult::text(format!("{}\nUnsubscribed via one-click POST to {}", text, url)),
+                Err(e) => CallToolResult::error(format!("{}\n{}", text, e)),
+            },
+            None => CallToolResult::error(format!(
+                "{}\nThe sender does not support one-click unsubscribe; use one of the targets above instead.",
+                text
+            )),
+        }
+    }
+}
+
+/// List a message's unsubscribe targets and whether one-click is offered
+fn format_unsubscribe_targets(info: &UnsubscribeInfo) -> String {
+    let mut text = String::from("Unsubscribe targets:\n");
+    for target in info.mailto.iter().chain(info.http.iter()) {
+        text.push_str(&format!("- {}\n", target));
+    }
+    text.push_str(&format!(
+        "One-click supported: {}\n",
+        if info.one_click_url().is_some() { "yes" } else { "no" }
+    ));
+    text
+}
+
+/// Render a filter's ID, full criteria and actions
+fn format_filter(filter: &Filter) -> String {
+    let criteria = &filter.criteria;
+    let size = criteria.size.map(|size| {
+        let comparison = match criteria.size_comparison {
+            Some(SizeComparison::Smaller) => "smaller than ",
+            Some(SizeComparison::Larger) => "larger than ",
+            _ => "",
+        };
+        format!("size: {}{}", comparison, format_size(size))
+    });
+
+    let criteria_parts: Vec<String> = [
+        criteria.from.as_ref().map(|v| format!("from: {}", v)),
+        criteria.to.as_ref().map(|v| format!("to: {}", v)),
+        criteria.subject.as_ref().map(|v| format!("subject: {}", v)),
+        criteria.query.as_ref().map(|v| format!("query: {}", v)),
+        criteria.negated_query.as_ref().map(|v| format!("negatedQuery: {}", v)),
+        criteria.has_attachment.map(|v| format!("hasAttachment: {}", v)),
+        criteria.exclude_chats.map(|v| format!("excludeChats: {}", v)),
+        size,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let action = &filter.action;
+    let action_parts: Vec<String> = [
+        action.add_label_ids.as_ref().map(|v| format!("addLabelIds: {}", v.join(", "))),
+        action.remove_label_ids.as_ref().map(|v| format!("removeLabelIds: {}", v.join(", "))),
+        action.forward.as_ref().map(|v| format!("forward: {}", v)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    format!(
+        "ID: {}\nCriteria: {}\nActions: {}\n",
+        filter.id.as_deref().unwrap_or(""),
+        criteria_parts.join(", "),
+        action_parts.join(", ")
+    )
+}
+
+/// Render a label's ID and name, with message counts when known
+fn format_label_line(label: &Label) -> String {
+    let counts = match (label.messages_total, label.messages_unread) {
+        (Some(total), Some(unread)) => format!(" ({} messages, {} unread)", total, unread),
+        _ => String::new(),
+    };
+    format!("ID: {}\nName: {}{}\n\n", label.id, label.name, counts)
+}
+
+/// Render attachments as a bulleted list with name, type, size and ID
+fn format_attachment_list(attachments: &[EmailAttachment]) -> String {
+    let mut text = format!("Attachments ({}):\n", attachments.len());
+    for a in attachments {
+        text.push_str(&format!(
+            "- {} ({}, {}, ID: {})\n",
+            a.filename,
+            a.mime_type,
+            format_size(a.size),
+            a.id
+        ));
+    }
+    text
+}
+
+/// Render messages as a transcript: a "From X on DATE:" line, then the plain
+/// text body (already converted from HTML for HTML-only mail)
+fn thread_transcript(messages: &[ReadMessageResult]) -> String {
+    messages
+        .iter()
+        .map(|m| {
+            let from = if m.from.is_empty() { "(unknown sender)" } else { m.from.as_str() };
+            let date = match (m.date.as_str(), m.received.as_deref()) {
+                ("", Some(received)) => received,
+                ("", None) => "(unknown date)",
+                (date, _) => date,
+            };
+            format!("From {} on {}:\n{}", from, date, m.body.trim())
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Explain a message rejected for size: per-attachment sizes and what to drop
+fn message_too_large_report(size: usize, limit: usize, attachments: &[(String, usize)]) -> String {
+    let mut text = format!(
+        "Message is too large to send: {} encoded, Gmail's limit is {}.\n",
+        format_size(size as i64),
+        format_size(limit as i64)
+    );
+
+    if attachments.is_empty() {
+        text.push_str("\nThe message has no attachments; shorten the body or split it across several emails.");
+        return text;
+    }
+
+    let mut by_size: Vec<&(String, usize)> = attachments.iter().collect();
+    by_size.sort_by_key(|(_, encoded)| std::cmp::Reverse(*encoded));
+    text.push_str("\nAttachments, largest first (sizes after base64 encoding):\n");
+    for (name, encoded) in by_size {
+        text.push_str(&format!("- {}: {}\n", name, format_size(*encoded as i64)));
+    }
+
+    let (dropped, remaining) = attachments_to_drop(size, limit, attachments);
+    if remaining <= limit {
+        text.push_str(&format!(
+            "\nSuggestion: drop {} (about {} without them), and share large files as links (e.g. Google Drive) instead of attachments.",
+            dropped.join(", "),
+            format_size(remaining as i64)
+        ));
+    } else {
+        text.push_str("\nEven without attachments the message exceeds the limit; shorten the body as well.");
+    }
+    text
+}
+
+/// Append a batch's cancellation, success count and failures to `text`
+fn push_batch_outcome(text: &mut String, result: &BatchOperationResult, total: usize, done: &str, verb: &str) {
+    if result.cancelled {
+        text.push_str(&format!(
+            "Cancelled after processing {} of {} messages\n",
+            result.success_count + result.failure_count,
+            total
+        ));
+    }
+    text.push_str(&format!("Successfully {}: {} messages\n", done, result.success_count));
+
+    if result.failure_count > 0 {
+        text.push_str(&format!(
+            "Failed to {}: {} messages\n\nFailed message IDs:\n",
+            verb, result.failure_count
+        ));
+        for (id, err) in &result.failures {
+            text.push_str(&format!("- {}... ({})\n", &id[..16.min(id.len())], err));
+        }
+    }
+}
+
+/// One `search_emails` result entry
+fn format_search_result(result: &SearchMessageResult) -> String {
+    let mut entry = format!(
+        "ID: {}\nSubject: {}\nFrom: {}\nDate: {}\n",
+        result.id, result.subject, result.from, result.date
+    );
+    if let Some(ref received) = result.received {
+        entry.push_str(&format!("Received: {}\n", received));
+    }
+    entry
+}
+
+/// Check that a destructive tool call carries `confirm: true`
+fn require_confirm(args: Value, warning: &str) -> std::result::Result<(), CallToolResult> {
+    #[derive(Deserialize)]
+    struct Args {
+        #[serde(default)]
+        confirm: bool,
+    }
+
+    match serde_json::from_value::<Args>(args) {
+        Ok(Args { confirm: true }) => Ok(()),
+        Ok(_) => Err(CallToolResult::error(format!(
+            "{}. Set confirm: true to proceed.",
+            warning
+        ))),
+        Err(e) => Err(CallToolResult::error(format!("Invalid arguments: {}", e))),
+    }
+}
+
+/// Read a raw MIME message from either a file path or an inline string
+fn load_raw_source(path: Option<&str>, raw: Option<&str>) -> std::result::Result<Vec<u8>, String> {
+    match (path, raw) {
+        (Some(_), Some(_)) => Err("Provide either rawPath or raw, not both".to_string()),
+        (Some(path), None) => std::fs::read(path)
+            .map_err(|e| format!("Failed to read message file {}: {}", path, e)),
+        (None, Some(raw)) => Ok(raw.as_bytes().to_vec()),
+        (None, None) => Err("Either rawPath or raw is required".to_string()),
+    }
+}
+
+/// OAuth scope each tool needs
+const TOOL_SCOPES: &[(&str, &str)] = &[
+    ("send_email", scopes::MODIFY),
+    ("draft_email", scopes::MODIFY),
+    ("reply_email", scopes::MODIFY),
+    ("send_email_batch", scopes::MODIFY),
+    ("read_email", scopes::MODIFY),
+    ("batch_read_emails", scopes::MODIFY),
+    ("get_thread_text", scopes::MODIFY),
+    ("get_message_raw_header", scopes::MODIFY),
+    ("search_emails", scopes::MODIFY),
+    ("run_saved_query", scopes::MODIFY),
+    ("get_new_messages", scopes::MODIFY),
+    ("verify_recipient", scopes::MODIFY),
+    ("modify_email", scopes::MODIFY),
+    ("delete_email", scopes::MODIFY),
+    ("get_importance", scopes::MODIFY),
+    ("set_importance", scopes::MODIFY),
+    ("move_email", scopes::MODIFY),
+    ("list_email_labels", scopes::MODIFY),
+    ("batch_modify_emails", scopes::MODIFY),
+    ("batch_delete_emails", scopes::MODIFY),
+    ("empty_trash", scopes::FULL_ACCESS),
+    ("empty_spam", scopes::MODIFY),
+    ("create_label", scopes::MODIFY),
+    ("update_label", scopes::MODIFY),
+    ("delete_label", scopes::MODIFY),
+    ("get_label", scopes::MODIFY),
+    ("get_or_create_label", scopes::MODIFY),
+    ("export_labels", scopes::MODIFY),
+    ("import_labels", scopes::MODIFY),
+    ("create_filter", scopes::SETTINGS_BASIC),
+    ("list_filters", scopes::SETTINGS_BASIC),
+    ("get_filter", scopes::SETTINGS_BASIC),
+    ("delete_filter", scopes::SETTINGS_BASIC),
+    ("export_filters", scopes::SETTINGS_BASIC),
+    ("import_filters", scopes::SETTINGS_BASIC),
+    ("create_filter_from_template", scopes::SETTINGS_BASIC),
+    ("preview_filter", scopes::MODIFY),
+    ("apply_filter_to_existing", scopes::MODIFY),
+    ("download_attachment", scopes::MODIFY),
+    ("download_all_attachments", scopes::MODIFY),
+    ("import_email", scopes::MODIFY),
+    ("insert_email", scopes::MODIFY),
+    ("get_unsubscribe_info", scopes::MODIFY),
+    ("unsubscribe", scopes::MODIFY),
+];
+
+/// Scope required by a tool, if it is a known tool
+fn required_scope(tool: &str) -> Option<&'static str> {
+    TOOL_SCOPES
+        .iter()
+        .find(|(name, _)| *name == tool)
+        .map(|(_, scope)| *scope)
 }
 
 // ==================== Schema Definitions ====================
 
+/// Every tool this server implements
+fn all_tools() -> Vec<Tool> {
+    vec![
+        tool_def("send_email", "Sends a new email", send_email_schema()),
+        tool_def("draft_email", "Create a new email draft", send_email_schema()),
+        tool_def("reply_email", "Replies to the sender of an email in the same thread, without re-specifying recipients or subject", reply_email_schema()),
+        tool_def("send_email_batch", "Sends a separate, individually addressed email for each item (mail merge)", send_email_batch_schema()),
+        tool_def("read_email", "Retrieves the content of a specific email", read_email_schema()),
+        tool_def("batch_read_emails", "Retrieves several emails by ID concurrently, returning a JSON array with an error entry for each message that couldn't be read", batch_read_emails_schema()),
+        tool_def("get_thread_text", "Returns a thread as one plain text transcript of its message bodies, oldest first, with a \"From X on DATE:\" line before each (suited to summarization)", get_thread_text_schema()),
+        tool_def("get_message_raw_header", "Returns selected raw headers of an email (by default the SPF/DKIM/DMARC authentication and Received headers) without downloading the body", get_message_raw_header_schema()),
+        tool_def("search_emails", "Searches for emails using Gmail search syntax", search_emails_schema()),
+        tool_def("save_query", "Saves a Gmail search query under a name for reuse with run_saved_query", save_query_schema()),
+        tool_def("list_saved_queries", "Lists saved Gmail search queries by name", json!({"type": "object", "properties": {}})),
+        tool_def("run_saved_query", "Runs a saved Gmail search query by name, like search_emails", run_saved_query_schema()),
+        tool_def("get_new_messages", "Lists inbox messages that arrived since the previous get_new_messages call; the first call only records the starting point", json!({"type": "object", "properties": {}})),
+        tool_def("verify_recipient", "Checks for prior correspondence with addresses before sending, to catch typos or made-up recipients", verify_recipient_schema()),
+        tool_def("modify_email", "Modifies email labels (move to different folders)", modify_email_schema()),
+        tool_def("delete_email", "Permanently deletes an email", delete_email_schema()),
+        tool_def("get_importance", "Reports whether Gmail has marked an email important (the IMPORTANT label); Gmail does not expose why", get_importance_schema()),
+        tool_def("set_importance", "Marks an email important or not important", set_importance_schema()),
+        tool_def("move_email", "Moves an email into a label (adds the label and removes it from the inbox)", move_email_schema()),
+        tool_def("list_email_labels", "Retrieves all available Gmail labels, optionally with message/unread counts", list_labels_schema()),
+        tool_def("batch_modify_emails", "Modifies labels for multiple emails in batches", batch_modify_emails_schema()),
+        tool_def("batch_delete_emails", "Moves multiple emails to trash in batches, or permanently deletes them with permanent: true (requires the https://mail.google.com/ scope, granted with GMAIL_FULL_ACCESS=1)", batch_delete_emails_schema()),
+        tool_def("empty_trash", "Permanently deletes every message in Trash (requires confirm: true and the https://mail.google.com/ scope, granted with GMAIL_FULL_ACCESS=1)", empty_folder_schema("Trash")),
+        tool_def("empty_spam", "Moves every message in Spam to Trash (requires confirm: true)", empty_folder_schema("Spam")),
+        tool_def("create_label", "Creates a new Gmail label", create_label_schema()),
+        tool_def("update_label", "Updates an existing Gmail label", update_label_schema()),
+        tool_def("delete_label", "Deletes a Gmail label", delete_label_schema()),
+        tool_def("get_label", "Gets a label's settings, color and total/unread message counts", get_label_schema()),
+        tool_def("get_or_create_label", "Gets an existing label by name or creates it if it doesn't exist", get_or_create_label_schema()),
+        tool_def("export_labels", "Saves all user labels with their colors and visibility to a JSON file for backup or migration", export_labels_schema()),
+        tool_def("import_labels", "Recreates the labels from an export_labels file, reusing labels that already exist and applying the saved colors and visibility", import_labels_schema()),
+        tool_def("create_filter", "Creates a new Gmail filter with custom criteria and actions", create_filter_schema()),
+        tool_def("list_filters", "Retrieves all Gmail filters", json!({"type": "object", "properties": {}})),
+        tool_def("get_filter", "Gets details of a specific Gmail filter", get_filter_schema()),
+        tool_def("delete_filter", "Deletes a Gmail filter", delete_filter_schema()),
+        tool_def("export_filters", "Saves all filters to a JSON file for backup or migration, with label IDs written as label names", export_filters_schema()),
+        tool_def("import_filters", "Recreates the filters from an export_filters file, creating missing labels and skipping filters that already exist", import_filters_schema()),
+        tool_def("create_filter_from_template", "Creates a filter using a pre-defined template for common scenarios", create_filter_from_template_schema()),
+        tool_def("preview_filter", "Shows how many existing messages match filter criteria (counting stops above 1000), with a sample of the most recent, before the filter is created", preview_filter_schema()),
+        tool_def("apply_filter_to_existing", "Applies a filter's label changes to the existing messages it matches, given a filter ID or criteria with labels (above the server's confirmation threshold, requires confirm: true)", apply_filter_to_existing_schema()),
+        tool_def("download_attachment", "Downloads an email attachment to a specified location", download_attachment_schema()),
+        tool_def("download_all_attachments", "Downloads every attachment of an email into a directory", download_all_attachments_schema()),
+        tool_def("import_email", "Imports a raw MIME message into the mailbox as if it had been received, applying spam classification and filters (the message is not sent)", import_email_schema()),
+        tool_def("insert_email", "Inserts a raw MIME message directly into the mailbox with the given labels, skipping spam classification and filters (the message is not sent); use import_email for normal delivery processing", insert_email_schema()),
+        tool_def("get_unsubscribe_info", "Reads a message's List-Unsubscribe headers and returns its mailto/http unsubscribe targets without acting on them", get_unsubscribe_info_schema()),
+        tool_def("unsubscribe", "Unsubscribes from a message's mailing list with a one-click (RFC 8058) POST, when the sender supports it", unsubscribe_schema()),
+        tool_def("server_info", "Reports the server name and version, MCP protocol version and enabled capabilities", json!({"type": "object", "properties": {}})),
+        tool_def("get_usage", "Reports the estimated Gmail API quota units used by this server session, per API method", json!({"type": "object", "properties": {}})),
+    ]
+}
+
 fn tool_def(name: &str, description: &str, input_schema: Value) -> Tool {
     Tool {
         name: name.to_string(),
@@ -858,7 +3333,7 @@ fn send_email_schema() -> Value {
             "to": {
                 "type": "array",
                 "items": {"type": "string"},
-                "description": "List of recipient email addresses"
+                "description": "List of recipient email addresses or group names from aliases.toml"
             },
             "subject": {
                 "type": "string",
@@ -874,87 +3349,368 @@ fn send_email_schema() -> Value {
             },
             "mimeType": {
                 "type": "string",
-                "enum": ["text/plain", "text/html", "multipart/alternative"],
-                "description": "Email content type"
+                "enum": ["text/plain", "text/html", "multipart/alternative"],
+                "description": "Email content type"
+            },
+            "cc": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "List of CC recipients (addresses or group names)"
+            },
+            "bcc": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "List of BCC recipients (addresses or group names)"
+            },
+            "replyTo": {
+                "type": "string",
+                "description": "Address replies should be sent to"
+            },
+            "threadId": {
+                "type": "string",
+                "description": "Thread ID to reply to; without inReplyTo, the latest message in the thread supplies In-Reply-To and References"
+            },
+            "inReplyTo": {
+                "type": "string",
+                "description": "Message ID being replied to"
+            },
+            "headers": {
+                "type": "object",
+                "additionalProperties": {"type": "string"},
+                "description": "Extra headers to add, e.g. {\"X-Priority\": \"1\"}; standard headers like From, To and MIME-Version cannot be overridden"
+            },
+            "replyToMessageId": {
+                "type": "string",
+                "description": "Gmail ID of the message being replied to; sets threadId, In-Reply-To and References from it"
+            },
+            "attachments": {
+                "type": "array",
+                "items": {
+                    "oneOf": [
+                        {
+                            "type": "string",
+                            "description": "Local file path, or an http(s):// URL to download (up to 25 MB)"
+                        },
+                        {
+                            "type": "object",
+                            "properties": {
+                                "filename": {"type": "string"},
+                                "mimeType": {"type": "string", "description": "Defaults to a guess from the filename extension"},
+                                "dataBase64": {"type": "string", "description": "File content, base64-encoded"}
+                            },
+                            "required": ["filename", "dataBase64"]
+                        }
+                    ]
+                },
+                "description": "Files to attach, as paths/URLs or inline {filename, mimeType, dataBase64} objects"
+            },
+            "labelIds": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Label IDs to apply to the sent copy (send_email only)"
+            },
+            "labelNames": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Label names to apply to the sent copy (send_email only)"
+            },
+            "appendSignature": {
+                "type": "boolean",
+                "description": "Append the configured signature (GMAIL_SIGNATURE) after the body (default: true)"
+            },
+            "fromName": {
+                "type": "string",
+                "description": "Display name shown with your address in From (default: GMAIL_FROM_NAME)"
+            },
+            "idempotencyKey": {
+                "type": "string",
+                "description": "Client-chosen key for safe retries; repeating a successful call with the same key within an hour returns the original result instead of sending again"
+            }
+        },
+        "required": ["to", "subject", "body"]
+    })
+}
+
+fn reply_email_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "messageId": {
+                "type": "string",
+                "description": "ID of the email to reply to; the reply goes to its Reply-To address, or else its sender"
+            },
+            "body": {
+                "type": "string",
+                "description": "Reply body content"
+            },
+            "htmlBody": {
+                "type": "string",
+                "description": "HTML version of the reply body"
+            },
+            "mimeType": {
+                "type": "string",
+                "enum": ["text/plain", "text/html", "multipart/alternative"],
+                "description": "Email content type"
+            },
+            "cc": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "List of CC recipients (addresses or group names)"
+            },
+            "attachments": {
+                "type": "array",
+                "items": {},
+                "description": "Files to attach, as in send_email"
+            },
+            "appendSignature": {
+                "type": "boolean",
+                "description": "Append the configured signature after the body (default: true)"
+            },
+            "fromName": {
+                "type": "string",
+                "description": "Display name shown with your address in From, as in send_email"
+            }
+        },
+        "required": ["messageId", "body"]
+    })
+}
+
+fn batch_read_emails_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "messageIds": {
+                "type": "array",
+                "items": {"type": "string"},
+                "maxItems": MAX_BATCH_READ,
+                "description": "IDs of the email messages to read"
+            },
+            "bodyMaxChars": {
+                "type": "number",
+                "description": "Truncate each body to this many characters, ending with an ellipsis and bodyTruncated: true"
+            },
+            "snippetOnly": {
+                "type": "boolean",
+                "description": "Return headers and Gmail's short snippet instead of bodies, without downloading them (default: false)"
+            }
+        },
+        "required": ["messageIds"]
+    })
+}
+
+fn get_thread_text_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "threadId": {
+                "type": "string",
+                "description": "ID of the thread to render"
+            },
+            "includeDrafts": {
+                "type": "boolean",
+                "description": "Include unsent drafts in the transcript (default: false)"
+            }
+        },
+        "required": ["threadId"]
+    })
+}
+
+fn read_email_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "messageId": {
+                "type": "string",
+                "description": "ID of the email message to retrieve"
+            },
+            "includeHeaders": {
+                "type": "boolean",
+                "description": "Include all raw message headers in original order (default: false)"
+            },
+            "attachmentsOnly": {
+                "type": "boolean",
+                "description": "Return only the attachment list, skipping the body (default: false)"
+            },
+            "plaintext": {
+                "type": "boolean",
+                "description": "For HTML-only emails, return readable text with tags stripped and entities decoded; false returns the raw HTML (default: true)"
+            }
+        },
+        "required": ["messageId"]
+    })
+}
+
+fn get_message_raw_header_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "messageId": {
+                "type": "string",
+                "description": "ID of the email message"
+            },
+            "headers": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Header names to return (default: Authentication-Results, ARC-Authentication-Results, Received-SPF, DKIM-Signature, Received, Return-Path, From)"
+            }
+        },
+        "required": ["messageId"]
+    })
+}
+
+fn search_emails_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "query": {
+                "type": "string",
+                "description": "Gmail search query"
+            },
+            "maxResults": {
+                "type": "number",
+                "description": "Maximum number of results (default: 10 unless configured, at most 500)"
+            },
+            "snippetOnly": {
+                "type": "boolean",
+                "description": "Return only IDs, received dates and snippets, skipping subject/sender headers for a lighter request (default: false)"
+            }
+        },
+        "required": ["query"]
+    })
+}
+
+fn save_query_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": {
+                "type": "string",
+                "description": "Name to save the query under"
+            },
+            "query": {
+                "type": "string",
+                "description": "Gmail search query"
+            },
+            "overwrite": {
+                "type": "boolean",
+                "description": "Replace an existing query with the same name (default: false)"
+            }
+        },
+        "required": ["name", "query"]
+    })
+}
+
+fn run_saved_query_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": {
+                "type": "string",
+                "description": "Name of the saved query"
+            },
+            "maxResults": {
+                "type": "number",
+                "description": "Maximum number of results (default: 10 unless configured, at most 500)"
+            },
+            "snippetOnly": {
+                "type": "boolean",
+                "description": "Return only IDs, received dates and snippets (default: false)"
+            }
+        },
+        "required": ["name"]
+    })
+}
+
+fn verify_recipient_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "emails": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Addresses to check for prior sent or received mail"
+            }
+        },
+        "required": ["emails"]
+    })
+}
+
+fn modify_email_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "messageId": {
+                "type": "string",
+                "description": "ID of the email message to modify"
             },
-            "cc": {
+            "labelIds": {
                 "type": "array",
                 "items": {"type": "string"},
-                "description": "List of CC recipients"
+                "description": "List of label names or IDs to apply"
             },
-            "bcc": {
+            "addLabelIds": {
                 "type": "array",
                 "items": {"type": "string"},
-                "description": "List of BCC recipients"
+                "description": "List of label names or IDs to add"
             },
-            "threadId": {
-                "type": "string",
-                "description": "Thread ID to reply to"
-            },
-            "inReplyTo": {
-                "type": "string",
-                "description": "Message ID being replied to"
+            "removeLabelIds": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "List of label names or IDs to remove"
             }
         },
-        "required": ["to", "subject", "body"]
+        "required": ["messageId"]
     })
 }
 
-fn read_email_schema() -> Value {
+fn get_importance_schema() -> Value {
     json!({
         "type": "object",
         "properties": {
             "messageId": {
                 "type": "string",
-                "description": "ID of the email message to retrieve"
+                "description": "ID of the email message to check"
             }
         },
         "required": ["messageId"]
     })
 }
 
-fn search_emails_schema() -> Value {
+fn set_importance_schema() -> Value {
     json!({
         "type": "object",
         "properties": {
-            "query": {
+            "messageId": {
                 "type": "string",
-                "description": "Gmail search query"
+                "description": "ID of the email message to update"
             },
-            "maxResults": {
-                "type": "number",
-                "description": "Maximum number of results"
+            "important": {
+                "type": "boolean",
+                "description": "true to mark the email important, false to clear the marker"
             }
         },
-        "required": ["query"]
+        "required": ["messageId", "important"]
     })
 }
 
-fn modify_email_schema() -> Value {
+fn move_email_schema() -> Value {
     json!({
         "type": "object",
         "properties": {
             "messageId": {
                 "type": "string",
-                "description": "ID of the email message to modify"
-            },
-            "labelIds": {
-                "type": "array",
-                "items": {"type": "string"},
-                "description": "List of label IDs to apply"
+                "description": "ID of the email message to move"
             },
-            "addLabelIds": {
-                "type": "array",
-                "items": {"type": "string"},
-                "description": "List of label IDs to add"
+            "label": {
+                "type": "string",
+                "description": "Name or ID of the label to move the email into"
             },
-            "removeLabelIds": {
-                "type": "array",
-                "items": {"type": "string"},
-                "description": "List of label IDs to remove"
+            "keepInInbox": {
+                "type": "boolean",
+                "description": "Only add the label, leaving the email in the inbox (default: false)"
             }
         },
-        "required": ["messageId"]
+        "required": ["messageId", "label"]
     })
 }
 
@@ -999,6 +3755,36 @@ fn batch_modify_emails_schema() -> Value {
     })
 }
 
+fn send_email_batch_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "messages": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "to": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Recipients of this message"
+                        },
+                        "subject": {"type": "string"},
+                        "body": {"type": "string"}
+                    },
+                    "required": ["to", "subject", "body"]
+                },
+                "description": "Messages to send, each as a separate email"
+            },
+            "intervalMs": {
+                "type": "number",
+                "description": "Pause between sends in milliseconds, to stay under Gmail's sending rate limits (default: 500, max: 60000)"
+            }
+        },
+        "required": ["messages"]
+    })
+}
+
 fn batch_delete_emails_schema() -> Value {
     json!({
         "type": "object",
@@ -1010,13 +3796,34 @@ fn batch_delete_emails_schema() -> Value {
             },
             "batchSize": {
                 "type": "number",
-                "description": "Batch size (default: 50)"
+                "description": "Batch size (default: 50, or 1000 when permanent)"
+            },
+            "permanent": {
+                "type": "boolean",
+                "description": "Permanently delete with one batchDelete call per batch instead of trashing each message; cannot be undone and needs the https://mail.google.com/ scope, granted with GMAIL_FULL_ACCESS=1 (default: false)"
+            },
+            "confirm": {
+                "type": "boolean",
+                "description": "Must be true to delete more messages than the server's confirmation threshold (default: 100)"
             }
         },
         "required": ["messageIds"]
     })
 }
 
+fn empty_folder_schema(folder: &str) -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "confirm": {
+                "type": "boolean",
+                "description": format!("Must be true to empty {}", folder)
+            }
+        },
+        "required": ["confirm"]
+    })
+}
+
 fn create_label_schema() -> Value {
     json!({
         "type": "object",
@@ -1078,6 +3885,36 @@ fn delete_label_schema() -> Value {
     })
 }
 
+fn list_labels_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "type": {
+                "type": "string",
+                "enum": ["system", "user", "all"],
+                "description": "Only list labels of this type (default: all)"
+            },
+            "includeCounts": {
+                "type": "boolean",
+                "description": "Fetch message/unread counts for each label (default: false; one extra API call per label)"
+            }
+        }
+    })
+}
+
+fn get_label_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "id": {
+                "type": "string",
+                "description": "ID or name of the label"
+            }
+        },
+        "required": ["id"]
+    })
+}
+
 fn get_or_create_label_schema() -> Value {
     json!({
         "type": "object",
@@ -1120,9 +3957,15 @@ fn create_filter_schema() -> Value {
             "action": {
                 "type": "object",
                 "properties": {
-                    "addLabelIds": {"type": "array", "items": {"type": "string"}},
-                    "removeLabelIds": {"type": "array", "items": {"type": "string"}},
-                    "forward": {"type": "string"}
+                    "addLabelIds": {"type": "array", "items": {"type": "string"}, "description": "Label names or IDs to add (missing labels are created)"},
+                    "removeLabelIds": {"type": "array", "items": {"type": "string"}, "description": "Label names or IDs to remove"},
+                    "forward": {"type": "string"},
+                    "markImportant": {"type": "boolean", "description": "Always mark as important"},
+                    "neverSpam": {"type": "boolean", "description": "Never send to spam"},
+                    "markRead": {"type": "boolean", "description": "Mark as read"},
+                    "archive": {"type": "boolean", "description": "Skip the inbox"},
+                    "star": {"type": "boolean", "description": "Star the message"},
+                    "delete": {"type": "boolean", "description": "Move to trash"}
                 }
             }
         },
@@ -1156,13 +3999,138 @@ fn delete_filter_schema() -> Value {
     })
 }
 
+fn export_labels_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "path": {
+                "type": "string",
+                "description": "JSON file to write the labels to"
+            }
+        },
+        "required": ["path"]
+    })
+}
+
+fn import_labels_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "path": {
+                "type": "string",
+                "description": "JSON file written by export_labels"
+            }
+        },
+        "required": ["path"]
+    })
+}
+
+fn export_filters_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "path": {
+                "type": "string",
+                "description": "JSON file to write the filters to"
+            }
+        },
+        "required": ["path"]
+    })
+}
+
+fn import_filters_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "path": {
+                "type": "string",
+                "description": "JSON file written by export_filters"
+            }
+        },
+        "required": ["path"]
+    })
+}
+
+fn preview_filter_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "criteria": {
+                "type": "object",
+                "description": "Filter criteria, as passed to create_filter",
+                "properties": {
+                    "from": {"type": "string"},
+                    "to": {"type": "string"},
+                    "subject": {"type": "string"},
+                    "query": {"type": "string"},
+                    "negatedQuery": {"type": "string"},
+                    "hasAttachment": {"type": "boolean"},
+                    "excludeChats": {"type": "boolean"},
+                    "size": {"type": "number"},
+                    "sizeComparison": {"type": "string", "enum": ["unspecified", "smaller", "larger"]}
+                }
+            },
+            "maxResults": {
+                "type": "number",
+                "description": "Matching messages to show (default: the server's search default, at most 500)"
+            }
+        },
+        "required": ["criteria"]
+    })
+}
+
+fn apply_filter_to_existing_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "filterId": {
+                "type": "string",
+                "description": "ID of an existing filter whose criteria and label action to apply"
+            },
+            "criteria": {
+                "type": "object",
+                "description": "Filter criteria, as passed to create_filter (instead of filterId)",
+                "properties": {
+                    "from": {"type": "string"},
+                    "to": {"type": "string"},
+                    "subject": {"type": "string"},
+                    "query": {"type": "string"},
+                    "negatedQuery": {"type": "string"},
+                    "hasAttachment": {"type": "boolean"},
+                    "excludeChats": {"type": "boolean"},
+                    "size": {"type": "number"},
+                    "sizeComparison": {"type": "string", "enum": ["unspecified", "smaller", "larger"]}
+                }
+            },
+            "addLabelIds": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Label names or IDs to add (with criteria)"
+            },
+            "removeLabelIds": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Label names or IDs to remove (with criteria)"
+            },
+            "batchSize": {
+                "type": "number",
+                "description": "Batch size (default: 50)"
+            },
+            "confirm": {
+                "type": "boolean",
+                "description": "Must be true to relabel more messages than the server's confirmation threshold (default: 100)"
+            }
+        }
+    })
+}
+
 fn create_filter_from_template_schema() -> Value {
     json!({
         "type": "object",
         "properties": {
             "template": {
                 "type": "string",
-                "enum": ["fromSender", "withSubject", "withAttachments", "largeEmails", "containingText", "mailingList"],
+                "enum": ["fromSender", "withSubject", "withAttachments", "largeEmails", "containingText", "mailingList", "deleteFromSender"],
                 "description": "Pre-defined filter template"
             },
             "parameters": {
@@ -1172,6 +4140,7 @@ fn create_filter_from_template_schema() -> Value {
                     "senderEmail": {"type": "string"},
                     "subjectText": {"type": "string"},
                     "searchText": {"type": "string"},
+                    "field": {"type": "string", "enum": ["anywhere", "subject", "body", "from"]},
                     "listIdentifier": {"type": "string"},
                     "sizeInBytes": {"type": "number"},
                     "labelIds": {"type": "array", "items": {"type": "string"}},
@@ -1180,13 +4149,14 @@ fn create_filter_from_template_schema() -> Value {
                     "markImportant": {"type": "boolean"}
                 }
             },
-            "senderEmail": {"type": "string", "description": "Email address for fromSender template"},
+            "senderEmail": {"type": "string", "description": "Email address for fromSender and deleteFromSender templates"},
             "subjectText": {"type": "string", "description": "Subject text for withSubject template"},
             "searchText": {"type": "string", "description": "Search text for containingText template"},
+            "field": {"type": "string", "enum": ["anywhere", "subject", "body", "from"], "description": "Where containingText searches (default: anywhere). body excludes subject matches, so it misses messages with the text in both body and subject"},
             "listIdentifier": {"type": "string", "description": "List ID for mailingList template"},
             "sizeInBytes": {"type": "number", "description": "Size threshold for largeEmails template"},
-            "labelIds": {"type": "array", "items": {"type": "string"}, "description": "Labels to apply"},
-            "labelId": {"type": "string", "description": "Single label to apply (alternative to labelIds)"},
+            "labelIds": {"type": "array", "items": {"type": "string"}, "description": "Label names or IDs to apply (missing labels are created)"},
+            "labelId": {"type": "string", "description": "Single label name or ID to apply (alternative to labelIds)"},
             "archive": {"type": "boolean", "description": "Whether to archive matching emails"},
             "markAsRead": {"type": "boolean", "description": "Whether to mark matching emails as read"},
             "markImportant": {"type": "boolean", "description": "Whether to mark matching emails as important"}
@@ -1220,3 +4190,106 @@ fn download_attachment_schema() -> Value {
     })
 }
 
+fn download_all_attachments_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "messageId": {
+                "type": "string",
+                "description": "ID of the email containing the attachments"
+            },
+            "savePath": {
+                "type": "string",
+                "description": "Directory to save to (default: current directory)"
+            },
+            "mimeTypes": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Only download attachments declared with one of these MIME types; a type ending in /* matches its family, e.g. image/*"
+            },
+            "extensions": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Only download attachments whose filename has one of these extensions, e.g. pdf (combined with mimeTypes, either may match)"
+            }
+        },
+        "required": ["messageId"]
+    })
+}
+
+fn import_email_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "rawPath": {
+                "type": "string",
+                "description": "Path to an RFC 822 (.eml) file to import"
+            },
+            "raw": {
+                "type": "string",
+                "description": "Raw RFC 822 message (alternative to rawPath)"
+            },
+            "labelIds": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Label IDs to apply (e.g. INBOX, UNREAD)"
+            },
+            "neverMarkSpam": {
+                "type": "boolean",
+                "description": "Never send the message to spam (default: false)"
+            },
+            "internalDateSource": {
+                "type": "string",
+                "enum": ["receivedTime", "dateHeader"],
+                "description": "Source for the message's internal date (default: dateHeader)"
+            }
+        }
+    })
+}
+
+fn insert_email_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "rawPath": {
+                "type": "string",
+                "description": "Path to an RFC 822 (.eml) file to insert"
+            },
+            "raw": {
+                "type": "string",
+                "description": "Raw RFC 822 message (alternative to rawPath)"
+            },
+            "labelIds": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Label IDs to apply (e.g. INBOX, UNREAD)"
+            }
+        }
+    })
+}
+
+fn get_unsubscribe_info_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "messageId": {
+                "type": "string",
+                "description": "ID of the email to inspect"
+            }
+        },
+        "required": ["messageId"]
+    })
+}
+
+fn unsubscribe_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "messageId": {
+                "type": "string",
+                "description": "ID of an email from the mailing list"
+            }
+        },
+        "required": ["messageId"]
+    })
+}
@@ -11,6 +11,9 @@ pub const JSONRPC_VERSION: &str = "2.0";
 /// MCP protocol version
 pub const MCP_VERSION: &str = "2024-11-05";
 
+/// Protocol versions this server can speak, oldest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &[MCP_VERSION, "2025-03-26"];
+
 /// JSON-RPC request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcRequest {
@@ -70,7 +73,6 @@ impl JsonRpcResponse {
 
 /// JSON-RPC notification (no id, no response expected)
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[allow(dead_code)] // Reserved for future notification support
 pub struct JsonRpcNotification {
     /// JSON-RPC version
     pub jsonrpc: String,
@@ -83,6 +85,18 @@ pub struct JsonRpcNotification {
     pub params: Option<Value>,
 }
 
+/// Params of a `notifications/cancelled` notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelledParams {
+    /// ID of the request to cancel
+    pub request_id: RequestId,
+
+    /// Optional reason for the cancellation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
 /// Request ID (can be string or number)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(untagged)]
@@ -135,7 +149,6 @@ impl JsonRpcError {
     }
 
     /// Invalid params (-32602)
-    #[allow(dead_code)]
     pub fn invalid_params(message: impl Into<String>) -> Self {
         Self {
             code: -32602,
@@ -145,7 +158,6 @@ impl JsonRpcError {
     }
 
     /// Internal error (-32603)
-    #[allow(dead_code)]
     pub fn internal_error(message: impl Into<String>) -> Self {
         Self {
             code: -32603,
@@ -181,12 +193,27 @@ pub struct ServerCapabilities {
     pub prompts: Option<PromptsCapability>,
 }
 
+impl ServerCapabilities {
+    /// Names of the enabled capabilities
+    pub fn enabled(&self) -> Vec<&'static str> {
+        [
+            self.tools.is_some().then_some("tools"),
+            self.resources.is_some().then_some("resources"),
+            self.prompts.is_some().then_some("prompts"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
 /// Tools capability marker
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ToolsCapability {}
 
 /// Resources capability marker
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct ResourcesCapability {
     #[serde(default)]
     pub subscribe: bool,
@@ -197,6 +224,7 @@ pub struct ResourcesCapability {
 
 /// Prompts capability marker
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct PromptsCapability {
     #[serde(default)]
     pub list_changed: bool,
@@ -205,21 +233,21 @@ pub struct PromptsCapability {
 /// Initialize request params
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-#[allow(dead_code)] // Reserved for parsing client init requests
 pub struct InitializeParams {
     /// Protocol version
     pub protocol_version: String,
 
     /// Client info
+    #[serde(default)]
     pub client_info: ClientInfo,
 
     /// Client capabilities
+    #[serde(default)]
     pub capabilities: ClientCapabilities,
 }
 
 /// Client info
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[allow(dead_code)] // Used by InitializeParams
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ClientInfo {
     /// Client name
     pub name: String,
@@ -230,7 +258,6 @@ pub struct ClientInfo {
 
 /// Client capabilities
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[allow(dead_code)] // Used by InitializeParams
 pub struct ClientCapabilities {
     /// Experimental capabilities
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -286,6 +313,34 @@ pub struct CallToolParams {
     /// Tool arguments
     #[serde(default)]
     pub arguments: Value,
+
+    /// Request metadata
+    #[serde(default, rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<RequestMeta>,
+}
+
+/// Request metadata (`_meta`)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestMeta {
+    /// Token the client wants progress notifications tagged with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress_token: Option<Value>,
+}
+
+/// Params of a `notifications/progress` notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressParams {
+    /// Progress token from the originating request
+    pub progress_token: Value,
+
+    /// Work completed so far
+    pub progress: u64,
+
+    /// Total amount of work, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
 }
 
 /// Tool result content item
@@ -307,6 +362,7 @@ pub enum ToolResultContent {
 
 /// Resource content
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ResourceContent {
     /// Resource URI
     pub uri: String,
@@ -324,6 +380,115 @@ pub struct ResourceContent {
     pub mime_type: Option<String>,
 }
 
+/// Resource definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Resource {
+    /// Resource URI
+    pub uri: String,
+
+    /// Human-readable name
+    pub name: String,
+
+    /// Resource description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// MIME type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// List resources result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListResourcesResult {
+    /// Available resources
+    pub resources: Vec<Resource>,
+}
+
+/// Read resource params
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadResourceParams {
+    /// Resource URI
+    pub uri: String,
+}
+
+/// Read resource result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadResourceResult {
+    /// Resource contents
+    pub contents: Vec<ResourceContent>,
+}
+
+/// Prompt definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prompt {
+    /// Prompt name
+    pub name: String,
+
+    /// Prompt description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Arguments accepted by the prompt
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arguments: Vec<PromptArgument>,
+}
+
+/// Prompt argument definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptArgument {
+    /// Argument name
+    pub name: String,
+
+    /// Argument description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Whether the argument must be provided
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// List prompts result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListPromptsResult {
+    /// Available prompts
+    pub prompts: Vec<Prompt>,
+}
+
+/// Get prompt params
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetPromptParams {
+    /// Prompt name
+    pub name: String,
+
+    /// Prompt arguments
+    #[serde(default)]
+    pub arguments: std::collections::HashMap<String, String>,
+}
+
+/// Message returned as part of a prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptMessage {
+    /// Message role ("user" or "assistant")
+    pub role: String,
+
+    /// Message content
+    pub content: ToolResultContent,
+}
+
+/// Get prompt result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetPromptResult {
+    /// Prompt description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Prompt messages
+    pub messages: Vec<PromptMessage>,
+}
+
 /// Call tool result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -354,14 +519,38 @@ impl CallToolResult {
             is_error: true,
         }
     }
+
+    /// Cut text content longer than `max_chars` characters, ending it with a
+    /// `[truncated N chars]` marker; 0 leaves the result untouched
+    pub fn truncated(mut self, max_chars: usize) -> Self {
+        if max_chars == 0 {
+            return self;
+        }
+        for content in &mut self.content {
+            if let ToolResultContent::Text { text } = content {
+                if let Some((cut, _)) = text.char_indices().nth(max_chars) {
+                    let dropped = text[cut..].chars().count();
+                    text.truncate(cut);
+                    text.push_str(&format!("\n\n[truncated {} chars]", dropped));
+                }
+            }
+        }
+        self
+    }
 }
 
 /// MCP methods
 pub mod methods {
     pub const INITIALIZE: &str = "initialize";
     pub const INITIALIZED: &str = "notifications/initialized";
+    pub const CANCELLED: &str = "notifications/cancelled";
+    pub const PROGRESS: &str = "notifications/progress";
     pub const LIST_TOOLS: &str = "tools/list";
     pub const CALL_TOOL: &str = "tools/call";
+    pub const LIST_RESOURCES: &str = "resources/list";
+    pub const READ_RESOURCE: &str = "resources/read";
+    pub const LIST_PROMPTS: &str = "prompts/list";
+    pub const GET_PROMPT: &str = "prompts/get";
     pub const PING: &str = "ping";
 }
 
@@ -369,6 +558,29 @@ pub mod methods {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_call_tool_result_truncated() {
+        let text = |result: CallToolResult| match &result.content[0] {
+            ToolResultContent::Text { text } => text.clone(),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(text(CallToolResult::text("héllo wörld").truncated(5)), "héllo\n\n[truncated 6 chars]");
+        assert_eq!(text(CallToolResult::text("short").truncated(5)), "short");
+        assert_eq!(text(CallToolResult::text("unlimited").truncated(0)), "unlimited");
+    }
+
+    #[test]
+    fn test_server_capabilities_enabled() {
+        let caps = ServerCapabilities {
+            tools: Some(ToolsCapability {}),
+            resources: None,
+            prompts: Some(PromptsCapability::default()),
+        };
+        assert_eq!(caps.enabled(), vec!["tools", "prompts"]);
+        assert!(ServerCapabilities::default().enabled().is_empty());
+    }
+
     #[test]
     fn test_request_deserialize() {
         let json = r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#;
@@ -385,6 +597,14 @@ mod tests {
         assert!(!json.contains("\"error\""));
     }
 
+    #[test]
+    fn test_cancelled_params_deserialize() {
+        let json = r#"{"requestId":"req-7","reason":"user aborted"}"#;
+        let params: CancelledParams = serde_json::from_str(json).unwrap();
+        assert_eq!(params.request_id, RequestId::String("req-7".to_string()));
+        assert_eq!(params.reason.as_deref(), Some("user aborted"));
+    }
+
     #[test]
     fn test_tool_result() {
         let result = CallToolResult::text("Hello");
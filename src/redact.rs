@@ -0,0 +1,129 @@
+//! Redaction of personal data in log output
+//!
+//! Gmail error bodies and tool errors can echo back addresses, subjects and
+//! message content. Before such text is logged, email addresses are masked and
+//! long text is truncated, so retained logs from shared deployments don't
+//! accumulate mailbox data. Controlled by `GMAIL_LOG_REDACT` (on by default).
+
+/// Longest text kept in a redacted log line, in characters
+pub const MAX_LOGGED_CHARS: usize = 200;
+
+/// Placeholder that replaces each email address
+const EMAIL_PLACEHOLDER: &str = "<email>";
+
+/// Parse the `GMAIL_LOG_REDACT` value; anything but an explicit "off" keeps redaction on
+pub fn redaction_enabled(value: Option<&str>) -> bool {
+    !matches!(
+        value.map(|v| v.trim().to_ascii_lowercase()).as_deref(),
+        Some("0" | "false" | "off" | "no")
+    )
+}
+
+/// Prepare `text` for logging, redacting it when `enabled`
+pub fn for_log(text: &str, enabled: bool) -> String {
+    if enabled {
+        redact(text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Mask email addresses in `text` and truncate it to `MAX_LOGGED_CHARS`
+pub fn redact(text: &str) -> String {
+    truncate(&mask_emails(text), MAX_LOGGED_CHARS)
+}
+
+/// Replace everything that looks like an email address with a placeholder
+pub fn mask_emails(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] != '@' {
+            i += 1;
+            continue;
+        }
+
+        let mut start = i;
+        while start > copied && is_local_char(chars[start - 1]) {
+            start -= 1;
+        }
+
+        let mut end = i + 1;
+        while end < chars.len() && is_domain_char(chars[end]) {
+            end += 1;
+        }
+        // Don't swallow sentence punctuation after the domain
+        while end > i + 1 && matches!(chars[end - 1], '.' | '-') {
+            end -= 1;
+        }
+
+        let domain: String = chars[i + 1..end].iter().collect();
+        if start < i && domain.contains('.') && !domain.starts_with('.') {
+            out.extend(&chars[copied..start]);
+            out.push_str(EMAIL_PLACEHOLDER);
+            copied = end;
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+
+    out.extend(&chars[copied..]);
+    out
+}
+
+/// Cut `text` to at most `max_chars` characters, noting how much was dropped
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((cut, _)) => format!(
+            "{}... [{} more chars redacted]",
+            &text[..cut],
+            text[cut..].chars().count()
+        ),
+        None => text.to_string(),
+    }
+}
+
+fn is_local_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '%' | '+' | '-')
+}
+
+fn is_domain_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_emails() {
+        assert_eq!(
+            mask_emails("Invalid To header: john.doe+news@example.com, <jane@mail.example.org>."),
+            "Invalid To header: <email>, <<email>>."
+        );
+        assert_eq!(mask_emails("no addresses @ here or user@localhost"), "no addresses @ here or user@localhost");
+    }
+
+    #[test]
+    fn test_redact_truncates_long_text() {
+        let body = "x".repeat(MAX_LOGGED_CHARS + 50);
+        let redacted = redact(&body);
+        assert!(redacted.starts_with(&"x".repeat(MAX_LOGGED_CHARS)));
+        assert!(redacted.ends_with("[50 more chars redacted]"));
+        assert_eq!(redact("short"), "short");
+    }
+
+    #[test]
+    fn test_redaction_enabled() {
+        assert!(redaction_enabled(None));
+        assert!(redaction_enabled(Some("1")));
+        assert!(!redaction_enabled(Some("false")));
+        assert!(!redaction_enabled(Some(" OFF ")));
+        assert_eq!(for_log("a@b.com", false), "a@b.com");
+        assert_eq!(for_log("a@b.com", true), "<email>");
+    }
+}
@@ -253,13 +253,7 @@ mod email_utils_tests {
             to: vec!["test@example.com".to_string()],
             subject: "Test Subject".to_string(),
             body: "Test body".to_string(),
-            html_body: None,
-            mime_type: None,
-            cc: None,
-            bcc: None,
-            thread_id: None,
-            in_reply_to: None,
-            attachments: None,
+            ..Default::default()
         };
 
         let result = create_email_message(&params).unwrap();
@@ -277,11 +271,7 @@ mod email_utils_tests {
             body: "Plain text version".to_string(),
             html_body: Some("<h1>HTML Version</h1>".to_string()),
             mime_type: Some(MimeType::MultipartAlternative),
-            cc: None,
-            bcc: None,
-            thread_id: None,
-            in_reply_to: None,
-            attachments: None,
+            ..Default::default()
         };
 
         let result = create_email_message(&params).unwrap();
@@ -296,13 +286,9 @@ mod email_utils_tests {
             to: vec!["to@example.com".to_string()],
             subject: "Test".to_string(),
             body: "Body".to_string(),
-            html_body: None,
-            mime_type: None,
             cc: Some(vec!["cc@example.com".to_string()]),
             bcc: Some(vec!["bcc@example.com".to_string()]),
-            thread_id: None,
-            in_reply_to: None,
-            attachments: None,
+            ..Default::default()
         };
 
         let result = create_email_message(&params).unwrap();
@@ -316,13 +302,9 @@ mod email_utils_tests {
             to: vec!["to@example.com".to_string()],
             subject: "Re: Original".to_string(),
             body: "Reply body".to_string(),
-            html_body: None,
-            mime_type: None,
-            cc: None,
-            bcc: None,
             thread_id: Some("thread123".to_string()),
             in_reply_to: Some("<original@example.com>".to_string()),
-            attachments: None,
+            ..Default::default()
         };
 
         let result = create_email_message(&params).unwrap();
@@ -330,19 +312,70 @@ mod email_utils_tests {
         assert!(result.contains("References: <original@example.com>"));
     }
 
+    #[test]
+    fn test_create_email_with_references_chain() {
+        let params = EmailParams {
+            to: vec!["to@example.com".to_string()],
+            subject: "Re: Original".to_string(),
+            body: "Reply body".to_string(),
+            thread_id: Some("thread123".to_string()),
+            in_reply_to: Some("<second@example.com>".to_string()),
+            references: Some(build_references(
+                "<second@example.com>",
+                Some("<first@example.com>"),
+                None,
+            )),
+            ..Default::default()
+        };
+
+        let result = create_email_message(&params).unwrap();
+        assert!(result.contains("In-Reply-To: <second@example.com>"));
+        assert!(result.contains("References: <first@example.com> <second@example.com>"));
+    }
+
+    #[test]
+    fn test_create_email_with_reply_to() {
+        let mut params = EmailParams {
+            to: vec!["to@example.com".to_string()],
+            subject: "Test".to_string(),
+            body: "Body".to_string(),
+            reply_to: Some("team@example.com".to_string()),
+            ..Default::default()
+        };
+
+        let result = create_email_message(&params).unwrap();
+        assert!(result.contains("Reply-To: team@example.com"));
+
+        params.reply_to = Some("not an address".to_string());
+        assert!(create_email_message(&params).is_err());
+    }
+
+    #[test]
+    fn test_header_injection_is_neutralized() {
+        let params = EmailParams {
+            to: vec!["to@example.com".to_string()],
+            subject: "Hello\r\nBcc: attacker@example.com".to_string(),
+            body: "Body".to_string(),
+            cc: Some(vec!["cc@example.com\r\nX-Injected: yes".to_string()]),
+            in_reply_to: Some("<a@example.com>\r\nX-Injected: yes".to_string()),
+            ..Default::default()
+        };
+
+        let result = create_email_message(&params).unwrap();
+        let header_block = result.split("\r\n\r\n").next().unwrap();
+        for line in header_block.split("\r\n") {
+            assert!(!line.starts_with("Bcc:"), "injected Bcc header: {}", line);
+            assert!(!line.starts_with("X-Injected:"), "injected header: {}", line);
+        }
+    }
+
     #[test]
     fn test_email_validation_rejects_invalid() {
         let params = EmailParams {
             to: vec!["invalid-email".to_string()],
             subject: "Test".to_string(),
             body: "Body".to_string(),
-            html_body: None,
-            mime_type: None,
-            cc: None,
-            bcc: None,
-            thread_id: None,
-            in_reply_to: None,
-            attachments: None,
+            ..Default::default()
         };
 
         let result = create_email_message(&params);
@@ -423,6 +456,15 @@ mod filter_template_tests {
         assert!(criteria.query.as_ref().unwrap().contains("rust-users"));
         assert_eq!(action.remove_label_ids, Some(vec!["INBOX".to_string()]));
     }
+
+    #[test]
+    fn test_delete_from_sender_template() {
+        let (criteria, action) = FilterTemplates::delete_from_sender("spam@example.com");
+
+        assert_eq!(criteria.from, Some("spam@example.com".to_string()));
+        assert_eq!(action.add_label_ids, Some(vec!["TRASH".to_string()]));
+        assert_eq!(action.remove_label_ids, Some(vec!["INBOX".to_string()]));
+    }
 }
 
 mod types_serialization_tests {
@@ -556,3 +598,377 @@ mod mcp_types_tests {
     }
 }
 
+
+mod tool_handler_tests {
+    use std::sync::Arc;
+
+    use gmail_mcp_server_rust::config::gmail::scopes;
+    use gmail_mcp_server_rust::gmail::api::GmailApi;
+    use gmail_mcp_server_rust::gmail::mock::MockGmailClient;
+    use gmail_mcp_server_rust::gmail::types::{LabelColor, UpdateLabelRequest};
+    use gmail_mcp_server_rust::mcp::access::{ToolAccess, WRITE_TOOLS};
+    use gmail_mcp_server_rust::mcp::tools::{ProgressReporter, ToolContext, ToolHandler};
+    use gmail_mcp_server_rust::mcp::types::CallToolResult;
+    use serde_json::{json, Value};
+
+    async fn call(handler: &ToolHandler, name: &str, args: Value) -> CallToolResult {
+        handler.call_tool(name, args, &ToolContext::default()).await
+    }
+
+    fn text(result: &CallToolResult) -> String {
+        serde_json::to_value(&result.content).unwrap()[0]["text"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_search_and_read_email() {
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample()));
+
+        let search = call(&handler, "search_emails", json!({"query": "in:inbox", "maxResults": 2})).await;
+        assert!(!search.is_error);
+        assert_eq!(
+            text(&search),
+            "ID: 0000000000000003\nSubject: Weekly digest\nFrom: Newsletter <news@example.org>\n\
+             Date: Thu, 01 Jan 2026 00:02:00 +0000\nReceived: 2026-01-01T00:02:00Z\n\n\
+             ID: 0000000000000002\nSubject: Invoice #1042\nFrom: Bob Example <bob@example.com>\n\
+             Date: Thu, 01 Jan 2026 00:01:00 +0000\nReceived: 2026-01-01T00:01:00Z\n"
+        );
+
+        let read = call(&handler, "read_email", json!({"messageId": "0000000000000001"})).await;
+        let read = text(&read);
+        assert!(read.starts_with("Thread ID: 0000000000000001\nSubject: Project kickoff\n"), "{}", read);
+        assert!(read.contains("Labels: INBOX, UNREAD"), "{}", read);
+        assert!(read.ends_with("The kickoff meeting is on Monday at 10:00.\n\nAlice"), "{}", read);
+
+        let missing = call(&handler, "read_email", json!({"messageId": "ffff"})).await;
+        assert!(missing.is_error);
+    }
+
+    #[tokio::test]
+    async fn test_reply_lands_in_thread() {
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample()));
+
+        let reply = call(&handler, "reply_email", json!({"messageId": "0000000000000001", "body": "Thanks"})).await;
+        assert!(text(&reply).starts_with("Email sent successfully with ID: 0000000000000004"));
+
+        let thread = call(&handler, "get_thread_text", json!({"threadId": "0000000000000001"})).await;
+        assert!(text(&thread).ends_with("From me@example.com on Thu, 01 Jan 2026 00:03:00 +0000:\nThanks"));
+    }
+
+    #[tokio::test]
+    async fn test_send_to_draft_only_thread_falls_back_to_thread_id() {
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample()));
+
+        let draft = call(&handler, "draft_email", json!({"to": ["bob@example.com"], "subject": "Plan", "body": "Draft"})).await;
+        assert_eq!(text(&draft), "Email draft created successfully with ID: r5");
+
+        let sent = call(
+            &handler,
+            "send_email",
+            json!({"to": ["bob@example.com"], "subject": "Plan", "body": "Sent", "threadId": "0000000000000004"}),
+        )
+        .await;
+        assert!(!sent.is_error, "{}", text(&sent));
+        assert!(text(&sent).contains("\nNote: no message to reply to was found in thread 0000000000000004"), "{}", text(&sent));
+    }
+
+    #[tokio::test]
+    async fn test_labels() {
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample()));
+
+        let created = call(&handler, "create_label", json!({"name": "Work"})).await;
+        assert_eq!(text(&created), "Label created successfully:\nID: Label_4\nName: Work\nType: user");
+
+        let modified = call(
+            &handler,
+            "modify_email",
+            json!({"messageId": "0000000000000002", "addLabelIds": ["Work"], "removeLabelIds": ["UNREAD"]}),
+        )
+        .await;
+        assert!(!modified.is_error, "{}", text(&modified));
+
+        let labels = text(&call(&handler, "list_email_labels", json!({})).await);
+        assert!(labels.starts_with("Found 9 labels (8 system, 1 user):"), "{}", labels);
+        assert!(labels.contains("Name: Work\n"), "{}", labels);
+
+        let counted = text(&call(&handler, "list_email_labels", json!({"includeCounts": true})).await);
+        assert!(counted.contains("Name: Work (1 messages, 0 unread)"), "{}", counted);
+        assert!(counted.contains("Name: INBOX ("), "{}", counted);
+    }
+
+    #[tokio::test]
+    async fn test_send_email_batch_reports_failures() {
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample()));
+        let messages = json!([
+            {"to": ["bob@example.com"], "subject": "One", "body": "1"},
+            {"to": ["not-an-address"], "subject": "Two", "body": "2"},
+            {"to": ["carol@example.com"], "subject": "Three", "body": "3"}
+        ]);
+
+        let sent = call(&handler, "send_email_batch", json!({"messages": messages, "intervalMs": 0})).await;
+        let sent = text(&sent);
+        assert!(sent.starts_with("Batch send complete.\nSent: 2 messages\n"), "{}", sent);
+        assert!(sent.contains("Failed to send: 1 messages\n- #2 to not-an-address ("), "{}", sent);
+    }
+
+    #[tokio::test]
+    async fn test_send_email_batch_stops_when_cancelled() {
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample()));
+        let messages = json!([
+            {"to": ["bob@example.com"], "subject": "One", "body": "1"},
+            {"to": ["carol@example.com"], "subject": "Two", "body": "2"}
+        ]);
+
+        // Cancel as soon as the first message reports progress
+        let ctx = ToolContext::default();
+        let cancel = ctx.cancel.clone();
+        let ctx = ToolContext {
+            progress: Some(ProgressReporter::new(json!("batch"), move |_| cancel.cancel())),
+            ..ctx
+        };
+        let result = handler
+            .call_tool("send_email_batch", json!({"messages": messages, "intervalMs": 0}), &ctx)
+            .await;
+        assert_eq!(
+            text(&result),
+            "Batch send cancelled.\nProcessed before cancellation: 1 of 2 messages\nSent: 1 messages\n\n\
+             Sent message IDs:\n- 0000000000000004\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_import_labels_round_trip() {
+        let color = LabelColor {
+            text_color: Some("#ffffff".to_string()),
+            background_color: Some("#16a766".to_string()),
+        };
+        let source = Arc::new(MockGmailClient::sample());
+        let work = source.create_label("Work", Some("hide"), Some("labelShowIfUnread")).await.unwrap();
+        let updates = UpdateLabelRequest {
+            name: None,
+            message_list_visibility: None,
+            label_list_visibility: None,
+            color: Some(color),
+        };
+        source.update_label(&work.id, updates).await.unwrap();
+        source.create_label("Receipts", None, None).await.unwrap();
+
+        let path = std::env::temp_dir().join(format!("gmail-mcp-labels-{}.json", uuid::Uuid::new_v4()));
+        let path_arg = json!({"path": path.to_str().unwrap()});
+        let exported = call(&ToolHandler::new(source), "export_labels", path_arg.clone()).await;
+        assert!(text(&exported).starts_with("Exported 2 labels"), "{}", text(&exported));
+
+        // The target already has a Work label, which should be reused and updated
+        let target = Arc::new(MockGmailClient::sample());
+        let existing = target.create_label("work", None, None).await.unwrap();
+        let imported = call(&ToolHandler::new(target.clone()), "import_labels", path_arg).await;
+        std::fs::remove_file(&path).unwrap();
+        assert!(text(&imported).starts_with("Imported 2 labels"), "{}", text(&imported));
+
+        let labels = target.list_labels().await.unwrap();
+        assert_eq!(labels.count.user, 2);
+        let work = labels.user.iter().find(|l| l.id == existing.id).unwrap();
+        let color = work.color.as_ref().unwrap();
+        assert_eq!(color.text_color.as_deref(), Some("#ffffff"));
+        assert_eq!(color.background_color.as_deref(), Some("#16a766"));
+        assert_eq!(work.message_list_visibility.as_deref(), Some("hide"));
+        assert_eq!(work.label_list_visibility.as_deref(), Some("labelShowIfUnread"));
+        assert!(labels.user.iter().any(|l| l.name == "Receipts"));
+    }
+
+    #[tokio::test]
+    async fn test_empty_trash_reports_counts() {
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample()));
+
+        let trashed = call(&handler, "delete_email", json!({"messageId": "0000000000000001"})).await;
+        assert!(!trashed.is_error, "{}", text(&trashed));
+
+        assert!(call(&handler, "empty_trash", json!({})).await.is_error);
+        let emptied = call(&handler, "empty_trash", json!({"confirm": true})).await;
+        assert_eq!(text(&emptied), "Trash emptied.\nPermanently deleted: 1 messages\n");
+        assert_eq!(text(&call(&handler, "empty_trash", json!({"confirm": true})).await), "Trash is already empty.");
+    }
+
+    #[tokio::test]
+    async fn test_empty_spam_needs_only_modify_scope() {
+        let mock = MockGmailClient::sample().with_granted_scopes(&[scopes::MODIFY, scopes::SETTINGS_BASIC]);
+        let spam = mock.add_message("Spammer <spam@example.net>", "me@example.com", "Win big", "Click here");
+        let handler = ToolHandler::new(Arc::new(mock));
+
+        let moved = call(&handler, "modify_email", json!({"messageId": spam, "addLabelIds": ["SPAM"], "removeLabelIds": ["INBOX"]})).await;
+        assert!(!moved.is_error, "{}", text(&moved));
+
+        let emptied = call(&handler, "empty_spam", json!({"confirm": true})).await;
+        assert!(!emptied.is_error, "{}", text(&emptied));
+        assert_eq!(text(&emptied), "Spam emptied.\nMoved to trash: 1 messages\n");
+
+        let trash = call(&handler, "empty_trash", json!({"confirm": true})).await;
+        assert!(trash.is_error);
+        assert!(text(&trash).contains("GMAIL_FULL_ACCESS"), "{}", text(&trash));
+    }
+
+    #[tokio::test]
+    async fn test_preview_filter() {
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample()));
+
+        let preview = call(&handler, "preview_filter", json!({"criteria": {"from": "bob@example.com"}})).await;
+        assert_eq!(
+            text(&preview),
+            "Query: from:bob@example.com\nMatching messages: 1\n\nMost recent 1:\n\n\
+             ID: 0000000000000002\nSubject: Invoice #1042\nFrom: Bob Example <bob@example.com>\n\
+             Date: Thu, 01 Jan 2026 00:01:00 +0000\nReceived: 2026-01-01T00:01:00Z\n"
+        );
+
+        let none = call(&handler, "preview_filter", json!({"criteria": {"subject": "holiday", "hasAttachment": true}})).await;
+        assert_eq!(text(&none), "Query: subject:holiday has:attachment\nMatching messages: 0\n");
+
+        assert!(call(&handler, "preview_filter", json!({"criteria": {}})).await.is_error);
+    }
+
+    #[tokio::test]
+    async fn test_preview_filter_stops_counting() {
+        let mock = MockGmailClient::new();
+        for i in 0..1001 {
+            mock.add_message("Bulk <bulk@example.com>", "me@example.com", &format!("Offer {}", i), "Buy now");
+        }
+        let handler = ToolHandler::new(Arc::new(mock));
+
+        let preview = call(&handler, "preview_filter", json!({"criteria": {"from": "bulk@example.com"}, "maxResults": 1})).await;
+        assert!(
+            text(&preview).starts_with("Query: from:bulk@example.com\nMatching messages: more than 1000 (counting stopped there)\n"),
+            "{}",
+            text(&preview)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_filter_to_existing() {
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample())).with_confirm_threshold(1);
+
+        let created = call(
+            &handler,
+            "create_filter",
+            json!({"criteria": {"from": "bob@example.com"}, "action": {"star": true, "archive": true}}),
+        )
+        .await;
+        assert!(!created.is_error, "{}", text(&created));
+
+        let applied = call(&handler, "apply_filter_to_existing", json!({"filterId": "ANe1Bm4"})).await;
+        assert_eq!(
+            text(&applied),
+            "Query: from:bob@example.com\nMatching messages: 1\nSuccessfully relabeled: 1 messages\n"
+        );
+        let starred = call(&handler, "search_emails", json!({"query": "is:starred -in:inbox"})).await;
+        assert!(text(&starred).starts_with("ID: 0000000000000002\n"), "{}", text(&starred));
+
+        let criteria = json!({"criteria": {"query": "example"}, "addLabelIds": ["IMPORTANT"]});
+        let refused = call(&handler, "apply_filter_to_existing", criteria.clone()).await;
+        assert!(refused.is_error);
+        assert!(text(&refused).contains("This would relabel more than 1 messages"), "{}", text(&refused));
+
+        let mut confirmed = criteria;
+        confirmed["confirm"] = json!(true);
+        let applied = call(&handler, "apply_filter_to_existing", confirmed).await;
+        assert!(text(&applied).ends_with("Successfully relabeled: 3 messages\n"), "{}", text(&applied));
+    }
+
+    #[tokio::test]
+    async fn test_apply_trashing_filter_uses_delete_wording() {
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample())).with_confirm_threshold(0);
+        let created = call(
+            &handler,
+            "create_filter_from_template",
+            json!({"template": "deleteFromSender", "parameters": {"senderEmail": "bob@example.com"}}),
+        )
+        .await;
+        assert!(!created.is_error, "{}", text(&created));
+
+        let refused = call(&handler, "apply_filter_to_existing", json!({"filterId": "ANe1Bm4"})).await;
+        assert!(text(&refused).contains("This would move to trash more than 0 messages"), "{}", text(&refused));
+
+        let applied = call(&handler, "apply_filter_to_existing", json!({"filterId": "ANe1Bm4", "confirm": true})).await;
+        assert_eq!(
+            text(&applied),
+            "Query: from:bob@example.com\nMatching messages: 1\nSuccessfully relabeled: 1 messages\n\
+             Successfully moved to trash: 1 messages\n"
+        );
+        let trashed = call(&handler, "search_emails", json!({"query": "in:trash"})).await;
+        assert!(text(&trashed).starts_with("ID: 0000000000000002\n"), "{}", text(&trashed));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_tools_are_hidden_and_rejected() {
+        let access = ToolAccess::new(None, vec!["delete_email".to_string()]);
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample())).with_tool_access(access);
+
+        assert!(handler.list_tools().iter().all(|tool| tool.name != "delete_email"));
+        let deleted = call(&handler, "delete_email", json!({"messageId": "0000000000000001"})).await;
+        assert!(deleted.is_error);
+        assert_eq!(text(&deleted), "Error: Tool 'delete_email' is disabled on this server (GMAIL_DISABLED_TOOLS)");
+
+        let only_read = ToolAccess::new(Some(vec!["read_email".to_string()]), Vec::new());
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample())).with_tool_access(only_read);
+        let names: Vec<String> = handler.list_tools().into_iter().map(|tool| tool.name).collect();
+        assert_eq!(names, vec!["read_email"]);
+        assert!(call(&handler, "search_emails", json!({"query": "in:inbox"})).await.is_error);
+    }
+
+    #[tokio::test]
+    async fn test_batch_delete_over_threshold_needs_confirm() {
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample())).with_confirm_threshold(2);
+        let ids = json!(["0000000000000001", "0000000000000002", "0000000000000003"]);
+
+        let refused = call(&handler, "batch_delete_emails", json!({"messageIds": ids})).await;
+        assert!(refused.is_error);
+        assert_eq!(
+            text(&refused),
+            "Error: This would move to trash 3 messages, more than the confirmation threshold of 2. \
+             Set confirm: true to proceed."
+        );
+
+        let deleted = call(&handler, "batch_delete_emails", json!({"messageIds": ids, "confirm": true})).await;
+        assert!(text(&deleted).contains("Successfully moved to trash: 3 messages"), "{}", text(&deleted));
+    }
+
+    #[tokio::test]
+    async fn test_permanent_batch_delete_needs_full_access() {
+        let ids = json!({"messageIds": ["0000000000000001"], "permanent": true});
+
+        let modify_only = MockGmailClient::sample().with_granted_scopes(&[scopes::MODIFY, scopes::SETTINGS_BASIC]);
+        let handler = ToolHandler::new(Arc::new(modify_only));
+        let refused = call(&handler, "batch_delete_emails", ids.clone()).await;
+        assert!(refused.is_error);
+        assert!(text(&refused).contains("Set GMAIL_FULL_ACCESS=1"), "{}", text(&refused));
+
+        let full = MockGmailClient::sample().with_granted_scopes(&[scopes::FULL_ACCESS, scopes::SETTINGS_BASIC]);
+        let handler = ToolHandler::new(Arc::new(full));
+        let deleted = call(&handler, "batch_delete_emails", ids).await;
+        assert!(text(&deleted).contains("Successfully permanently deleted: 1 messages"), "{}", text(&deleted));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_hides_write_tools() {
+        let everything = ToolHandler::new(Arc::new(MockGmailClient::sample()));
+        let all: Vec<String> = everything.list_tools().into_iter().map(|tool| tool.name).collect();
+        for name in WRITE_TOOLS {
+            assert!(all.iter().any(|tool| tool == name), "{} is not a tool", name);
+        }
+
+        let handler = ToolHandler::new(Arc::new(MockGmailClient::sample()))
+            .with_tool_access(ToolAccess::default().with_read_only(true));
+        let listed: Vec<String> = handler.list_tools().into_iter().map(|tool| tool.name).collect();
+        assert_eq!(listed.len(), all.len() - WRITE_TOOLS.len());
+        assert!(listed.iter().any(|tool| tool == "download_attachment"));
+
+        let sent = call(&handler, "send_email", json!({"to": ["bob@example.com"], "subject": "Hi", "body": "Hi"})).await;
+        assert!(sent.is_error);
+        assert!(text(&sent).contains("read-only"));
+        assert!(!call(&handler, "search_emails", json!({"query": "in:inbox"})).await.is_error);
+
+        let info = call(&handler, "get_unsubscribe_info", json!({"messageId": "0000000000000003"})).await;
+        assert_eq!(text(&info), "This message has no List-Unsubscribe header.");
+        assert!(call(&handler, "unsubscribe", json!({"messageId": "0000000000000003"})).await.is_error);
+    }
+}